@@ -0,0 +1,3 @@
+-- Argon2id PHC string (~100 karakter) tidak muat di kolom Password lama
+ALTER TABLE [dbo].[AuthUser] ALTER COLUMN [Password] NVARCHAR(255) NULL;
+GO
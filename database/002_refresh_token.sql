@@ -0,0 +1,17 @@
+-- Refresh token opaque (hanya hash yang disimpan), satu family per login
+CREATE TABLE [dbo].[AuthRefreshToken] (
+    [TokenNID]        BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]     INT            NOT NULL,
+    [FamilyID]        NVARCHAR(64)   NOT NULL,
+    [ParentTokenNID]  BIGINT         NULL,
+    [TokenHash]       NVARCHAR(64)   NOT NULL,
+    [IssuedAt]        DATETIME2      NOT NULL,
+    [ExpiresAt]       DATETIME2      NOT NULL,
+    [UsedAt]          DATETIME2      NULL,
+    [RevokedAt]       DATETIME2      NULL,
+    [IpAddress]       NVARCHAR(64)   NULL
+);
+GO
+CREATE UNIQUE INDEX [IX_AuthRefreshToken_TokenHash] ON [dbo].[AuthRefreshToken] ([TokenHash]);
+CREATE INDEX [IX_AuthRefreshToken_FamilyID] ON [dbo].[AuthRefreshToken] ([FamilyID]);
+GO
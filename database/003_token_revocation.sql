@@ -0,0 +1,13 @@
+-- Daftar JWT yang dicabut (per jti) dan cut-off per user (semua token sebelum RevokedBefore)
+CREATE TABLE [dbo].[AuthTokenRevocation] (
+    [RevocationNID]  BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Jti]            NVARCHAR(64)   NULL,
+    [AuthUserNID]    INT            NOT NULL,
+    [RevokedBefore]  DATETIME2      NULL,
+    [ExpiresAt]      DATETIME2      NOT NULL,
+    [RevokedAt]      DATETIME2      NOT NULL,
+    [Reason]         NVARCHAR(100)  NULL
+);
+GO
+CREATE INDEX [IX_AuthTokenRevocation_ExpiresAt] ON [dbo].[AuthTokenRevocation] ([ExpiresAt]);
+GO
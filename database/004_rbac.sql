@@ -0,0 +1,34 @@
+-- Role & permission per AuthUser
+CREATE TABLE [dbo].[AuthRole] (
+    [RoleNID]   INT           NOT NULL PRIMARY KEY,
+    [RoleCode]  NVARCHAR(50)  NOT NULL UNIQUE,
+    [RoleName]  NVARCHAR(100) NOT NULL
+);
+GO
+CREATE TABLE [dbo].[AuthRolePermission] (
+    [RoleNID]     INT           NOT NULL,
+    [Permission]  NVARCHAR(100) NOT NULL,
+    CONSTRAINT [PK_AuthRolePermission] PRIMARY KEY ([RoleNID], [Permission])
+);
+GO
+CREATE TABLE [dbo].[AuthUserRole] (
+    [AuthUserNID]  INT        NOT NULL,
+    [RoleNID]      INT        NOT NULL,
+    [AssignedAt]   DATETIME2  NOT NULL,
+    [AssignedBy]   INT        NULL,
+    CONSTRAINT [PK_AuthUserRole] PRIMARY KEY ([AuthUserNID], [RoleNID])
+);
+GO
+
+INSERT INTO [dbo].[AuthRole] ([RoleNID], [RoleCode], [RoleName]) VALUES
+    (1, 'applicant', 'Applicant'),
+    (2, 'sales', 'Sales'),
+    (3, 'reviewer', 'Reviewer'),
+    (4, 'administrator', 'Administrator');
+GO
+
+INSERT INTO [dbo].[AuthRolePermission] ([RoleNID], [Permission]) VALUES
+    (2, 'admin.access'), (2, 'kyc.read'),
+    (3, 'admin.access'), (3, 'kyc.read'), (3, 'kyc.review'), (3, 'table.read'),
+    (4, 'admin.access'), (4, 'kyc.read'), (4, 'kyc.write'), (4, 'kyc.review'), (4, 'table.read'), (4, 'user.manage');
+GO
@@ -0,0 +1,24 @@
+-- TOTP (RFC 6238) per AuthUser. Secret disimpan terenkripsi (AES-256-GCM dengan nonce acak, format `kid.base64`, kunci dari `DATA_KEYS`)
+CREATE TABLE [dbo].[AuthUserTotp] (
+    [AuthUserNID]     INT            NOT NULL PRIMARY KEY,
+    [Secret]          NVARCHAR(255)  NOT NULL,
+    [Enabled]         BIT            NOT NULL DEFAULT 0,
+    [LastUsedStep]    BIGINT         NULL,
+    [CreatedAt]       DATETIME2      NOT NULL,
+    [VerifiedAt]      DATETIME2      NULL
+);
+GO
+-- Recovery code sekali pakai, hanya hash SHA-256 yang disimpan
+CREATE TABLE [dbo].[AuthRecoveryCode] (
+    [CodeNID]      BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]  INT           NOT NULL,
+    [CodeHash]     NVARCHAR(64)  NOT NULL,
+    [CreatedAt]    DATETIME2     NOT NULL,
+    [UsedAt]       DATETIME2     NULL
+);
+GO
+CREATE INDEX [IX_AuthRecoveryCode_AuthUserNID] ON [dbo].[AuthRecoveryCode] ([AuthUserNID]);
+GO
+-- Status 2FA ikut dibawa saat rotasi refresh token
+ALTER TABLE [dbo].[AuthRefreshToken] ADD [Mfa] BIT NOT NULL DEFAULT 0;
+GO
@@ -0,0 +1,28 @@
+-- Riwayat percobaan login (berhasil & gagal) per email dan IP
+CREATE TABLE [dbo].[AuthLoginAttempt] (
+    [AttemptNID]   BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Email]        NVARCHAR(255)  NULL,
+    [IpAddress]    NVARCHAR(100)  NOT NULL,
+    [Success]      BIT            NOT NULL,
+    [AttemptedAt]  DATETIME2      NOT NULL
+);
+GO
+CREATE INDEX [IX_AuthLoginAttempt_Email] ON [dbo].[AuthLoginAttempt] ([Email], [AttemptedAt]);
+GO
+CREATE INDEX [IX_AuthLoginAttempt_IpAddress] ON [dbo].[AuthLoginAttempt] ([IpAddress], [AttemptedAt]);
+GO
+-- Lockout sementara per email atau per IP, beserta IP & device saat lockout terjadi
+CREATE TABLE [dbo].[AuthLockout] (
+    [LockoutNID]   BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Email]        NVARCHAR(255)  NULL,
+    [IpAddress]    NVARCHAR(100)  NOT NULL,
+    [DeviceInfo]   NVARCHAR(500)  NULL,
+    [Reason]       NVARCHAR(100)  NOT NULL,
+    [LockedAt]     DATETIME2      NOT NULL,
+    [LockedUntil]  DATETIME2      NOT NULL,
+    [UnlockedAt]   DATETIME2      NULL,
+    [UnlockedBy]   INT            NULL
+);
+GO
+CREATE INDEX [IX_AuthLockout_Email] ON [dbo].[AuthLockout] ([Email], [LockedUntil]);
+GO
@@ -0,0 +1,17 @@
+-- Outbox email: dikirim oleh worker di background, retry dengan backoff
+CREATE TABLE [dbo].[MailOutbox] (
+    [MailNID]        BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Recipient]      NVARCHAR(255)  NOT NULL,
+    [Subject]        NVARCHAR(255)  NOT NULL,
+    [Body]           NVARCHAR(MAX)  NOT NULL,
+    [Template]       NVARCHAR(100)  NOT NULL,
+    [Status]         NVARCHAR(20)   NOT NULL, -- pending / sent / failed
+    [Attempts]       INT            NOT NULL DEFAULT 0,
+    [NextAttemptAt]  DATETIME2      NOT NULL,
+    [LastError]      NVARCHAR(1000) NULL,
+    [CreatedAt]      DATETIME2      NOT NULL,
+    [SentAt]         DATETIME2      NULL
+);
+GO
+CREATE INDEX [IX_MailOutbox_Status] ON [dbo].[MailOutbox] ([Status], [NextAttemptAt]);
+GO
@@ -0,0 +1,12 @@
+-- Link aktivasi & reset password disimpan sebagai hash SHA-256 (hex) dan dikosongkan setelah dipakai
+ALTER TABLE [dbo].[AuthUser] ALTER COLUMN [OTPGeneratedLink] NVARCHAR(100) NULL;
+GO
+ALTER TABLE [dbo].[AuthUser] ALTER COLUMN [ResetPasswordKey] NVARCHAR(100) NULL;
+GO
+-- Link lama (plaintext) tidak bisa dicocokkan dengan hash, user perlu minta link baru
+UPDATE [dbo].[AuthUser] SET [OTPGeneratedLink] = NULL WHERE [OTPGeneratedLink] IS NOT NULL AND LEN([OTPGeneratedLink]) <> 64;
+GO
+UPDATE [dbo].[AuthUser] SET [ResetPasswordKey] = NULL, [ResetPasswordFlag] = 0 WHERE [ResetPasswordKey] IS NOT NULL AND LEN([ResetPasswordKey]) <> 64;
+GO
+CREATE INDEX [IX_MailOutbox_Recipient] ON [dbo].[MailOutbox] ([Recipient], [Template], [CreatedAt]);
+GO
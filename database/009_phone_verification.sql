@@ -0,0 +1,17 @@
+-- OTP verifikasi nomor handphone. Hanya hash kode yang disimpan
+CREATE TABLE [dbo].[PhoneVerification] (
+    [VerificationNID]  BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]      INT            NOT NULL,
+    [MobilePhone]      NVARCHAR(50)   NOT NULL,
+    [CodeHash]         NVARCHAR(64)   NOT NULL,
+    [Attempts]         INT            NOT NULL DEFAULT 0,
+    [CreatedAt]        DATETIME2      NOT NULL,
+    [ExpiresAt]        DATETIME2      NOT NULL,
+    [VerifiedAt]       DATETIME2      NULL
+);
+GO
+CREATE INDEX [IX_PhoneVerification_AuthUserNID] ON [dbo].[PhoneVerification] ([AuthUserNID], [CreatedAt]);
+GO
+-- Nomor yang sudah terverifikasi. Jika Handphone berubah, verifikasi dianggap tidak berlaku
+ALTER TABLE [dbo].[AuthUser] ADD [PhoneVerifiedAt] DATETIME2 NULL, [PhoneVerified] NVARCHAR(50) NULL;
+GO
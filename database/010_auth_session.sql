@@ -0,0 +1,17 @@
+-- Satu baris per login. SessionID = FamilyID refresh token, jadi satu sesi = satu family
+CREATE TABLE [dbo].[AuthSession] (
+    [SessionID]      NVARCHAR(64)   NOT NULL PRIMARY KEY,
+    [AuthUserNID]    INT            NOT NULL,
+    [IpAddress]      NVARCHAR(64)   NULL,
+    [UserAgent]      NVARCHAR(512)  NULL,
+    [CreatedAt]      DATETIME2      NOT NULL,
+    [LastSeenAt]     DATETIME2      NOT NULL,
+    [RevokedAt]      DATETIME2      NULL,
+    [RevokedReason]  NVARCHAR(100)  NULL
+);
+GO
+CREATE INDEX [IX_AuthSession_AuthUserNID] ON [dbo].[AuthSession] ([AuthUserNID], [RevokedAt]);
+GO
+-- Revocation per sesi: semua access token dengan claim `sid` ini ditolak
+ALTER TABLE [dbo].[AuthTokenRevocation] ADD [SessionID] NVARCHAR(64) NULL;
+GO
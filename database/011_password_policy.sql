@@ -0,0 +1,15 @@
+-- History hash password per user, dipakai untuk menolak password yang pernah dipakai (PASSWORD_HISTORY_COUNT)
+CREATE TABLE [dbo].[AuthPasswordHistory] (
+    [HistoryNID]    BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT            NOT NULL,
+    [PasswordHash]  NVARCHAR(255)  NOT NULL,
+    [CreatedAt]     DATETIME2      NOT NULL
+);
+GO
+CREATE INDEX [IX_AuthPasswordHistory_AuthUserNID] ON [dbo].[AuthPasswordHistory] ([AuthUserNID], [CreatedAt]);
+GO
+-- Umur password (PASSWORD_MAX_AGE_DAYS) dihitung dari kolom ini
+ALTER TABLE [dbo].[AuthUser] ADD [PasswordChangedAt] DATETIME2 NULL;
+GO
+UPDATE [dbo].[AuthUser] SET [PasswordChangedAt] = [RegisterDate] WHERE [PasswordChangedAt] IS NULL;
+GO
@@ -0,0 +1,18 @@
+-- Riwayat permintaan ganti email. Email baru baru dipakai setelah link konfirmasi (TokenHash) dibuka
+CREATE TABLE [dbo].[AuthEmailChange] (
+    [EmailChangeNID]  BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]     INT            NOT NULL,
+    [OldEmail]        NVARCHAR(255)  NOT NULL,
+    [NewEmail]        NVARCHAR(255)  NOT NULL,
+    [TokenHash]       NVARCHAR(100)  NULL,
+    [IpAddress]       NVARCHAR(64)   NULL,
+    [RequestedAt]     DATETIME2      NOT NULL,
+    [ExpiresAt]       DATETIME2      NOT NULL,
+    [ConfirmedAt]     DATETIME2      NULL,
+    [CancelledAt]     DATETIME2      NULL
+);
+GO
+CREATE INDEX [IX_AuthEmailChange_AuthUserNID] ON [dbo].[AuthEmailChange] ([AuthUserNID], [RequestedAt]);
+GO
+CREATE INDEX [IX_AuthEmailChange_TokenHash] ON [dbo].[AuthEmailChange] ([TokenHash]);
+GO
@@ -0,0 +1,17 @@
+-- Identitas eksternal (OpenID Connect). Sub hanya unik per provider, jadi disimpan berpasangan
+ALTER TABLE [dbo].[AuthUser] ADD [OidcProvider] NVARCHAR(50) NULL;
+GO
+CREATE UNIQUE INDEX [UX_AuthUser_OidcSubject] ON [dbo].[AuthUser] ([OidcProvider], [Sub]) WHERE [OidcProvider] IS NOT NULL;
+GO
+-- State login OIDC yang sedang berjalan: state, nonce & PKCE code_verifier, dipakai sekali saat callback
+CREATE TABLE [dbo].[AuthOidcState] (
+    [State]         NVARCHAR(64)   NOT NULL PRIMARY KEY,
+    [Provider]      NVARCHAR(50)   NOT NULL,
+    [Nonce]         NVARCHAR(64)   NOT NULL,
+    [CodeVerifier]  NVARCHAR(128)  NOT NULL,
+    [IpAddress]     NVARCHAR(64)   NULL,
+    [CreatedAt]     DATETIME2      NOT NULL,
+    [ExpiresAt]     DATETIME2      NOT NULL,
+    [UsedAt]        DATETIME2      NULL
+);
+GO
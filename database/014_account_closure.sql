@@ -0,0 +1,35 @@
+-- Permintaan tutup akun dari user. Status: pending → approved (data pribadi dihapus) / rejected / cancelled
+CREATE TABLE [dbo].[AuthAccountClosure] (
+    [ClosureNID]    BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT             NOT NULL,
+    [Reason]        NVARCHAR(500)   NULL,
+    [Status]        NVARCHAR(20)    NOT NULL,
+    [RequestedAt]   DATETIME2       NOT NULL,
+    [ReviewedBy]    INT             NULL,
+    [ReviewedAt]    DATETIME2       NULL,
+    [ReviewNote]    NVARCHAR(500)   NULL,
+    [CompletedAt]   DATETIME2       NULL
+);
+GO
+-- Satu permintaan pending per user
+CREATE UNIQUE INDEX [UX_AuthAccountClosure_Pending] ON [dbo].[AuthAccountClosure] ([AuthUserNID]) WHERE [Status] = 'pending';
+GO
+CREATE INDEX [IX_AuthAccountClosure_Status] ON [dbo].[AuthAccountClosure] ([Status], [RequestedAt]);
+GO
+-- Baris AuthUser tetap disimpan sebagai catatan retensi, tanpa data pribadi
+ALTER TABLE [dbo].[AuthUser] ADD [ClosedAt] DATETIME2 NULL;
+GO
+-- Audit trail aksi sensitif. Detail berisi JSON tanpa data pribadi
+CREATE TABLE [dbo].[AuditLog] (
+    [AuditNID]     BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Action]       NVARCHAR(100)   NOT NULL,
+    [ActorNID]     INT             NULL,
+    [SubjectNID]   INT             NULL,
+    [Detail]       NVARCHAR(MAX)   NULL,
+    [CreatedAt]    DATETIME2       NOT NULL
+);
+GO
+CREATE INDEX [IX_AuditLog_SubjectNID] ON [dbo].[AuditLog] ([SubjectNID], [CreatedAt]);
+GO
+CREATE INDEX [IX_AuditLog_Action] ON [dbo].[AuditLog] ([Action], [CreatedAt]);
+GO
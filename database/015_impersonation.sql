@@ -0,0 +1,23 @@
+-- Sesi "view as applicant" oleh admin. Jti = access token impersonation, dicabut saat sesi diakhiri
+CREATE TABLE [dbo].[AuthImpersonation] (
+    [ImpersonationNID]  BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AdminNID]          INT             NOT NULL,
+    [AuthUserNID]       INT             NOT NULL,
+    [Jti]               NVARCHAR(64)    NOT NULL,
+    [Reason]            NVARCHAR(500)   NOT NULL,
+    [ReadOnly]          BIT             NOT NULL DEFAULT 1,
+    [IpAddress]         NVARCHAR(64)    NULL,
+    [StartedAt]         DATETIME2       NOT NULL,
+    [ExpiresAt]         DATETIME2       NOT NULL,
+    [EndedAt]           DATETIME2       NULL,
+    [EndedBy]           INT             NULL
+);
+GO
+CREATE INDEX [IX_AuthImpersonation_AdminNID] ON [dbo].[AuthImpersonation] ([AdminNID], [StartedAt]);
+GO
+CREATE INDEX [IX_AuthImpersonation_AuthUserNID] ON [dbo].[AuthImpersonation] ([AuthUserNID], [StartedAt]);
+GO
+
+-- Hanya administrator yang boleh impersonate
+INSERT INTO [dbo].[AuthRolePermission] ([RoleNID], [Permission]) VALUES (4, 'user.impersonate');
+GO
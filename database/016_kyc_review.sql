@@ -0,0 +1,16 @@
+-- Hasil review aplikasi KYC. IsApproved melengkapi IsRejected / IsRevised yang sudah ada
+ALTER TABLE [dbo].[UserKyc] ADD [IsApproved] BIT NOT NULL DEFAULT 0;
+GO
+-- Riwayat keputusan reviewer. Decision: approved / rejected / revision, ReopenedStage diisi untuk revision
+CREATE TABLE [dbo].[KycReviewHistory] (
+    [ReviewNID]       BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]     INT             NOT NULL,
+    [Decision]        NVARCHAR(20)    NOT NULL,
+    [Reason]          NVARCHAR(500)   NOT NULL,
+    [ReopenedStage]   INT             NULL,
+    [ReviewedBy]      INT             NOT NULL,
+    [CreatedAt]       DATETIME2       NOT NULL
+);
+GO
+CREATE INDEX [IX_KycReviewHistory_AuthUserNID] ON [dbo].[KycReviewHistory] ([AuthUserNID], [CreatedAt]);
+GO
@@ -0,0 +1,10 @@
+-- Draft form onboarding: JSON parsial tanpa validasi, satu baris per user & form.
+-- Baru dipindah ke kolom UserKyc saat form di-submit, lalu dihapus
+CREATE TABLE [dbo].[KycDraft] (
+    [AuthUserNID]   INT             NOT NULL,
+    [Step]          NVARCHAR(30)    NOT NULL,
+    [Data]          NVARCHAR(MAX)   NOT NULL,
+    [UpdatedAt]     DATETIME2       NOT NULL,
+    CONSTRAINT [PK_KycDraft] PRIMARY KEY ([AuthUserNID], [Step])
+);
+GO
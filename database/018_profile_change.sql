@@ -0,0 +1,36 @@
+-- Perubahan data nasabah setelah aplikasi disetujui. Status: pending → approved / rejected / cancelled.
+-- UserKyc.ChangeNID = permintaan yang sedang pending (0 = tidak ada),
+-- UserKyc.PendingCIFNID = perubahan terakhir yang sudah disetujui tapi belum dikirim ke CIF (0 = tidak ada)
+CREATE TABLE [dbo].[KycChangeRequest] (
+    [ChangeNID]     INT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT             NOT NULL,
+    [AutoNID]       INT             NOT NULL,
+    [Section]       NVARCHAR(30)    NOT NULL,
+    [Changes]       NVARCHAR(MAX)   NOT NULL,
+    [Previous]      NVARCHAR(MAX)   NOT NULL,
+    [Reason]        NVARCHAR(500)   NULL,
+    [Status]        NVARCHAR(20)    NOT NULL,
+    [RequestedAt]   DATETIME2       NOT NULL,
+    [ReviewedBy]    INT             NULL,
+    [ReviewedAt]    DATETIME2       NULL,
+    [ReviewNote]    NVARCHAR(500)   NULL
+);
+GO
+CREATE INDEX [IX_KycChangeRequest_AuthUserNID] ON [dbo].[KycChangeRequest] ([AuthUserNID], [RequestedAt]);
+GO
+CREATE INDEX [IX_KycChangeRequest_Status] ON [dbo].[KycChangeRequest] ([Status], [RequestedAt]);
+GO
+-- Snapshot lengkap baris UserKyc (JSON) setiap kali perubahan diterapkan.
+-- Versi 1 = data sebelum perubahan pertama, ChangeNID NULL
+CREATE TABLE [dbo].[UserKycVersion] (
+    [VersionNID]    INT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT             NOT NULL,
+    [AutoNID]       INT             NOT NULL,
+    [Version]       INT             NOT NULL,
+    [ChangeNID]     INT             NULL,
+    [Data]          NVARCHAR(MAX)   NOT NULL,
+    [CreatedBy]     INT             NULL,
+    [CreatedAt]     DATETIME2       NOT NULL,
+    CONSTRAINT [UX_UserKycVersion] UNIQUE ([AutoNID], [Version])
+);
+GO
@@ -0,0 +1,21 @@
+-- Batch export aplikasi yang sudah disetujui ke format import CIF back-office.
+-- RecordType: N = nasabah baru (UserKyc.IsImported 0 → 1), U = perubahan data (UserKyc.PendingCIFNID → 0)
+CREATE TABLE [dbo].[CifExportBatch] (
+    [BatchNID]      INT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [FileName]      NVARCHAR(260)   NOT NULL,
+    [Format]        NVARCHAR(20)    NOT NULL,
+    [RecordCount]   INT             NOT NULL,
+    [Checksum]      CHAR(64)        NOT NULL,
+    [CreatedAt]     DATETIME2       NOT NULL
+);
+GO
+CREATE TABLE [dbo].[CifExportItem] (
+    [BatchNID]      INT             NOT NULL,
+    [AutoNID]       INT             NOT NULL,
+    [RecordType]    CHAR(1)         NOT NULL,
+    [ChangeNID]     INT             NULL,
+    CONSTRAINT [PK_CifExportItem] PRIMARY KEY ([BatchNID], [AutoNID])
+);
+GO
+CREATE INDEX [IX_CifExportItem_AutoNID] ON [dbo].[CifExportItem] ([AutoNID]);
+GO
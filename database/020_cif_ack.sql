@@ -0,0 +1,15 @@
+-- Riwayat ingest file acknowledgment dari back-office (CIFID, ClientID & AccountStatus per nasabah).
+-- Detail per baris ada di file report reconciliation (ReportFile)
+CREATE TABLE [dbo].[CifAckImport] (
+    [AckImportNID]  INT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [FileName]      NVARCHAR(260)   NOT NULL,
+    [Checksum]      CHAR(64)        NOT NULL,
+    [Matched]       INT             NOT NULL,
+    [Unmatched]     INT             NOT NULL,
+    [Conflicting]   INT             NOT NULL,
+    [ReportFile]    NVARCHAR(260)   NULL,
+    [CreatedAt]     DATETIME2       NOT NULL
+);
+GO
+CREATE INDEX [IX_CifAckImport_Checksum] ON [dbo].[CifAckImport] ([Checksum]);
+GO
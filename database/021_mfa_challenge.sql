@@ -0,0 +1,12 @@
+-- Token challenge 2FA (`/auth/login/2fa`). Dipakai sekali, percobaan kode dibatasi per challenge
+CREATE TABLE [dbo].[AuthMfaChallenge] (
+    [Jti]           NVARCHAR(64)   NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT            NOT NULL,
+    [Attempts]      INT            NOT NULL DEFAULT 0,
+    [CreatedAt]     DATETIME2      NOT NULL,
+    [ExpiresAt]     DATETIME2      NOT NULL,
+    [UsedAt]        DATETIME2      NULL
+);
+GO
+CREATE INDEX [IX_AuthMfaChallenge_ExpiresAt] ON [dbo].[AuthMfaChallenge] ([ExpiresAt]);
+GO
@@ -0,0 +1,8 @@
+-- Isi email berisi link aktivasi / reset / ganti email (token mentah), jadi disimpan terenkripsi (`DATA_KEYS`)
+-- dan dihapus setelah email selesai diproses (sent / failed)
+ALTER TABLE [dbo].[MailOutbox] ALTER COLUMN [Body] NVARCHAR(MAX) NULL;
+GO
+ALTER TABLE [dbo].[MailOutbox] ADD [BodySealed] BIT NOT NULL DEFAULT 0;
+GO
+UPDATE [dbo].[MailOutbox] SET [Body] = NULL WHERE [Status] IN ('sent', 'failed');
+GO
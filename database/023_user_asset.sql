@@ -0,0 +1,21 @@
+-- Pemilik file upload (path relatif di PATH_ASSET). Kolom file di UserKyc diisi client, jadi hanya path yang
+-- tercatat di sini yang boleh dihapus saat akun ditutup
+CREATE TABLE [dbo].[UserAsset] (
+    [AssetPath]     NVARCHAR(260)   NOT NULL PRIMARY KEY,
+    [AuthUserNID]   INT             NOT NULL,
+    [CreatedAt]     DATETIME2       NOT NULL
+);
+GO
+CREATE INDEX [IX_UserAsset_User] ON [dbo].[UserAsset] ([AuthUserNID]);
+GO
+-- Upload lama: path yang hanya dipakai satu applicant dianggap miliknya. Path yang dipakai lebih dari satu
+-- applicant tidak dicatat (tidak akan dihapus otomatis) dan harus dicek manual
+INSERT INTO [dbo].[UserAsset] ([AssetPath], [AuthUserNID], [CreatedAt])
+SELECT F.AssetPath, MIN(U.AuthUserNID), SYSUTCDATETIME()
+FROM [dbo].[UserKyc] K
+JOIN [dbo].[AuthUser] U ON U.WebCIFNID = K.AutoNID
+CROSS APPLY (VALUES (K.IDCardFile), (K.SelfieFile), (K.SignatureFile), (K.NPWPFile)) F(AssetPath)
+WHERE ISNULL(F.AssetPath, '') <> ''
+GROUP BY F.AssetPath
+HAVING COUNT(DISTINCT U.AuthUserNID) = 1;
+GO
@@ -0,0 +1,13 @@
+-- Riwayat permintaan kirim ulang aktivasi per email & IP, dicatat walaupun email tidak terdaftar
+-- supaya rate limit tidak membocorkan email mana yang ada
+CREATE TABLE [dbo].[ActivationResendRequest] (
+    [RequestNID]   BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY,
+    [Email]        NVARCHAR(255)  NOT NULL,
+    [IpAddress]    NVARCHAR(64)   NOT NULL,
+    [RequestedAt]  DATETIME2      NOT NULL
+);
+GO
+CREATE INDEX [IX_ActivationResendRequest_Email] ON [dbo].[ActivationResendRequest] ([Email], [RequestedAt]);
+GO
+CREATE INDEX [IX_ActivationResendRequest_IpAddress] ON [dbo].[ActivationResendRequest] ([IpAddress], [RequestedAt]);
+GO
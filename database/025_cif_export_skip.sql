@@ -0,0 +1,4 @@
+-- Record yang dilewati export CIF (lookup tidak ketemu, berisi delimiter, melebihi lebar kolom) ditandai
+-- supaya tidak menyumbat batch berikutnya. Dicoba lagi setelah datanya berubah atau setelah CIF_EXPORT_SKIP_RETRY_HOURS
+ALTER TABLE [dbo].[UserKyc] ADD [ExportSkippedAt] DATETIME2 NULL, [ExportSkipReason] NVARCHAR(500) NULL;
+GO
@@ -0,0 +1,106 @@
+"""Mock OpenID Connect provider untuk development & testing login OIDC.
+
+Tanpa halaman login: /authorize langsung redirect balik dengan code untuk user dari env.
+Mendukung discovery, JWKS (RS256), PKCE S256 dan nonce.
+
+    pip install cryptography
+    MOCK_OIDC_EMAIL=user@example.com python3 scripts/mock_oidc.py
+
+Env: MOCK_OIDC_PORT (9400), MOCK_OIDC_SUB, MOCK_OIDC_EMAIL, MOCK_OIDC_EMAIL_VERIFIED (true), MOCK_OIDC_NAME
+"""
+import base64
+import hashlib
+import json
+import os
+import secrets
+import time
+import urllib.parse
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+from cryptography.hazmat.primitives import hashes
+from cryptography.hazmat.primitives.asymmetric import padding, rsa
+
+PORT = int(os.environ.get("MOCK_OIDC_PORT", "9400"))
+ISSUER = f"http://127.0.0.1:{PORT}"
+KEY = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+CODES = {}
+
+
+def b64(data: bytes) -> str:
+    return base64.urlsafe_b64encode(data).rstrip(b"=").decode()
+
+
+def int_bytes(value: int) -> bytes:
+    return value.to_bytes((value.bit_length() + 7) // 8, "big")
+
+
+def sign(claims: dict) -> str:
+    header = b64(json.dumps({"alg": "RS256", "kid": "mock", "typ": "JWT"}).encode())
+    payload = b64(json.dumps(claims).encode())
+    signature = KEY.sign(f"{header}.{payload}".encode(), padding.PKCS1v15(), hashes.SHA256())
+    return f"{header}.{payload}.{b64(signature)}"
+
+
+class Handler(BaseHTTPRequestHandler):
+    def send_json(self, body: dict, status: int = 200):
+        data = json.dumps(body).encode()
+        self.send_response(status)
+        self.send_header("Content-Type", "application/json")
+        self.end_headers()
+        self.wfile.write(data)
+
+    def do_GET(self):
+        url = urllib.parse.urlparse(self.path)
+        query = dict(urllib.parse.parse_qsl(url.query))
+
+        if url.path == "/.well-known/openid-configuration":
+            self.send_json({
+                "issuer": ISSUER,
+                "authorization_endpoint": f"{ISSUER}/authorize",
+                "token_endpoint": f"{ISSUER}/token",
+                "jwks_uri": f"{ISSUER}/jwks",
+                "code_challenge_methods_supported": ["S256"],
+            })
+        elif url.path == "/jwks":
+            public = KEY.public_key().public_numbers()
+            self.send_json({"keys": [{
+                "kty": "RSA", "kid": "mock", "use": "sig", "alg": "RS256",
+                "n": b64(int_bytes(public.n)), "e": b64(int_bytes(public.e)),
+            }]})
+        elif url.path == "/authorize":
+            code = secrets.token_urlsafe(24)
+            CODES[code] = query
+            location = query["redirect_uri"] + "?" + urllib.parse.urlencode({"code": code, "state": query.get("state", "")})
+            self.send_response(302)
+            self.send_header("Location", location)
+            self.end_headers()
+        else:
+            self.send_json({"error": "not_found"}, 404)
+
+    def do_POST(self):
+        form = dict(urllib.parse.parse_qsl(self.rfile.read(int(self.headers["Content-Length"])).decode()))
+        request = CODES.pop(form.get("code"), None)
+
+        if request is None or form.get("redirect_uri") != request.get("redirect_uri"):
+            return self.send_json({"error": "invalid_grant"}, 400)
+        if b64(hashlib.sha256(form.get("code_verifier", "").encode()).digest()) != request.get("code_challenge"):
+            return self.send_json({"error": "invalid_grant", "error_description": "PKCE verification failed"}, 400)
+
+        now = int(time.time())
+        id_token = sign({
+            "iss": ISSUER,
+            "aud": request["client_id"],
+            "sub": os.environ.get("MOCK_OIDC_SUB", "mock-user-1"),
+            "email": os.environ.get("MOCK_OIDC_EMAIL", "mock.user@example.com"),
+            "email_verified": os.environ.get("MOCK_OIDC_EMAIL_VERIFIED", "true") == "true",
+            "name": os.environ.get("MOCK_OIDC_NAME", "Mock User"),
+            "nonce": request.get("nonce"),
+            "iat": now,
+            "exp": now + 300,
+        })
+        self.send_json({"access_token": secrets.token_urlsafe(24), "token_type": "Bearer", "expires_in": 300, "id_token": id_token})
+
+
+if __name__ == "__main__":
+    print(f"Mock OIDC provider on {ISSUER}")
+    HTTPServer(("127.0.0.1", PORT), Handler).serve_forever()
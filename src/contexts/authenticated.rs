@@ -0,0 +1,88 @@
+use std::fmt;
+use actix_identity::IdentityExt;
+use actix_web::{
+    dev::Payload,
+    http::{header, StatusCode},
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+};
+use futures::future::{ready, Ready};
+
+use super::{jwt_session::{validate_jwt, Claims}, model::ActionResult};
+
+/// ❌ Alasan request ditolak karena tidak terautentikasi, semuanya dijawab 401 dengan format yang sama
+#[derive(Debug)]
+pub enum AuthError {
+    /// Tidak ada cookie sesi maupun header `Authorization`
+    Missing,
+    /// Cookie sesi rusak atau header `Authorization` bukan `Bearer <token>`
+    Malformed,
+    /// Token ada tapi ditolak `validate_jwt` (kedaluwarsa, dicabut, tanda tangan salah)
+    Invalid(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Missing => write!(f, "Token not found"),
+            AuthError::Malformed => write!(f, "Invalid token"),
+            AuthError::Invalid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let result: ActionResult<(), String> = ActionResult {
+            message: "Unauthorized".to_string(),
+            error: Some(self.to_string()),
+            ..ActionResult::default()
+        };
+        HttpResponse::Unauthorized().json(result)
+    }
+}
+
+/// 🎫 Token dari header `Authorization: Bearer <token>`, `None` jika header tidak dikirim
+fn bearer_token(req: &HttpRequest) -> Option<Result<String, AuthError>> {
+    let value = req.headers().get(header::AUTHORIZATION)?;
+
+    let token = value.to_str().ok().and_then(|value| {
+        let (scheme, token) = value.trim().split_once(' ')?;
+        let token = token.trim();
+        (scheme.eq_ignore_ascii_case("Bearer") && !token.is_empty()).then(|| token.to_string())
+    });
+
+    Some(token.ok_or(AuthError::Malformed))
+}
+
+/// 🔐 Ambil & validasi access token: header `Bearer` didahulukan (klien mobile / API), lalu cookie sesi (web)
+pub fn resolve_claims(req: &HttpRequest) -> Result<Claims, AuthError> {
+    let token = match bearer_token(req) {
+        Some(token) => token?,
+        None => match req.get_identity() {
+            Ok(identity) => identity.id().map_err(|_| AuthError::Malformed)?,
+            Err(_) => return Err(AuthError::Missing),
+        },
+    };
+
+    validate_jwt(&token).map_err(|err| AuthError::Invalid(err.to_string()))
+}
+
+/// Claims yang sudah divalidasi middleware (`RequirePermission`, guard impersonation) dipakai ulang, selain itu validasi dari token
+pub fn current_claims(req: &HttpRequest) -> Result<Claims, AuthError> {
+    let claims = req.extensions().get::<Claims>().cloned();
+    claims.map(Ok).unwrap_or_else(|| resolve_claims(req))
+}
+
+/// 👤 Extractor user yang sedang login: handler cukup menerima `claims: Claims`
+impl FromRequest for Claims {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(current_claims(req))
+    }
+}
@@ -0,0 +1,118 @@
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+
+use crate::services::{
+    cif_ack_service::{CifAckConfig, CifAckService, ACK_CONFLICTING, ACK_MATCHED, ACK_UNMATCHED},
+    cif_export_service::{CifExportConfig, CifExportService},
+};
+use super::logger::write_log;
+
+const USAGE: &str = r#"Usage: rust-onboarding-client [command]
+
+Tanpa command: jalankan web server.
+
+Commands:
+  export-cif [--dry-run]                Export aplikasi yang sudah disetujui ke file import CIF
+  ingest-cif-ack <file> [--dry-run]     Isi CIFID / ClientID dari file acknowledgment back-office"#;
+
+/// 🖥️ Jalankan command line job sebagai pengganti web server.
+/// `None` = tidak ada command, lanjut start server; `Some(code)` = exit code proses
+pub async fn run(connection: &Pool<ConnectionManager>, args: &[String]) -> Option<i32> {
+    let (command, options) = args.split_first()?;
+    let dry_run = options.iter().any(|option| option == "--dry-run");
+    let positional: Vec<&String> = options.iter().filter(|option| !option.starts_with("--")).collect();
+
+    if let Some(option) = options.iter().find(|option| option.starts_with("--") && option.as_str() != "--dry-run") {
+        eprintln!("Unknown option '{}'\n\n{}", option, USAGE);
+        return Some(2);
+    }
+
+    let code = match (command.as_str(), positional.as_slice()) {
+        ("export-cif", []) => export_cif(connection, dry_run).await,
+        ("ingest-cif-ack", [file]) => ingest_cif_ack(connection, file, dry_run).await,
+        ("help" | "--help" | "-h", _) => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("Invalid command '{}'\n\n{}", args.join(" "), USAGE);
+            2
+        }
+    };
+
+    Some(code)
+}
+
+async fn export_cif(connection: &Pool<ConnectionManager>, dry_run: bool) -> i32 {
+    let config = match CifExportConfig::from_env() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    match CifExportService::export(connection, &config, dry_run).await {
+        Ok(summary) => {
+            let file = match (&summary.file_path, summary.dry_run) {
+                (_, true) => "dry run, no file written".to_string(),
+                (Some(path), false) => path.clone(),
+                (None, false) => "nothing to export".to_string(),
+            };
+            let message = format!(
+                "CIF export: {} new, {} update(s), {} skipped, checksum {} ({})",
+                summary.new_records, summary.update_records, summary.skipped.len(), summary.checksum, file
+            );
+            println!("{}", message);
+            for (auto_nid, reason) in &summary.skipped {
+                println!("  skipped AutoNID {}: {}", auto_nid, reason);
+            }
+            if !summary.dry_run {
+                write_log("INFO", message.as_str());
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("CIF export failed: {}", err);
+            write_log("ERROR", format!("CIF export failed: {}", err).as_str());
+            1
+        }
+    }
+}
+
+async fn ingest_cif_ack(connection: &Pool<ConnectionManager>, file: &str, dry_run: bool) -> i32 {
+    let config = match CifAckConfig::from_env() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    match CifAckService::ingest(connection, &config, file, dry_run).await {
+        Ok(summary) => {
+            let message = format!(
+                "CIF ack {}{}: {} matched, {} unmatched, {} conflicting, report {}",
+                file,
+                if summary.dry_run { " (dry run)" } else { "" },
+                summary.count(ACK_MATCHED),
+                summary.count(ACK_UNMATCHED),
+                summary.count(ACK_CONFLICTING),
+                summary.report_path.as_deref().unwrap_or("-"),
+            );
+            println!("{}", message);
+            for line in summary.lines.iter().filter(|line| line.result != ACK_MATCHED) {
+                println!("  line {} {}: {}", line.line, line.result, line.reason);
+            }
+            if !summary.dry_run {
+                write_log("INFO", message.as_str());
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("CIF ack ingest failed: {}", err);
+            write_log("ERROR", format!("CIF ack ingest failed {}: {}", file, err).as_str());
+            1
+        }
+    }
+}
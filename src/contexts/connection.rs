@@ -2,22 +2,28 @@ use bb8::{Pool, PooledConnection};
 use bb8_tiberius::ConnectionManager;
 use tiberius::Config;
 use tokio::sync::{Mutex, MutexGuard};
-use std::{env, sync::Arc};
+use std::{env, marker::PhantomData, sync::Arc};
+use super::logger::write_log;
 
 pub type DbPool = Pool<ConnectionManager>;
 pub struct Transaction<'a> {
-    pub conn: Arc<Mutex<Option<PooledConnection<'a, ConnectionManager>>>>, // 🔥 Pakai lifetime 'a
+    // Koneksi `'static` (`get_owned`) supaya `Drop` bisa memindahkannya ke task yang mengirim ROLLBACK
+    pub conn: Arc<Mutex<Option<PooledConnection<'static, ConnectionManager>>>>,
     committed: bool,
+    _pool: PhantomData<&'a DbPool>,
 }
 
 impl<'a> Transaction<'a> {
     pub async fn begin(pool: &'a Pool<ConnectionManager>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let mut conn = pool.get().await?; // ✅ Tidak pakai 'static, langsung gunakan 'a
+        let mut conn = pool.get_owned().await?;
+        // Jaga-jaga jika koneksi pool masih membawa transaksi yang tidak pernah ditutup
+        conn.simple_query("IF @@TRANCOUNT > 0 ROLLBACK").await?.into_results().await?;
         conn.simple_query("BEGIN TRANSACTION").await?; // Mulai transaksi
 
         Ok(Self {
             conn: Arc::new(Mutex::new(Some(conn))),
             committed: false,
+            _pool: PhantomData,
         })
     }
 
@@ -29,17 +35,43 @@ impl<'a> Transaction<'a> {
         self.committed = true;
         Ok(())
     }
+
+    /// 🔙 Batalkan transaksi. Dipanggil di jalur gagal supaya ROLLBACK selesai sebelum response dikirim
+    pub async fn rollback(mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn_guard: MutexGuard<Option<PooledConnection<ConnectionManager>>> = self.conn.lock().await;
+        if let Some(mut conn) = conn_guard.take() {
+            conn.simple_query("IF @@TRANCOUNT > 0 ROLLBACK").await?.into_results().await?;
+        }
+        self.committed = true;
+        Ok(())
+    }
 }
 
 impl<'a> Drop for Transaction<'a> {
     fn drop(&mut self) {
-        if !self.committed {
-            if let Ok(mut conn_guard) = self.conn.try_lock() {
-                if let Some(mut conn) = conn_guard.take() {
-                    // Eksekusi rollback langsung tanpa async
-                    let _ = conn.simple_query("ROLLBACK");
-                }
+        if self.committed {
+            return;
+        }
+        let Some(mut conn) = self.conn.try_lock().ok().and_then(|mut conn_guard| conn_guard.take()) else {
+            return;
+        };
+
+        // ⚠️ Drop tidak bisa await: ROLLBACK dikirim dari task terpisah yang memegang koneksinya.
+        // Koneksi baru kembali ke pool setelah ROLLBACK selesai, jadi tidak ada user pool lain yang ikut masuk transaksi ini
+        write_log("WARN", "Transaction dropped without commit or rollback, rolling back");
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let rolled_back = match conn.simple_query("IF @@TRANCOUNT > 0 ROLLBACK").await {
+                        Ok(stream) => stream.into_results().await.map(|_| ()),
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = rolled_back {
+                        write_log("ERROR", format!("Failed to rollback dropped transaction: {:?}", err).as_str());
+                    }
+                });
             }
+            Err(_) => write_log("ERROR", "No runtime to rollback dropped transaction"),
         }
     }
 }
@@ -1,10 +1,13 @@
 use aes::Aes256;
 use ctr::cipher::{KeyIvInit, StreamCipher};
-use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use base64::{engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD}, Engine as _};
+use rand::{rng, RngCore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
 use sha2::{Digest, Sha256}; // Import SHA-256 untuk hashing
+use super::keyring::{data_keyring, DATA_KEY_LENGTH};
 
 type Aes256Ctr = ctr::Ctr64BE<Aes256>; // AES-256 dengan Counter Mode (CTR)
-/// 🔑 Kunci rahasia (HARUS 32-byte untuk AES-256)
+/// 🔑 Kunci rahasia (HARUS 32-byte untuk AES-256). Hanya untuk hash password lama & membaca data `seal_text` format lama
 const SECRET_KEY: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
 
 /// 📌 **Fungsi untuk membuat IV dari hash plaintext**
@@ -47,4 +50,78 @@ pub fn decrypt_text(encrypted_text: &str, original_text: &str) -> String {
     cipher.apply_keystream(&mut decrypted_data);
 
     String::from_utf8(decrypted_data).expect("Invalid UTF-8")
-}
\ No newline at end of file
+}
+/// #️⃣ Hash SHA-256 (hex) untuk token opaque yang disimpan di database
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 🔐 Enkripsi data rahasia yang perlu dibaca ulang (mis. secret TOTP) dengan AES-256-GCM.
+/// Kunci dari `DATA_KEYS` (bukan `SECRET_KEY`), nonce acak. Format: `kid.base64(nonce || ciphertext || tag)`
+pub fn seal_text(plain_text: &str) -> Result<String, String> {
+    let (kid, key) = data_keyring().active();
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rng().fill_bytes(&mut nonce);
+
+    let mut data = plain_text.as_bytes().to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(kid.as_bytes()), &mut data)
+        .map_err(|_| "Failed to seal text".to_string())?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(data);
+    Ok(format!("{}.{}", kid, URL_SAFE_NO_PAD.encode(sealed)))
+}
+
+/// 🔓 Kebalikan dari `seal_text`. Ciphertext yang diubah / kunci salah ditolak (tag GCM).
+/// Data lama (AES-256-CTR dengan `SECRET_KEY`) masih bisa dibuka, simpan ulang lewat `seal_text` (lihat `needs_reseal`)
+pub fn open_text(sealed_text: &str) -> Result<String, String> {
+    let Some((kid, encoded)) = sealed_text.split_once('.') else {
+        return open_legacy_text(sealed_text);
+    };
+
+    let key = data_keyring().get(kid).ok_or_else(|| format!("Unknown data key '{}'", kid))?;
+    let sealed = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| format!("Invalid sealed text: {}", e))?;
+    if sealed.len() < NONCE_LEN {
+        return Err("Invalid sealed text".to_string());
+    }
+
+    let (nonce, data) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| "Invalid sealed text".to_string())?;
+    let mut data = data.to_vec();
+    let plain = aead_key(key)?
+        .open_in_place(nonce, Aad::from(kid.as_bytes()), &mut data)
+        .map_err(|_| "Invalid sealed text".to_string())?;
+
+    String::from_utf8(plain.to_vec()).map_err(|e| format!("Invalid sealed text: {}", e))
+}
+
+/// 🔁 `true` jika data dienkripsi dengan format lama atau kunci yang bukan `DATA_ACTIVE_KID` (rotasi)
+pub fn needs_reseal(sealed_text: &str) -> bool {
+    match sealed_text.split_once('.') {
+        Some((kid, _)) => kid != data_keyring().active().0,
+        None => true,
+    }
+}
+
+fn aead_key(key: &[u8; DATA_KEY_LENGTH]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key).map(LessSafeKey::new).map_err(|_| "Invalid data key".to_string())
+}
+
+/// Format lama `seal_text`: IV acak 16 byte + AES-256-CTR dengan `SECRET_KEY`, tanpa autentikasi
+fn open_legacy_text(sealed_text: &str) -> Result<String, String> {
+    let sealed = URL_SAFE.decode(sealed_text).map_err(|e| format!("Invalid sealed text: {}", e))?;
+    if sealed.len() < 16 {
+        return Err("Invalid sealed text".to_string());
+    }
+
+    let (iv, data) = sealed.split_at(16);
+    let mut data = data.to_vec();
+    Aes256Ctr::new(SECRET_KEY.into(), iv.into()).apply_keystream(&mut data);
+
+    String::from_utf8(data).map_err(|e| format!("Invalid sealed text: {}", e))
+}
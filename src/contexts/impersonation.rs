@@ -0,0 +1,80 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header::{HeaderName, HeaderValue}, Method},
+    middleware::Next,
+    rt, web, Error, HttpMessage, HttpResponse,
+};
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+
+use crate::services::impersonation_service::ImpersonationService;
+use super::{authenticated::resolve_claims, jwt_session::{Claims, Impersonator}, model::ActionResult};
+
+/// Header penanda di setiap response impersonation, berisi `auth_usernid` admin
+pub const IMPERSONATED_BY_HEADER: &str = "x-impersonated-by";
+
+/// Route yang tetap ditolak walau impersonation diberi akses tulis: data login & penutupan akun hanya boleh diubah pemiliknya
+const OWNER_ONLY_PREFIXES: [&str; 2] = ["/v1/auth/", "/v1/user/close-account"];
+
+fn denied(message: &str) -> HttpResponse {
+    let result: ActionResult<(), String> = ActionResult {
+        message: "Forbidden".to_string(),
+        error: Some(message.to_string()),
+        ..ActionResult::default()
+    };
+    HttpResponse::Forbidden().json(result)
+}
+
+fn check_access(req: &ServiceRequest, impersonator: &Impersonator) -> Result<(), HttpResponse> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+    if impersonator.read_only {
+        return Err(denied("Impersonation session is read-only"));
+    }
+    if OWNER_ONLY_PREFIXES.iter().any(|prefix| req.path().starts_with(prefix)) {
+        return Err(denied("Not available while impersonating"));
+    }
+    Ok(())
+}
+
+/// 🕵️ Middleware global (pasang di dalam `IdentityMiddleware`): request dengan token impersonation dibatasi
+/// sesuai mode read-only, diberi header `X-Impersonated-By` dan dicatat satu per satu di `AuditLog`.
+/// Claims yang valid disimpan di extensions supaya extractor & `RequirePermission` tidak validasi ulang
+pub async fn impersonation_guard(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let claims: Option<Claims> = resolve_claims(req.request()).ok();
+    if let Some(claims) = claims.clone() {
+        req.extensions_mut().insert(claims);
+    }
+
+    let Some((impersonator, subject)) = claims.and_then(|claims| claims.impersonator.map(|impersonator| (impersonator, claims.auth_usernid))) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let pool = req.app_data::<web::Data<Pool<ConnectionManager>>>().cloned();
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+
+    let mut response = match check_access(&req, &impersonator) {
+        Ok(()) => next.call(req).await?.map_into_left_body(),
+        Err(denied) => {
+            let (request, _) = req.into_parts();
+            ServiceResponse::new(request, denied).map_into_right_body()
+        }
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&impersonator.auth_usernid.to_string()) {
+        response.headers_mut().insert(HeaderName::from_static(IMPERSONATED_BY_HEADER), value);
+    }
+
+    // Dicatat di background supaya tidak menambah waktu response
+    if let Some(pool) = pool {
+        let status = response.status().as_u16();
+        rt::spawn(async move {
+            ImpersonationService::log_request(pool, &impersonator, subject, &method, &path, status).await;
+        });
+    }
+
+    Ok(response)
+}
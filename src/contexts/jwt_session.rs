@@ -1,11 +1,11 @@
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use std::env;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Duration, Utc};
-use super::model::WebUser;
+use uuid::Uuid;
+use crate::services::revocation_service::RevocationService;
+use super::{keyring::jwt_keyring, model::WebUser};
 
-const SECRET_KEY: &[u8] = b"supersecretkey"; // 🔥 Ganti dengan key yang lebih aman!
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub result: bool,
     pub auth_usernid: i32,
@@ -16,12 +16,69 @@ pub struct Claims {
     pub expired_date: String,
     pub register_date: DateTime<Utc>,
     pub exp: usize,
+    pub iat: usize,
+    /// Waktu terbit dalam milidetik, supaya cut-off `revoke_user` tidak ikut menerima token yang terbit
+    /// lebih awal di detik yang sama. 0 untuk token lama
+    #[serde(default)]
+    pub iat_ms: i64,
+    pub jti: String,
     pub picture: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub mfa: bool,
+    /// ID sesi login (`AuthSession`), dipakai untuk revoke per device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Diisi jika token ini diterbitkan untuk admin yang sedang "view as applicant"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<Impersonator>,
+}
+
+/// 🕵️ Admin di balik token impersonation, `auth_usernid` di `Claims` tetap milik applicant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Impersonator {
+    /// `AuthImpersonation.ImpersonationNID`
+    pub id: i64,
+    pub auth_usernid: i32,
+    pub email: String,
+    /// Default `true`: hanya GET yang diizinkan
+    pub read_only: bool,
+}
+
+/// 🔐 Token sementara antara langkah password dan langkah TOTP, tidak bisa dipakai sebagai access token.
+/// `jti` dicatat di `AuthMfaChallenge` supaya token hanya bisa dipakai sekali dengan percobaan terbatas
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaChallengeClaims {
+    pub auth_usernid: i32,
+    /// Email login, dipakai untuk hitungan gagal di `LoginGuardService`
+    pub email: String,
+    pub purpose: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
+}
+
+const MFA_PURPOSE: &str = "mfa";
+
+/// ⏳ Masa berlaku access token (default 15 menit), perpanjang lewat `/auth/refresh`
+pub fn access_token_ttl() -> Duration {
+    let minutes: i64 = env::var("JWT_ACCESS_TTL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(15);
+    Duration::minutes(minutes)
+}
+
+/// ⏳ Masa berlaku token challenge 2FA (default 5 menit)
+pub fn mfa_challenge_ttl() -> Duration {
+    let minutes: i64 = env::var("MFA_CHALLENGE_TTL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+    Duration::minutes(minutes)
 }
 
 impl Claims {
     pub fn new(user: WebUser) -> Self {
-        let expired_token = Utc::now() + Duration::days(7); // Token berlaku 7 hari
+        let now = Utc::now();
+        let expired_token = now + access_token_ttl();
         let expired_date = expired_token.format("%Y-%m-%d %H:%M:%S").to_string();
         let exp = expired_token.timestamp() as usize; // ⏳ Set exp untuk validasi JWT
 
@@ -36,6 +93,14 @@ impl Claims {
             picture: user.picture,
             register_date: user.register_date,
             exp, // 🔥 Tambahkan ke struct
+            iat: now.timestamp() as usize,
+            iat_ms: now.timestamp_millis(),
+            jti: Uuid::new_v4().to_string(), // 🔥 ID unik untuk revocation
+            roles: user.roles,
+            permissions: user.permissions,
+            mfa: user.mfa,
+            sid: user.session_id,
+            impersonator: None,
         }
     }
 }
@@ -43,23 +108,42 @@ impl Claims {
 // 🔥 Generate JWT Token
 pub fn create_jwt(user: WebUser) -> Result<String, jsonwebtoken::errors::Error> {
     let claims = Claims::new(user); // 🔥 Clone user di sini
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(SECRET_KEY),
-    )?;
+    let token = jwt_keyring().sign(&claims)?; // 🔑 Kunci aktif + header `kid`
     Ok(token)
 }
 
+// 🔐 Generate token challenge 2FA setelah password benar, return (token, claims)
+pub fn create_mfa_token(auth_usernid: i32, email: &str) -> Result<(String, MfaChallengeClaims), jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = MfaChallengeClaims {
+        auth_usernid,
+        email: email.to_string(),
+        purpose: MFA_PURPOSE.to_string(),
+        exp: (now + mfa_challenge_ttl()).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        jti: Uuid::new_v4().to_string(),
+    };
+    let token = jwt_keyring().sign(&claims)?;
+    Ok((token, claims))
+}
+
+// 🔐 Validate token challenge 2FA (signature, exp & purpose). Status pakai-nya dicek di `TwoFactorService`
+pub fn validate_mfa_token(token: &str) -> Result<MfaChallengeClaims, jsonwebtoken::errors::Error> {
+    let claims: MfaChallengeClaims = jwt_keyring().verify(token)?;
+
+    if claims.purpose != MFA_PURPOSE {
+        return Err(jsonwebtoken::errors::Error::from(
+            jsonwebtoken::errors::ErrorKind::InvalidToken,
+        ));
+    }
+
+    Ok(claims)
+}
+
 // 🔥 Validate JWT Token
 pub fn validate_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    match decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(SECRET_KEY),
-        &Validation::new(Algorithm::HS256),
-    ) {
-        Ok(token_data) => {
-            let claims = token_data.claims;
+    match jwt_keyring().verify::<Claims>(token) {
+        Ok(claims) => {
             let now = Utc::now().timestamp() as usize;
 
             if claims.exp < now {
@@ -68,6 +152,13 @@ pub fn validate_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error>
                 ));
             }
 
+            // 🚫 Token sudah dicabut (logout, ganti password, force sign-out)
+            if RevocationService::is_revoked(&claims) {
+                return Err(jsonwebtoken::errors::Error::from(
+                    jsonwebtoken::errors::ErrorKind::InvalidToken,
+                ));
+            }
+
             Ok(claims)
         }
         Err(err) => {
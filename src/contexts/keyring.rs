@@ -0,0 +1,301 @@
+use std::{env, fs};
+use actix_web::cookie::Key;
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine as _};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error, ErrorKind},
+    jwk::{AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters},
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{rsa::{KeyPair as RsaKeyPair, PublicKeyComponents}, signature::{Ed25519KeyPair, KeyPair}};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use super::logger::write_log;
+
+/// Panjang minimum secret HS256 (byte)
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// 🔑 Satu kunci penandatangan JWT, dikenali lewat header `kid`
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    /// Public key untuk JWKS, `None` untuk HS256 (secret tidak boleh dipublikasikan)
+    jwk: Option<Jwk>,
+}
+
+/// 🗝️ Kumpulan kunci aktif. Token ditandatangani dengan `active`, diverifikasi dengan kunci mana pun di `keys`
+pub struct JwtKeyring {
+    active: String,
+    keys: Vec<SigningKey>,
+}
+
+static JWT_KEYRING: Lazy<JwtKeyring> = Lazy::new(|| {
+    JwtKeyring::from_env().unwrap_or_else(|err| panic!("Invalid JWT key configuration: {}", err))
+});
+
+/// Akses keyring global (dimuat sekali dari `.env`)
+pub fn jwt_keyring() -> &'static JwtKeyring {
+    &JWT_KEYRING
+}
+
+/// Panjang kunci enkripsi data (AES-256-GCM)
+pub const DATA_KEY_LENGTH: usize = 32;
+
+/// 🔒 Kunci enkripsi data rahasia di database (secret TOTP, isi outbox email), dikenali lewat `kid` di ciphertext
+pub struct DataKeyring {
+    active: String,
+    keys: Vec<(String, [u8; DATA_KEY_LENGTH])>,
+}
+
+static DATA_KEYRING: Lazy<DataKeyring> = Lazy::new(|| {
+    DataKeyring::from_env().unwrap_or_else(|err| panic!("Invalid data key configuration: {}", err))
+});
+
+/// Akses kunci enkripsi data global (dimuat sekali dari `.env`)
+pub fn data_keyring() -> &'static DataKeyring {
+    &DATA_KEYRING
+}
+
+/// 🚀 Muat & validasi semua kunci saat startup supaya salah konfigurasi langsung ketahuan
+pub fn init() -> Result<Key, String> {
+    Lazy::force(&JWT_KEYRING);
+    Lazy::force(&DATA_KEYRING);
+    session_key_from_env()
+}
+
+impl DataKeyring {
+
+    /// Sumber kunci: `DATA_KEYS_FILE` (satu entry per baris, baris `#` diabaikan) atau `DATA_KEYS` (dipisah koma).
+    /// Format entry `kid:base64` (32 byte, mis. `openssl rand -base64 32`), `DATA_ACTIVE_KID` default kunci pertama.
+    /// Tanpa konfigurasi server gagal start, kecuali `DATA_KEYS_EPHEMERAL=true` (hanya development): dibuat kunci acak
+    /// dan data terenkripsi (secret TOTP, email di outbox) tidak terbaca lagi setelah restart.
+    pub fn from_env() -> Result<Self, String> {
+        let entries: Vec<String> = match (env::var("DATA_KEYS_FILE"), env::var("DATA_KEYS")) {
+            (Ok(path), _) => fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read DATA_KEYS_FILE {}: {}", path, e))?
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            (_, Ok(keys)) => keys.split(',').map(str::to_string).collect(),
+            _ if env::var("DATA_KEYS_EPHEMERAL").is_ok_and(|value| value.eq_ignore_ascii_case("true")) => {
+                write_log("WARN", "DATA_KEYS not configured, using an ephemeral data encryption key (DATA_KEYS_EPHEMERAL)");
+                vec![format!("ephemeral:{}", random_base64(DATA_KEY_LENGTH))]
+            }
+            _ => return Err("DATA_KEYS or DATA_KEYS_FILE must be configured (DATA_KEYS_EPHEMERAL=true for development only)".to_string()),
+        };
+
+        let mut keys: Vec<(String, [u8; DATA_KEY_LENGTH])> = Vec::new();
+        for entry in entries.iter().map(|entry| entry.trim()).filter(|entry| !entry.is_empty() && !entry.starts_with('#')) {
+            let (kid, value) = entry.split_once(':').ok_or_else(|| "Data key entry must be in 'kid:base64' format".to_string())?;
+            let kid = kid.trim();
+
+            // `kid` ditulis di depan ciphertext dengan pemisah '.'
+            if kid.is_empty() || kid.contains('.') || keys.iter().any(|(existing, _)| existing == kid) {
+                return Err(format!("Empty, invalid or duplicate data key id '{}'", kid));
+            }
+
+            let bytes = STANDARD.decode(value.trim()).map_err(|e| format!("Data key '{}' must be base64: {}", kid, e))?;
+            let key: [u8; DATA_KEY_LENGTH] = bytes.try_into()
+                .map_err(|_| format!("Data key '{}' must decode to exactly {} bytes", kid, DATA_KEY_LENGTH))?;
+            keys.push((kid.to_string(), key));
+        }
+
+        let active = match env::var("DATA_ACTIVE_KID") {
+            Ok(kid) => kid,
+            Err(_) => keys.first().map(|(kid, _)| kid.clone()).ok_or_else(|| "No data key configured".to_string())?,
+        };
+        if !keys.iter().any(|(kid, _)| *kid == active) {
+            return Err(format!("DATA_ACTIVE_KID '{}' not found in configured keys", active));
+        }
+
+        Ok(Self { active, keys })
+    }
+
+    /// Kunci untuk enkripsi baru: (kid, key)
+    pub fn active(&self) -> (&str, &[u8; DATA_KEY_LENGTH]) {
+        self.keys.iter()
+            .find(|(kid, _)| *kid == self.active)
+            .map(|(kid, key)| (kid.as_str(), key))
+            .expect("active key is validated on load")
+    }
+
+    /// Kunci untuk dekripsi sesuai `kid` di ciphertext (aktif maupun lama)
+    pub fn get(&self, kid: &str) -> Option<&[u8; DATA_KEY_LENGTH]> {
+        self.keys.iter().find(|(existing, _)| existing == kid).map(|(_, key)| key)
+    }
+}
+
+impl JwtKeyring {
+
+    /// Sumber kunci (urutan prioritas):
+    /// - `JWT_KEYS_FILE`: satu entry per baris, baris `#` diabaikan
+    /// - `JWT_KEYS`: entry dipisah koma
+    ///
+    /// Format entry: `kid:secret` (HS256), `kid:RS256:/path/private.pem` atau `kid:EdDSA:/path/private.pem`.
+    ///
+    /// `JWT_ACTIVE_KID` memilih kunci untuk tanda tangan, default kunci pertama.
+    /// Tanpa konfigurasi dibuat kunci acak (hanya untuk development, token tidak berlaku setelah restart).
+    pub fn from_env() -> Result<Self, String> {
+        let entries: Vec<String> = match (env::var("JWT_KEYS_FILE"), env::var("JWT_KEYS")) {
+            (Ok(path), _) => fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read JWT_KEYS_FILE {}: {}", path, e))?
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            (_, Ok(keys)) => keys.split(',').map(str::to_string).collect(),
+            _ => {
+                write_log("WARN", "JWT_KEYS not configured, using an ephemeral signing key");
+                vec![format!("ephemeral:{}", random_base64(48))]
+            }
+        };
+
+        let mut keys: Vec<SigningKey> = Vec::new();
+        for entry in entries.iter().map(|entry| entry.trim()).filter(|entry| !entry.is_empty() && !entry.starts_with('#')) {
+            let (kid, value) = entry.split_once(':').ok_or_else(|| "JWT key entry must be in 'kid:secret' format".to_string())?;
+            let kid = kid.trim();
+
+            if kid.is_empty() || keys.iter().any(|key| key.kid == kid) {
+                return Err(format!("Empty or duplicate JWT key id '{}'", kid));
+            }
+
+            let key = match value.split_once(':') {
+                Some(("RS256", path)) => SigningKey::rsa(kid, path.trim())?,
+                Some(("EdDSA", path)) => SigningKey::ed25519(kid, path.trim())?,
+                _ => SigningKey::hmac(kid, value.trim())?,
+            };
+            keys.push(key);
+        }
+
+        let active = match env::var("JWT_ACTIVE_KID") {
+            Ok(kid) => kid,
+            Err(_) => keys.first().map(|key| key.kid.clone()).ok_or_else(|| "No JWT key configured".to_string())?,
+        };
+        if !keys.iter().any(|key| key.kid == active) {
+            return Err(format!("JWT_ACTIVE_KID '{}' not found in configured keys", active));
+        }
+
+        Ok(Self { active, keys })
+    }
+
+    fn active_key(&self) -> &SigningKey {
+        self.keys.iter().find(|key| key.kid == self.active).expect("active key is validated on load")
+    }
+
+    /// ✍️ Tanda tangani claims dengan kunci aktif, `kid` ikut di header
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, Error> {
+        let key = self.active_key();
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        encode(&header, claims, &key.encoding)
+    }
+
+    /// 🌐 Public key semua kunci asimetris (aktif maupun lama) untuk `/.well-known/jwks.json`
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect() }
+    }
+
+    /// ✅ Verifikasi token dengan kunci sesuai `kid`. Token tanpa `kid` / `kid` tidak dikenal ditolak
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T, Error> {
+        let kid = decode_header(token)?.kid.ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+        let key = self.keys.iter().find(|key| key.kid == kid).ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+
+        decode::<T>(token, &key.decoding, &Validation::new(key.algorithm)).map(|data| data.claims)
+    }
+}
+
+impl SigningKey {
+    fn hmac(kid: &str, secret: &str) -> Result<Self, String> {
+        if secret.len() < MIN_SECRET_LENGTH {
+            return Err(format!("JWT key '{}' must be at least {} bytes", kid, MIN_SECRET_LENGTH));
+        }
+
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::HS256,
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            jwk: None,
+        })
+    }
+
+    /// 🔐 RS256 dari private key PEM (PKCS#8 `PRIVATE KEY` atau PKCS#1 `RSA PRIVATE KEY`)
+    fn rsa(kid: &str, path: &str) -> Result<Self, String> {
+        let (pem_bytes, der) = read_private_pem(kid, path)?;
+        let key_pair = match der.tag() {
+            "RSA PRIVATE KEY" => RsaKeyPair::from_der(der.contents()),
+            _ => RsaKeyPair::from_pkcs8(der.contents()),
+        }.map_err(|e| format!("JWT key '{}' is not a valid RSA private key: {}", kid, e))?;
+
+        let public: PublicKeyComponents<Vec<u8>> = key_pair.public().into();
+        let (n, e) = (URL_SAFE_NO_PAD.encode(&public.n), URL_SAFE_NO_PAD.encode(&public.e));
+
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::RS256,
+            encoding: EncodingKey::from_rsa_pem(&pem_bytes).map_err(|e| format!("JWT key '{}': {}", kid, e))?,
+            decoding: DecodingKey::from_rsa_components(&n, &e).map_err(|e| format!("JWT key '{}': {}", kid, e))?,
+            jwk: Some(Jwk {
+                common: public_jwk_params(kid, KeyAlgorithm::RS256),
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters { n, e, ..Default::default() }),
+            }),
+        })
+    }
+
+    /// 🔐 EdDSA (Ed25519) dari private key PEM PKCS#8, mis. `openssl genpkey -algorithm ed25519`
+    fn ed25519(kid: &str, path: &str) -> Result<Self, String> {
+        let (pem_bytes, der) = read_private_pem(kid, path)?;
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der.contents())
+            .map_err(|e| format!("JWT key '{}' is not a valid Ed25519 private key: {}", kid, e))?;
+        let x = URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref());
+
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            encoding: EncodingKey::from_ed_pem(&pem_bytes).map_err(|e| format!("JWT key '{}': {}", kid, e))?,
+            decoding: DecodingKey::from_ed_components(&x).map_err(|e| format!("JWT key '{}': {}", kid, e))?,
+            jwk: Some(Jwk {
+                common: public_jwk_params(kid, KeyAlgorithm::EdDSA),
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters { curve: EllipticCurve::Ed25519, x, ..Default::default() }),
+            }),
+        })
+    }
+}
+
+fn read_private_pem(kid: &str, path: &str) -> Result<(Vec<u8>, pem::Pem), String> {
+    let pem_bytes = fs::read(path).map_err(|e| format!("Failed to read JWT key '{}' from {}: {}", kid, path, e))?;
+    let der = pem::parse(&pem_bytes).map_err(|e| format!("JWT key '{}' is not a valid PEM: {}", kid, e))?;
+    Ok((pem_bytes, der))
+}
+
+fn public_jwk_params(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+/// 🍪 Kunci cookie session (64 byte, base64) dari `SESSION_KEY_FILE` atau `SESSION_KEY`.
+/// Harus sama di semua instance; tanpa konfigurasi dibuat acak (session hilang setiap restart).
+fn session_key_from_env() -> Result<Key, String> {
+    let encoded = match (env::var("SESSION_KEY_FILE"), env::var("SESSION_KEY")) {
+        (Ok(path), _) => fs::read_to_string(&path).map_err(|e| format!("Failed to read SESSION_KEY_FILE {}: {}", path, e))?,
+        (_, Ok(key)) => key,
+        _ => {
+            write_log("WARN", "SESSION_KEY not configured, using an ephemeral cookie key");
+            return Ok(Key::generate());
+        }
+    };
+
+    let bytes = STANDARD.decode(encoded.trim()).map_err(|e| format!("SESSION_KEY must be base64: {}", e))?;
+    Key::try_from(bytes.as_slice()).map_err(|_| "SESSION_KEY must decode to at least 64 bytes".to_string())
+}
+
+fn random_base64(length: usize) -> String {
+    let mut bytes = vec![0u8; length];
+    rand::RngCore::fill_bytes(&mut rand::rng(), &mut bytes);
+    STANDARD.encode(bytes)
+}
@@ -0,0 +1,102 @@
+use std::{env, fs, path::PathBuf};
+use chrono::Local;
+use futures::future::BoxFuture;
+use lettre::{
+    message::header::ContentType,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+/// ✉️ Email yang sudah dirender dan siap dikirim
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+}
+
+/// 🔌 Transport pengiriman email. Dipilih lewat `MAIL_TRANSPORT` (`smtp` / `file`)
+pub trait MailTransport: Send + Sync {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Buat transport sesuai `.env`, default `file` supaya development tidak butuh SMTP
+pub fn transport_from_env() -> Result<Box<dyn MailTransport>, String> {
+    match env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "file".to_string()).to_lowercase().as_str() {
+        "smtp" => Ok(Box::new(SmtpMailTransport::from_env()?)),
+        "file" => Ok(Box::new(FileMailTransport::from_env())),
+        other => Err(format!("Unknown MAIL_TRANSPORT '{}'", other)),
+    }
+}
+
+/// 📮 Kirim lewat SMTP (lettre)
+pub struct SmtpMailTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailTransport {
+    pub fn from_env() -> Result<Self, String> {
+        let host = env::var("MAIL_SMTP_HOST").map_err(|_| "MAIL_SMTP_HOST harus diatur".to_string())?;
+        let port: u16 = env::var("MAIL_SMTP_PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(587);
+        let from = env::var("MAIL_FROM").map_err(|_| "MAIL_FROM harus diatur".to_string())?;
+
+        // `starttls` (default), `tls` (port 465) atau `none` (mail catcher lokal)
+        let builder = match env::var("MAIL_SMTP_TLS").unwrap_or_else(|_| "starttls".to_string()).to_lowercase().as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host).map_err(|e| format!("Invalid SMTP host: {}", e))?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host).map_err(|e| format!("Invalid SMTP host: {}", e))?,
+        };
+
+        let builder = match (env::var("MAIL_SMTP_USERNAME"), env::var("MAIL_SMTP_PASSWORD")) {
+            (Ok(username), Ok(password)) => builder.credentials(Credentials::new(username, password)),
+            _ => builder,
+        };
+
+        Ok(Self { transport: builder.port(port).build(), from })
+    }
+}
+
+impl MailTransport for SmtpMailTransport {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let email = Message::builder()
+                .from(self.from.parse().map_err(|e| format!("Invalid MAIL_FROM: {}", e))?)
+                .to(message.to.parse().map_err(|e| format!("Invalid recipient {}: {}", message.to, e))?)
+                .subject(message.subject.clone())
+                .header(ContentType::TEXT_HTML)
+                .body(message.html.clone())
+                .map_err(|e| format!("Failed to build email: {}", e))?;
+
+            self.transport.send(email).await.map(|_| ()).map_err(|e| format!("SMTP error: {}", e))
+        })
+    }
+}
+
+/// 📁 Simpan email sebagai file HTML (development / testing manual)
+pub struct FileMailTransport {
+    dir: PathBuf,
+}
+
+impl FileMailTransport {
+    pub fn from_env() -> Self {
+        Self { dir: PathBuf::from(env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "./mail".to_string())) }
+    }
+}
+
+impl MailTransport for FileMailTransport {
+    fn send<'a>(&'a self, message: &'a MailMessage) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create mail dir: {}", e))?;
+
+            let file_name = format!(
+                "{}-{}.html",
+                Local::now().format("%Y%m%d%H%M%S%3f"),
+                message.to.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            );
+            let content = format!("<!-- To: {} -->\n<!-- Subject: {} -->\n{}", message.to, message.subject, message.html);
+
+            fs::write(self.dir.join(file_name), content).map_err(|e| format!("Failed to write mail file: {}", e))
+        })
+    }
+}
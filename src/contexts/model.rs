@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 use validator::Validate;
+use super::onboarding::KycStage;
 use crate::services::validation_service::validator::{
     required, valid_phone_number, valid_name, valid_number_card, required_int,
     required_datetime, validate_base64_image, valid_password
@@ -19,7 +21,8 @@ pub struct LoginRequest {
     #[validate(required, email(message = "Invalid email format"))]
     pub email: Option<String>,
 
-    #[validate(custom(function = "required"), custom(function = "valid_password"))]
+    // Policy tidak dicek saat login, password lama yang belum memenuhi policy tetap bisa masuk
+    #[validate(custom(function = "required"))]
     pub password: Option<String>,
 }
 
@@ -474,6 +477,12 @@ pub struct ResetPasswordRequest {
     pub email: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResendActivationRequest {
+    #[validate(required, email(message = "Invalid email format"))]
+    pub email: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct ChangePasswordRequest {
     #[validate(required, email(message = "Invalid email format"))]
@@ -485,6 +494,289 @@ pub struct ChangePasswordRequest {
     pub reset_password_key: String
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangeEmailRequest {
+    #[validate(required, email(message = "Invalid email format"))]
+    pub new_email: Option<String>,
+
+    #[validate(custom(function = "required"))]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeRequest {
+    #[validate(length(min = 6, max = 16, message = "Invalid code"))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorLoginRequest {
+    #[validate(length(min = 1, message = "This field is required"))]
+    pub mfa_token: String,
+
+    #[validate(length(min = 6, max = 16, message = "Invalid code"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub qr_code: String,
+}
+
+/// 🌐 Awal login OIDC: browser diarahkan ke `authorization_url`, `state` juga disimpan di cookie
+#[derive(Debug, Serialize, Clone)]
+pub struct OidcRedirect {
+    pub authorization_url: String,
+    #[serde(skip)]
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Diisi provider jika user menolak / terjadi error di sisi provider
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetPhoneRequest {
+    #[validate(custom(function = "required"), custom(function = "valid_phone_number"))]
+    pub mobile_phone: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PhoneOtpVerifyRequest {
+    #[validate(length(min = 4, max = 9, message = "Invalid code"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PhoneOtpIssued {
+    pub expires_in: i64,
+    pub resend_in: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub ip_address: String,
+    pub user_agent: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: chrono::DateTime<Utc>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub last_seen_at: chrono::DateTime<Utc>,
+    /// Sesi yang sedang dipakai untuk request ini
+    pub current: bool,
+}
+
+/// ❌ Satu aturan password yang dilanggar, `rule` dipakai frontend untuk menampilkan pesan sendiri
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordViolation {
+    pub rule: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRoleRequest {
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnlockIpRequest {
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AccountClosureRequest {
+    #[validate(custom(function = "required"))]
+    pub password: Option<String>,
+
+    #[validate(length(max = 500, message = "Maximum 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ClosureReviewRequest {
+    #[validate(length(max = 500, message = "Maximum 500 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClosureListParams {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImpersonateRequest {
+    /// Alasan wajib diisi (nomor tiket support, dsb), ikut dicatat di audit
+    #[validate(custom(function = "required"), length(max = 500, message = "Maximum 500 characters"))]
+    pub reason: Option<String>,
+
+    /// Minta akses tulis, hanya untuk admin yang punya `kyc.write`
+    #[serde(default)]
+    pub write: bool,
+}
+
+/// 🕵️ Token "view as applicant", dipakai lewat header `Authorization: Bearer`
+#[derive(Debug, Serialize, Clone)]
+pub struct ImpersonationToken {
+    pub impersonation_id: i64,
+    pub auth_usernid: i32,
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImpersonationInfo {
+    pub impersonation_id: i64,
+    pub admin_usernid: i32,
+    pub auth_usernid: i32,
+    pub reason: String,
+    pub read_only: bool,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub started_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// ✏️ Permintaan ubah data setelah aplikasi disetujui, `changes` berisi field baru untuk satu `section`
+#[derive(Debug, Deserialize, Validate)]
+pub struct ProfileChangeRequest {
+    /// `address`, `bank` atau `occupation`
+    #[validate(custom(function = "required"))]
+    pub section: Option<String>,
+
+    #[serde(default)]
+    pub changes: serde_json::Map<String, serde_json::Value>,
+
+    #[validate(length(max = 500, message = "Maximum 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileChangeInfo {
+    pub change_nid: i32,
+    pub auth_usernid: i32,
+    pub section: String,
+    pub changes: serde_json::Value,
+    /// Nilai lama field yang diubah, saat permintaan dibuat
+    pub previous: serde_json::Value,
+    pub reason: Option<String>,
+    pub status: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub requested_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_option_datetime")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+}
+
+/// 🗂️ Satu versi data KYC (snapshot baris `UserKyc`)
+#[derive(Debug, Serialize, Clone)]
+pub struct KycVersionInfo {
+    pub version: i32,
+    pub change_nid: Option<i32>,
+    pub data: serde_json::Value,
+    pub created_by: Option<i32>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 📊 Kelengkapan data onboarding per form, dihitung dari data yang tersimpan
+#[derive(Debug, Serialize, Clone)]
+pub struct OnboardingProgress {
+    pub stage: Option<KycStage>,
+    /// Semua form lengkap dan aplikasi sedang menunggu keputusan reviewer
+    pub under_review: bool,
+    pub steps: Vec<StepProgress>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StepProgress {
+    pub step: String,
+    /// `not_started`, `incomplete`, `complete` atau `under_review`
+    pub status: String,
+    /// Field wajib yang belum diisi
+    pub missing: Vec<String>,
+    /// Field yang terisi tapi tidak lolos validasi form-nya, berisi pesan error
+    pub invalid: HashMap<String, String>,
+}
+
+/// 📝 Draft satu form onboarding, `data` berisi JSON apa adanya dari applicant
+#[derive(Debug, Serialize, Clone)]
+pub struct KycDraftInfo {
+    pub step: String,
+    pub data: serde_json::Value,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// ⚖️ Keputusan review KYC oleh reviewer
+#[derive(Debug, Deserialize, Validate)]
+pub struct KycReviewRequest {
+    /// `approve`, `reject` atau `revise`
+    #[validate(custom(function = "required"))]
+    pub decision: Option<String>,
+
+    /// Alasan keputusan, ikut dikirim ke applicant
+    #[validate(custom(function = "required"), length(max = 500, message = "Maximum 500 characters"))]
+    pub reason: Option<String>,
+
+    /// Form yang harus diperbaiki (wajib untuk `revise`), contoh `["bank_account", "occupation"]`
+    #[serde(default)]
+    pub steps: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KycReviewInfo {
+    pub review_nid: i64,
+    pub auth_usernid: i32,
+    pub decision: String,
+    pub reason: String,
+    /// Stage tujuan applicant setelah `revision`
+    pub reopened_stage: Option<KycStage>,
+    pub reviewed_by: i32,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 📥 Aplikasi yang sudah `completed` dan menunggu keputusan reviewer
+#[derive(Debug, Serialize, Clone)]
+pub struct KycPendingReview {
+    pub auth_usernid: i32,
+    pub email: String,
+    pub full_name: String,
+    pub is_revised: bool,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub last_update: DateTime<Utc>,
+}
+
+/// 🗑️ Permintaan tutup akun, `email` kosong setelah data pribadi dihapus
+#[derive(Debug, Serialize, Clone)]
+pub struct AccountClosureInfo {
+    pub closure_nid: i64,
+    pub auth_usernid: i32,
+    pub email: String,
+    pub reason: String,
+    pub status: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub requested_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_option_datetime")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ActionResult<T, E> {
     pub result: bool,
@@ -515,7 +807,33 @@ pub struct WebUser {
     pub disabled_login: bool,
     pub picture: Option<String>,
     #[serde(serialize_with = "serialize_datetime")]
-    pub register_date: chrono::DateTime<Utc>
+    pub register_date: chrono::DateTime<Utc>,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    /// `true` jika login ini sudah lolos faktor kedua (TOTP / recovery code)
+    pub mfa: bool,
+    /// Sesi login (`AuthSession`) yang akan dibawa ke claim `sid`
+    #[serde(skip)]
+    pub session_id: Option<String>,
+    /// Terakhir ganti password, untuk cek `PASSWORD_MAX_AGE_DAYS`
+    #[serde(skip)]
+    pub password_changed_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LoginResponse {
+    #[serde(flatten)]
+    pub user: WebUser,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RefreshTokenRequest {
+    #[serde(default)]
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -527,6 +845,7 @@ pub struct UserInfo {
     pub is_revised: bool,
     pub is_rejected: bool,
     pub is_finished: bool,
+    pub is_approved: bool,
     pub account_status: i32,
     pub mobile_phone: String,
     pub email: String,
@@ -642,6 +961,16 @@ where
     serializer.serialize_str(&formatted)
 }
 
+fn serialize_option_datetime<S>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match dt {
+        Some(dt) => serialize_datetime(dt, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 fn deserialize_date_only<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
 where
     D: Deserializer<'de>,
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::{decode, decode_header, jwk::{Jwk, JwkSet}, Algorithm, DecodingKey, Validation};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Metadata discovery & JWKS provider di-cache selama ini
+const METADATA_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// 🌐 Provider OpenID Connect, dikonfigurasi lewat `.env`:
+/// `OIDC_PROVIDERS=google,mock` lalu `OIDC_<NAMA>_ISSUER`, `OIDC_<NAMA>_CLIENT_ID`,
+/// `OIDC_<NAMA>_CLIENT_SECRET` (opsional), `OIDC_<NAMA>_REDIRECT_URI`, `OIDC_<NAMA>_SCOPES`
+#[derive(Debug, Clone)]
+pub struct OidcProvider {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: String,
+}
+
+/// Bagian dari `/.well-known/openid-configuration` yang dipakai
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// 🔗 Parameter satu kali login: `state` & `nonce` dicocokkan saat callback, `code_verifier` untuk PKCE
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// 👤 Identitas dari ID token yang sudah diverifikasi
+#[derive(Debug, Clone)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    /// Sebagian provider mengirim `"true"` (string)
+    email_verified: Option<Value>,
+    name: Option<String>,
+}
+
+static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build HTTP client")
+});
+
+/// (waktu diambil, metadata, JWKS) per provider
+type CachedMetadata = (Instant, ProviderMetadata, JwkSet);
+
+static METADATA_CACHE: Lazy<RwLock<HashMap<String, CachedMetadata>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Nama provider yang aktif (huruf kecil)
+pub fn provider_names() -> Vec<String> {
+    env::var("OIDC_PROVIDERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Provider hanya dikenali jika ada di `OIDC_PROVIDERS` dan konfigurasinya lengkap
+pub fn provider(name: &str) -> Result<OidcProvider, String> {
+    let name = name.to_lowercase();
+    if !provider_names().contains(&name) {
+        return Err(format!("Unknown OIDC provider '{}'", name));
+    }
+
+    let prefix = format!("OIDC_{}_", name.to_uppercase());
+    let read = |key: &str| env::var(format!("{}{}", prefix, key)).ok().filter(|value| !value.trim().is_empty());
+    let required = |key: &str| read(key).ok_or_else(|| format!("{}{} is not configured", prefix, key));
+
+    Ok(OidcProvider {
+        issuer: required("ISSUER")?.trim_end_matches('/').to_string(),
+        client_id: required("CLIENT_ID")?,
+        client_secret: read("CLIENT_SECRET"),
+        redirect_uri: required("REDIRECT_URI")?,
+        scopes: read("SCOPES").unwrap_or_else(|| "openid email profile".to_string()),
+        name,
+    })
+}
+
+fn random_url_safe(length: usize) -> String {
+    let mut bytes = vec![0u8; length];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl OidcProvider {
+
+    /// 🔎 Discovery metadata + JWKS, diambil ulang jika cache kedaluwarsa atau `refresh`
+    async fn metadata(&self, refresh: bool) -> Result<(ProviderMetadata, JwkSet), String> {
+        if !refresh {
+            let cache = METADATA_CACHE.read().map_err(|_| "OIDC metadata cache poisoned".to_string())?;
+            if let Some((fetched_at, metadata, jwks)) = cache.get(&self.name) {
+                if fetched_at.elapsed() < METADATA_CACHE_TTL {
+                    return Ok((metadata.clone(), jwks.clone()));
+                }
+            }
+        }
+
+        let discovery_url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let metadata: ProviderMetadata = HTTP_CLIENT.get(&discovery_url).send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("OIDC discovery failed for {}: {}", self.name, e))?
+            .json().await
+            .map_err(|e| format!("Invalid OIDC discovery document for {}: {}", self.name, e))?;
+
+        if metadata.issuer.trim_end_matches('/') != self.issuer {
+            return Err(format!("OIDC issuer mismatch for {}: {}", self.name, metadata.issuer));
+        }
+
+        let jwks: JwkSet = HTTP_CLIENT.get(&metadata.jwks_uri).send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to fetch JWKS for {}: {}", self.name, e))?
+            .json().await
+            .map_err(|e| format!("Invalid JWKS for {}: {}", self.name, e))?;
+
+        METADATA_CACHE.write()
+            .map_err(|_| "OIDC metadata cache poisoned".to_string())?
+            .insert(self.name.clone(), (Instant::now(), metadata.clone(), jwks.clone()));
+
+        Ok((metadata, jwks))
+    }
+
+    /// 🚀 URL authorization code + PKCE (S256)
+    pub async fn authorization_request(&self) -> Result<AuthorizationRequest, String> {
+        let (metadata, _) = self.metadata(false).await?;
+
+        let state = random_url_safe(32);
+        let nonce = random_url_safe(32);
+        let code_verifier = random_url_safe(48);
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = Url::parse_with_params(&metadata.authorization_endpoint, &[
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", self.scopes.as_str()),
+            ("state", state.as_str()),
+            ("nonce", nonce.as_str()),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]).map_err(|e| format!("Invalid authorization endpoint for {}: {}", self.name, e))?;
+
+        Ok(AuthorizationRequest { url: url.to_string(), state, nonce, code_verifier })
+    }
+
+    /// 🔁 Tukar authorization code dengan ID token lalu verifikasi tanda tangan, `iss`, `aud`, `exp` & `nonce`
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str, nonce: &str) -> Result<OidcIdentity, String> {
+        let (metadata, _) = self.metadata(false).await?;
+
+        let mut form: Vec<(&str, &str)> = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(client_secret) = self.client_secret.as_deref() {
+            form.push(("client_secret", client_secret));
+        }
+
+        let response = HTTP_CLIENT.post(&metadata.token_endpoint).form(&form).send().await
+            .map_err(|e| format!("OIDC token request failed for {}: {}", self.name, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body: String = response.text().await.unwrap_or_default().chars().take(500).collect();
+            return Err(format!("OIDC token request rejected by {} ({}): {}", self.name, status, body));
+        }
+
+        let token: TokenResponse = response.json().await.map_err(|e| format!("Invalid OIDC token response from {}: {}", self.name, e))?;
+        let id_token = token.id_token.ok_or_else(|| format!("OIDC token response from {} has no id_token", self.name))?;
+
+        let claims = self.verify_id_token(&id_token, &metadata).await?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("OIDC nonce mismatch".to_string());
+        }
+
+        let email_verified = matches!(claims.email_verified, Some(Value::Bool(true))) || matches!(claims.email_verified.as_ref(), Some(Value::String(value)) if value == "true");
+
+        Ok(OidcIdentity {
+            subject: claims.sub,
+            email: claims.email,
+            email_verified,
+            name: claims.name,
+        })
+    }
+
+    async fn verify_id_token(&self, id_token: &str, metadata: &ProviderMetadata) -> Result<IdTokenClaims, String> {
+        let header = decode_header(id_token).map_err(|e| format!("Invalid id_token: {}", e))?;
+
+        // Secret HMAC bukan milik provider, hanya algoritma asimetris yang diterima
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(format!("Unsupported id_token algorithm {:?}", header.alg));
+        }
+
+        let find_key = |jwks: &JwkSet| -> Option<Jwk> {
+            match header.kid.as_deref() {
+                Some(kid) => jwks.find(kid).cloned(),
+                None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+                None => None,
+            }
+        };
+
+        let (_, jwks) = self.metadata(false).await?;
+        // `kid` tidak dikenal → kemungkinan provider baru rotasi kunci, ambil ulang JWKS sekali
+        let jwk = match find_key(&jwks) {
+            Some(jwk) => jwk,
+            None => find_key(&self.metadata(true).await?.1).ok_or_else(|| "No matching key for id_token".to_string())?,
+        };
+
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|e| format!("Invalid provider key: {}", e))?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[metadata.issuer.as_str()]);
+        validation.set_audience(&[self.client_id.as_str()]);
+
+        decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| format!("Invalid id_token: {}", e))
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use serde::Serialize;
+
+/// 🧭 Tahapan onboarding KYC, disimpan sebagai angka di `UserKyc.Stage`.
+/// Nilai stage = form berikutnya yang harus diisi applicant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KycStage {
+    /// Data pribadi, plus data beneficiary owner jika pemilik dana bukan diri sendiri
+    PersonalData = 1,
+    BankAccount = 2,
+    Occupation = 3,
+    SupportingData = 4,
+    /// Semua form terisi (`IsFinished = 1`), tidak bisa diubah lagi lewat form onboarding
+    Completed = 5,
+}
+
+impl KycStage {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            1 => Some(KycStage::PersonalData),
+            2 => Some(KycStage::BankAccount),
+            3 => Some(KycStage::Occupation),
+            4 => Some(KycStage::SupportingData),
+            5 => Some(KycStage::Completed),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KycStage::PersonalData => "personal_data",
+            KycStage::BankAccount => "bank_account",
+            KycStage::Occupation => "occupation",
+            KycStage::SupportingData => "supporting_data",
+            KycStage::Completed => "completed",
+        }
+    }
+
+    /// Stage tempat form dengan nama `step` (lihat [`KycStep::as_str`]) diisi
+    pub fn of_step(step: &str) -> Option<Self> {
+        match step {
+            "personal_data" | "beneficiary_owner" | "documents" => Some(KycStage::PersonalData),
+            "bank_account" => Some(KycStage::BankAccount),
+            "occupation" => Some(KycStage::Occupation),
+            "supporting_data" => Some(KycStage::SupportingData),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KycStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' ({})", self.as_str(), self.as_i32())
+    }
+}
+
+/// 📝 Form onboarding yang mengubah data KYC, tiap save function lewat [`transition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KycStep {
+    /// `beneficiary_owner`: 1 = diri sendiri, 2 = orang lain (wajib isi form beneficiary owner)
+    PersonalData { beneficiary_owner: i32 },
+    BeneficiaryOwner { beneficiary_owner: i32 },
+    BankAccount,
+    Occupation,
+    SupportingData,
+    /// Upload KTP / selfie / tanda tangan, boleh kapan saja selama onboarding dan tidak memajukan stage.
+    /// Wajib lengkap sebelum `supporting_data` menyelesaikan onboarding
+    Documents,
+}
+
+/// 📋 Data tersimpan di luar `Stage` yang ikut menentukan perpindahan stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KycFacts {
+    /// `TableRequest.CIFInvestorBeneficiaryOwner` sebelum form disimpan (0 = data pribadi belum pernah disimpan)
+    pub beneficiary_owner: i32,
+    /// `IDCardFile`, `SelfieFile` & `SignatureFile` sudah terisi
+    pub documents_uploaded: bool,
+}
+
+impl KycStep {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KycStep::PersonalData { .. } => "personal_data",
+            KycStep::BeneficiaryOwner { .. } => "beneficiary_owner",
+            KycStep::BankAccount => "bank_account",
+            KycStep::Occupation => "occupation",
+            KycStep::SupportingData => "supporting_data",
+            KycStep::Documents => "documents",
+        }
+    }
+
+    /// Stage paling awal di mana form ini boleh disimpan
+    fn required_stage(self) -> KycStage {
+        match self {
+            KycStep::PersonalData { .. } | KycStep::BeneficiaryOwner { .. } | KycStep::Documents => KycStage::PersonalData,
+            KycStep::BankAccount => KycStage::BankAccount,
+            KycStep::Occupation => KycStage::Occupation,
+            KycStep::SupportingData => KycStage::SupportingData,
+        }
+    }
+
+    /// Stage tujuan saat form ini disimpan tepat di `required_stage`
+    fn target_stage(self) -> KycStage {
+        match self {
+            KycStep::PersonalData { beneficiary_owner: 1 } | KycStep::BeneficiaryOwner { .. } => KycStage::BankAccount,
+            KycStep::PersonalData { .. } | KycStep::Documents => KycStage::PersonalData,
+            KycStep::BankAccount => KycStage::Occupation,
+            KycStep::Occupation => KycStage::SupportingData,
+            KycStep::SupportingData => KycStage::Completed,
+        }
+    }
+
+    /// Syarat tambahan di luar urutan stage
+    fn guard(self, facts: KycFacts) -> Result<(), StageError> {
+        match self {
+            KycStep::PersonalData { beneficiary_owner } if beneficiary_owner != 1 && beneficiary_owner != 2 => {
+                Err(StageError::Guard("Invalid beneficiary owner"))
+            }
+            KycStep::BeneficiaryOwner { beneficiary_owner: 1 } => Err(StageError::Guard("Invalid beneficiary owner")),
+            KycStep::BeneficiaryOwner { .. } if facts.beneficiary_owner != 2 => {
+                Err(StageError::Guard("Beneficiary owner form is only required when personal data names another beneficiary owner"))
+            }
+            KycStep::SupportingData if !facts.documents_uploaded => {
+                Err(StageError::Guard("ID card, selfie and signature must be uploaded before completing onboarding"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// ❌ Alasan form onboarding ditolak
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageError {
+    /// `UserKyc.Stage` berisi angka di luar [`KycStage`]
+    Unknown(i32),
+    /// Form dikirim sebelum form sebelumnya selesai
+    NotReached { step: KycStep, current: KycStage, expected: KycStage },
+    /// Onboarding sudah selesai
+    Completed { step: KycStep },
+    Guard(&'static str),
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StageError::Unknown(stage) => write!(f, "Unknown onboarding stage {}", stage),
+            StageError::NotReached { step, current, expected } => write!(
+                f,
+                "Cannot save '{}': current stage is {}, expected stage {} or later",
+                step.as_str(), current, expected
+            ),
+            StageError::Completed { step } => write!(
+                f,
+                "Cannot save '{}': onboarding is already {}",
+                step.as_str(), KycStage::Completed
+            ),
+            StageError::Guard(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// 🔀 Validasi perpindahan stage untuk satu form dan kembalikan stage baru yang harus disimpan.
+///
+/// Form yang sudah pernah diisi boleh disimpan ulang selama onboarding belum selesai,
+/// tapi stage tidak pernah mundur, kecuali data pribadi diubah ke beneficiary owner orang lain:
+/// stage kembali ke `personal_data` sampai form beneficiary owner diisi.
+pub fn transition(current: i32, step: KycStep, facts: KycFacts) -> Result<KycStage, StageError> {
+    let current = KycStage::from_i32(current).ok_or(StageError::Unknown(current))?;
+
+    if current == KycStage::Completed {
+        return Err(StageError::Completed { step });
+    }
+
+    let expected = step.required_stage();
+    if current < expected {
+        return Err(StageError::NotReached { step, current, expected });
+    }
+
+    step.guard(facts)?;
+
+    if let KycStep::PersonalData { beneficiary_owner: 2 } = step {
+        if facts.beneficiary_owner != 2 {
+            return Ok(KycStage::PersonalData);
+        }
+    }
+
+    Ok(current.max(step.target_stage()))
+}
+
+/// ⚖️ Keputusan reviewer atas aplikasi yang sudah `completed` dan belum diputuskan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Approve,
+    Reject,
+    /// Kembalikan ke applicant, stage mundur ke form paling awal yang harus diperbaiki
+    Revise { reopen: KycStage },
+}
+
+impl ReviewDecision {
+    /// `decision`: `approve` / `reject` / `revise`, `steps` wajib untuk `revise`
+    pub fn parse(decision: &str, steps: &[String]) -> Result<Self, String> {
+        match decision {
+            "approve" => Ok(ReviewDecision::Approve),
+            "reject" => Ok(ReviewDecision::Reject),
+            "revise" => {
+                let mut reopen: Option<KycStage> = None;
+                for step in steps {
+                    let stage = KycStage::of_step(step).ok_or_else(|| format!("Unknown step '{}'", step))?;
+                    reopen = Some(reopen.map_or(stage, |current| current.min(stage)));
+                }
+                reopen
+                    .map(|reopen| ReviewDecision::Revise { reopen })
+                    .ok_or_else(|| "At least one step is required for revision".to_string())
+            }
+            _ => Err(format!("Unknown decision '{}'", decision)),
+        }
+    }
+
+    /// Nilai `KycReviewHistory.Decision`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReviewDecision::Approve => "approved",
+            ReviewDecision::Reject => "rejected",
+            ReviewDecision::Revise { .. } => "revision",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELF_OWNED: KycFacts = KycFacts { beneficiary_owner: 1, documents_uploaded: false };
+    const OTHER_OWNER: KycFacts = KycFacts { beneficiary_owner: 2, documents_uploaded: false };
+    const DOCUMENTS: KycFacts = KycFacts { beneficiary_owner: 1, documents_uploaded: true };
+
+    #[test]
+    fn forms_advance_in_order() {
+        assert_eq!(transition(1, KycStep::PersonalData { beneficiary_owner: 1 }, KycFacts::default()), Ok(KycStage::BankAccount));
+        assert_eq!(transition(2, KycStep::BankAccount, SELF_OWNED), Ok(KycStage::Occupation));
+        assert_eq!(transition(3, KycStep::Occupation, SELF_OWNED), Ok(KycStage::SupportingData));
+        assert_eq!(transition(4, KycStep::SupportingData, DOCUMENTS), Ok(KycStage::Completed));
+    }
+
+    #[test]
+    fn skipping_a_form_is_rejected() {
+        assert_eq!(
+            transition(1, KycStep::Occupation, SELF_OWNED),
+            Err(StageError::NotReached { step: KycStep::Occupation, current: KycStage::PersonalData, expected: KycStage::Occupation })
+        );
+    }
+
+    #[test]
+    fn unknown_and_completed_stages_are_rejected() {
+        assert_eq!(transition(9, KycStep::BankAccount, SELF_OWNED), Err(StageError::Unknown(9)));
+        assert_eq!(transition(5, KycStep::Documents, DOCUMENTS), Err(StageError::Completed { step: KycStep::Documents }));
+    }
+
+    #[test]
+    fn resaving_an_earlier_form_keeps_the_stage() {
+        assert_eq!(transition(4, KycStep::BankAccount, SELF_OWNED), Ok(KycStage::SupportingData));
+        assert_eq!(transition(3, KycStep::PersonalData { beneficiary_owner: 1 }, SELF_OWNED), Ok(KycStage::Occupation));
+        assert_eq!(transition(3, KycStep::PersonalData { beneficiary_owner: 2 }, OTHER_OWNER), Ok(KycStage::Occupation));
+    }
+
+    #[test]
+    fn beneficiary_owner_form_is_required_for_another_owner() {
+        assert_eq!(transition(1, KycStep::PersonalData { beneficiary_owner: 2 }, KycFacts::default()), Ok(KycStage::PersonalData));
+        assert_eq!(transition(1, KycStep::BeneficiaryOwner { beneficiary_owner: 2 }, OTHER_OWNER), Ok(KycStage::BankAccount));
+        assert!(matches!(transition(1, KycStep::BeneficiaryOwner { beneficiary_owner: 2 }, KycFacts::default()), Err(StageError::Guard(_))));
+        assert!(matches!(transition(1, KycStep::PersonalData { beneficiary_owner: 3 }, KycFacts::default()), Err(StageError::Guard(_))));
+    }
+
+    #[test]
+    fn switching_to_another_owner_reopens_personal_data() {
+        assert_eq!(transition(3, KycStep::PersonalData { beneficiary_owner: 2 }, SELF_OWNED), Ok(KycStage::PersonalData));
+    }
+
+    #[test]
+    fn documents_never_advance_but_are_required_to_complete() {
+        assert_eq!(transition(1, KycStep::Documents, KycFacts::default()), Ok(KycStage::PersonalData));
+        assert_eq!(transition(4, KycStep::Documents, SELF_OWNED), Ok(KycStage::SupportingData));
+        assert!(matches!(transition(4, KycStep::SupportingData, SELF_OWNED), Err(StageError::Guard(_))));
+    }
+}
@@ -0,0 +1,116 @@
+use std::env;
+use actix_web::web;
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use once_cell::sync::Lazy;
+use rand::{rng, RngCore};
+use super::crypto::encrypt_text;
+
+/// 🔑 Versi format hash yang tersimpan di kolom `AuthUser.Password`
+///
+/// - `LegacyAes`: ciphertext AES-256-CTR lama (base64, tanpa prefix `$`)
+/// - `Argon2id`: PHC string `$argon2id$v=19$m=..,t=..,p=..$salt$hash`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashVersion {
+    LegacyAes,
+    Argon2id,
+}
+
+impl HashVersion {
+    pub fn detect(stored: &str) -> Self {
+        if stored.starts_with("$argon2id$") {
+            HashVersion::Argon2id
+        } else {
+            HashVersion::LegacyAes
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordCheck {
+    pub valid: bool,
+    /// `true` jika hash perlu dibuat ulang (format lama / parameter sudah berubah)
+    pub needs_rehash: bool,
+}
+
+/// Parameter Argon2id, bisa diatur lewat `.env`
+fn current_params() -> Params {
+    let memory = env::var("PASSWORD_HASH_MEMORY_KIB").ok().and_then(|v| v.parse().ok()).unwrap_or(19_456);
+    let iterations = env::var("PASSWORD_HASH_ITERATIONS").ok().and_then(|v| v.parse().ok()).unwrap_or(2);
+    let parallelism = env::var("PASSWORD_HASH_PARALLELISM").ok().and_then(|v| v.parse().ok()).unwrap_or(1);
+
+    Params::new(memory, iterations, parallelism, None).unwrap_or_default()
+}
+
+fn hasher() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, current_params())
+}
+
+/// Hash pembanding untuk email yang tidak terdaftar, dibuat sekali dengan parameter aktif
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| {
+    let mut password = [0u8; 32];
+    rng().fill_bytes(&mut password);
+    hash_password_blocking(&String::from_utf8_lossy(&password)).unwrap_or_default()
+});
+
+/// 🔐 Hash password dengan Argon2id + salt acak 16 byte.
+/// Argon2 sengaja berat, jadi dijalankan di thread pool blocking supaya worker async tidak tertahan
+pub async fn hash_password(password: &str) -> Result<String, String> {
+    let password = password.to_string();
+    web::block(move || hash_password_blocking(&password))
+        .await
+        .map_err(|e| format!("Failed to hash password: {:?}", e))?
+}
+
+/// 🔓 Verifikasi password terhadap hash yang tersimpan (mendukung format lama), di thread pool blocking
+pub async fn verify_password(password: &str, stored: &str) -> PasswordCheck {
+    let (password, stored) = (password.to_string(), stored.to_string());
+    web::block(move || verify_password_blocking(&password, &stored))
+        .await
+        .unwrap_or(PasswordCheck { valid: false, needs_rehash: false })
+}
+
+/// ⏱️ Verifikasi tiruan untuk email yang tidak ditemukan, supaya waktu respons login tidak membedakan email terdaftar
+pub async fn verify_dummy_password(password: &str) {
+    let password = password.to_string();
+    let _ = web::block(move || verify_password_blocking(&password, &DUMMY_HASH)).await;
+}
+
+fn hash_password_blocking(password: &str) -> Result<String, String> {
+    let mut salt_bytes = [0u8; 16];
+    rng().fill_bytes(&mut salt_bytes);
+
+    let salt = SaltString::encode_b64(&salt_bytes).map_err(|e| format!("Failed to encode salt: {}", e))?;
+
+    hasher()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// Versi sinkron [`verify_password`], hanya untuk kode yang sudah berjalan di thread blocking
+pub fn verify_password_blocking(password: &str, stored: &str) -> PasswordCheck {
+    match HashVersion::detect(stored) {
+        HashVersion::LegacyAes => {
+            let valid = constant_time_eq(encrypt_text(password.to_string()).as_bytes(), stored.as_bytes());
+            PasswordCheck { valid, needs_rehash: valid }
+        }
+        HashVersion::Argon2id => match PasswordHash::new(stored) {
+            Ok(parsed) => {
+                let valid = hasher().verify_password(password.as_bytes(), &parsed).is_ok();
+                let needs_rehash = valid && Params::try_from(&parsed).map_or(true, |p| p != current_params());
+                PasswordCheck { valid, needs_rehash }
+            }
+            Err(_) => PasswordCheck { valid: false, needs_rehash: false },
+        },
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
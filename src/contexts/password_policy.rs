@@ -0,0 +1,127 @@
+use std::{collections::HashSet, env, fs};
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use sha1::{Digest, Sha1};
+use super::{logger::write_log, model::PasswordViolation};
+
+/// 📜 Aturan password, bisa diatur lewat `.env`
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_letter: bool,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Jumlah password terakhir (termasuk yang sekarang) yang tidak boleh dipakai ulang, 0 = nonaktif
+    pub history_count: i32,
+    /// Umur maksimum password, `None` = tidak pernah kedaluwarsa
+    pub max_age: Option<Duration>,
+}
+
+/// SHA-1 password umum / bocor, dimuat sekali dari `PASSWORD_BREACHED_LIST`
+static BREACHED_PASSWORDS: Lazy<HashSet<String>> = Lazy::new(|| {
+    let path = env::var("PASSWORD_BREACHED_LIST").unwrap_or_else(|_| "./data/breached_passwords.txt".to_string());
+
+    match fs::read_to_string(&path) {
+        Ok(content) => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            // Dump HIBP berformat `HASH:COUNT`
+            .map(|line| line.split(':').next().unwrap_or_default().to_uppercase())
+            .collect(),
+        Err(err) => {
+            write_log("WARN", format!("Breached password list {} not loaded: {}", path, err).as_str());
+            HashSet::new()
+        }
+    }
+});
+
+fn sha1_hex(value: &str) -> String {
+    Sha1::digest(value.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect()
+}
+
+fn violation(rule: &str, message: String) -> PasswordViolation {
+    PasswordViolation { rule: rule.to_string(), message }
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: i64| -> i64 { env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default) };
+        let flag = |key: &str, default: bool| -> bool { env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default) };
+        let max_age_days = read("PASSWORD_MAX_AGE_DAYS", 0);
+
+        Self {
+            min_length: read("PASSWORD_MIN_LENGTH", 8).max(1) as usize,
+            max_length: read("PASSWORD_MAX_LENGTH", 128).max(1) as usize,
+            require_letter: flag("PASSWORD_REQUIRE_LETTER", true),
+            require_uppercase: flag("PASSWORD_REQUIRE_UPPERCASE", false),
+            require_lowercase: flag("PASSWORD_REQUIRE_LOWERCASE", false),
+            require_digit: flag("PASSWORD_REQUIRE_DIGIT", true),
+            require_symbol: flag("PASSWORD_REQUIRE_SYMBOL", false),
+            history_count: read("PASSWORD_HISTORY_COUNT", 5).max(0) as i32,
+            max_age: (max_age_days > 0).then(|| Duration::days(max_age_days)),
+        }
+    }
+
+    /// ✅ Cek aturan yang tidak butuh database (panjang, jenis karakter, daftar bocor).
+    /// Semua aturan yang dilanggar dikembalikan, bukan hanya yang pertama
+    pub fn check(&self, password: &str) -> Vec<PasswordViolation> {
+        let mut violations: Vec<PasswordViolation> = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            violations.push(violation("min_length", format!("Password must be at least {} characters", self.min_length)));
+        }
+        if length > self.max_length {
+            violations.push(violation("max_length", format!("Password must be at most {} characters", self.max_length)));
+        }
+        if self.require_letter && !password.chars().any(char::is_alphabetic) {
+            violations.push(violation("letter", "Password must contain a letter".to_string()));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(violation("uppercase", "Password must contain an uppercase letter".to_string()));
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(violation("lowercase", "Password must contain a lowercase letter".to_string()));
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(violation("digit", "Password must contain a number".to_string()));
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violations.push(violation("symbol", "Password must contain a symbol".to_string()));
+        }
+        if Self::is_breached(password) {
+            violations.push(violation("breached", "Password is too common or has appeared in a data breach".to_string()));
+        }
+
+        violations
+    }
+
+    /// 🔎 Cocokkan SHA-1 password (dan versi huruf kecilnya) dengan daftar lokal
+    pub fn is_breached(password: &str) -> bool {
+        BREACHED_PASSWORDS.contains(&sha1_hex(password)) || BREACHED_PASSWORDS.contains(&sha1_hex(&password.to_lowercase()))
+    }
+
+    pub fn reused_violation(&self) -> PasswordViolation {
+        violation("history", format!("Password must not match any of your last {} passwords", self.history_count))
+    }
+
+    /// ⏳ Password kedaluwarsa jika `PASSWORD_MAX_AGE_DAYS` aktif dan sudah terlewati
+    pub fn is_expired(&self, changed_at: Option<DateTime<Utc>>) -> bool {
+        match (self.max_age, changed_at) {
+            (Some(max_age), Some(changed_at)) => changed_at + max_age < Utc::now(),
+            _ => false,
+        }
+    }
+
+    pub fn expired_violation(&self) -> PasswordViolation {
+        let days = self.max_age.map(|max_age| max_age.num_days()).unwrap_or_default();
+        violation("max_age", format!("Password is older than {} days, please reset your password", days))
+    }
+}
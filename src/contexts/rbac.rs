@@ -0,0 +1,139 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage, HttpResponse, ResponseError,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+
+use super::{authenticated::current_claims, jwt_session::Claims, model::ActionResult};
+
+/// 🛡️ Daftar permission yang bisa diminta oleh route.
+/// Mapping role → permission disimpan di tabel `AuthRolePermission`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    AdminAccess,
+    KycRead,
+    KycWrite,
+    KycReview,
+    TableRead,
+    UserManage,
+    UserImpersonate,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::AdminAccess => "admin.access",
+            Permission::KycRead => "kyc.read",
+            Permission::KycWrite => "kyc.write",
+            Permission::KycReview => "kyc.review",
+            Permission::TableRead => "table.read",
+            Permission::UserManage => "user.manage",
+            Permission::UserImpersonate => "user.impersonate",
+        }
+    }
+}
+
+/// Role bawaan yang dikenal aplikasi (`AuthRole.RoleCode`)
+pub const ROLE_APPLICANT: &str = "applicant";
+pub const ROLE_SALES: &str = "sales";
+pub const ROLE_REVIEWER: &str = "reviewer";
+pub const ROLE_ADMINISTRATOR: &str = "administrator";
+
+impl Claims {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.iter().any(|p| p == permission.as_str())
+    }
+}
+
+/// Middleware: tolak request jika token tidak punya permission yang diminta.
+///
+/// Bisa dipasang di scope (`.wrap(RequirePermission::new(..))`) maupun per route
+/// (`#[get("/path", wrap = "RequirePermission::new(Permission::TableRead)")]`).
+pub struct RequirePermission {
+    permission: Permission,
+}
+
+impl RequirePermission {
+    pub fn new(permission: Permission) -> Self {
+        Self { permission }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware { service, permission: self.permission }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: S,
+    permission: Permission,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        match authorize(&req, self.permission) {
+            Ok(()) => {
+                let fut = self.service.call(req);
+                Box::pin(async move { fut.await.map(|res| res.map_into_left_body()) })
+            }
+            Err(response) => {
+                let (request, _) = req.into_parts();
+                Box::pin(async move { Ok(ServiceResponse::new(request, response).map_into_right_body()) })
+            }
+        }
+    }
+}
+
+fn authorize(req: &ServiceRequest, permission: Permission) -> Result<(), HttpResponse> {
+    let mut result: ActionResult<(), String> = ActionResult::default();
+
+    let claims = match current_claims(req.request()) {
+        Ok(claims) => claims,
+        Err(err) => return Err(err.error_response()),
+    };
+
+    // 🕵️ Token impersonation punya hak applicant, tidak boleh dipakai untuk route staff
+    if claims.impersonator.is_some() {
+        result.message = "Forbidden".to_string();
+        result.error = Some("Not available while impersonating".to_string());
+        return Err(HttpResponse::Forbidden().json(result));
+    }
+
+    // 🔐 Staff yang bisa melihat dokumen KYC wajib login lewat 2FA
+    if claims.has_permission(Permission::KycRead) && !claims.mfa {
+        result.message = "Forbidden".to_string();
+        result.error = Some("Two-factor authentication required".to_string());
+        return Err(HttpResponse::Forbidden().json(result));
+    }
+
+    if !claims.has_permission(permission) {
+        result.message = "Forbidden".to_string();
+        result.error = Some(format!("Missing permission '{}'", permission.as_str()));
+        return Err(HttpResponse::Forbidden().json(result));
+    }
+
+    req.extensions_mut().insert(claims);
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::env;
+use futures::future::BoxFuture;
+use super::logger::write_log;
+
+/// 📱 Provider pengiriman OTP (SMS / WhatsApp). Dipilih lewat `SMS_PROVIDER`
+pub trait SmsProvider: Send + Sync {
+    fn send<'a>(&'a self, phone: &'a str, message: &'a str) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Buat provider sesuai `.env`, default `mock`
+pub fn provider_from_env() -> Result<Box<dyn SmsProvider>, String> {
+    match env::var("SMS_PROVIDER").unwrap_or_else(|_| "mock".to_string()).to_lowercase().as_str() {
+        "mock" => Ok(Box::new(MockSmsProvider)),
+        other => Err(format!("Unknown SMS_PROVIDER '{}'", other)),
+    }
+}
+
+/// 🧪 Provider lokal: pesan hanya ditulis ke log, tidak dikirim ke mana pun
+pub struct MockSmsProvider;
+
+impl SmsProvider for MockSmsProvider {
+    fn send<'a>(&'a self, phone: &'a str, message: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            write_log("INFO", format!("[MOCK SMS] to {}: {}", phone, message).as_str());
+            Ok(())
+        })
+    }
+}
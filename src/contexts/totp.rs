@@ -0,0 +1,78 @@
+use std::env;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use qrcode::{render::svg, QrCode};
+use rand::{rng, RngCore};
+use sha1::Sha1;
+
+/// ⏱️ Parameter TOTP standar (RFC 6238) supaya kompatibel dengan Google Authenticator dkk
+const DIGITS: u32 = 6;
+const PERIOD: i64 = 30;
+/// Toleransi jam client: 1 step sebelum & sesudah
+const SKEW: i64 = 1;
+
+/// 🔑 Secret acak 160-bit, dikembalikan dalam base32 (tanpa padding)
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+fn code_at(secret: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(&(step as u64).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation (RFC 4226 section 5.3)
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+
+    binary % 10u32.pow(DIGITS)
+}
+
+/// ✅ Cek kode TOTP. Return step yang cocok supaya kode yang sama tidak bisa dipakai ulang.
+/// Step `<= last_used_step` selalu ditolak.
+pub fn verify_code(secret: &str, code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = Utc::now().timestamp() / PERIOD;
+
+    (current - SKEW..=current + SKEW)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at(&secret, *step) == code)
+}
+
+/// 🔗 URI `otpauth://` untuk aplikasi authenticator
+pub fn provisioning_uri(account: &str, secret: &str) -> String {
+    let issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "Onboarding".to_string());
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        encode_uri_component(&issuer), encode_uri_component(account), secret, encode_uri_component(&issuer), DIGITS, PERIOD
+    )
+}
+
+/// 🖼️ QR code dari provisioning URI dalam bentuk data URI SVG
+pub fn qr_code(uri: &str) -> Result<String, String> {
+    let code = QrCode::new(uri.as_bytes()).map_err(|e| format!("Failed to create QR code: {}", e))?;
+    let image = code.render::<svg::Color>().min_dimensions(200, 200).build();
+    Ok(format!("data:image/svg+xml;base64,{}", STANDARD.encode(image)))
+}
+
+fn encode_uri_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
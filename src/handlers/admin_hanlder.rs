@@ -1,20 +1,22 @@
 use std::collections::HashMap;
-use actix_identity::Identity;
-use actix_web::{get, post, web, HttpResponse, Responder, Scope};
+use actix_web::{body::{BoxBody, EitherBody}, dev::{ServiceFactory, ServiceRequest, ServiceResponse}, get, post, web, Error, HttpRequest, HttpResponse, Responder, Scope};
 use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
 use validator::{Validate, ValidationError};
 
 use crate::{
     contexts::{
-        jwt_session::validate_jwt, 
-        model::{ActionResult, CIFFileRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, ResultList, TableDataParams, UserInfo}}, 
-    services::{admin_service::AdminService, file_service::FileService, validation_service::validator::format_validation_errors}
+        jwt_session::Claims, 
+        rbac::{Permission, RequirePermission},
+        model::{ActionResult, AssignRoleRequest, CIFFileRequest, ClosureListParams, ClosureReviewRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, ImpersonateRequest, KycReviewRequest, ResultList, TableDataParams, UnlockIpRequest, UserInfo}}, 
+    services::{account_closure_service::AccountClosureService, admin_service::AdminService, file_service::FileService, generic_service::GenericService, impersonation_service::ImpersonationService, kyc_review_service::KycReviewService, login_guard_service::LoginGuardService, profile_change_service::ProfileChangeService, role_service::RoleService, session_service::SessionService, validation_service::validator::format_validation_errors}
 };
 
-pub fn admin_scope() -> Scope {
+pub fn admin_scope() -> Scope<impl ServiceFactory<ServiceRequest, Config = (), Response = ServiceResponse<EitherBody<BoxBody>>, Error = Error, InitError = ()>> {
     
+    // 🛡️ Semua route admin minimal butuh `admin.access`, tiap route bisa minta permission tambahan
     web::scope("/admin")
+        .wrap(RequirePermission::new(Permission::AdminAccess))
         .service(data_pribadi)
         .service(data_bank)
         .service(data_pekerjaan)
@@ -23,101 +25,436 @@ pub fn admin_scope() -> Scope {
         .service(data_beneficiary)
         .service(data_cif_file)
         .service(get_table_data)
+        .service(force_sign_out)
+        .service(assign_roles)
+        .service(unlock_user)
+        .service(unlock_ip)
+        .service(user_sessions)
+        .service(account_closures)
+        .service(approve_account_closure)
+        .service(reject_account_closure)
+        .service(impersonate_user)
+        .service(impersonations)
+        .service(end_impersonation)
+        .service(kyc_reviews_pending)
+        .service(kyc_review_history)
+        .service(review_kyc)
+        .service(profile_changes)
+        .service(approve_profile_change)
+        .service(reject_profile_change)
+        .service(kyc_versions)
 }
 
-#[get("/get-table")]
-async fn get_table_data(params: web::Query<TableDataParams>, pool: web::Data<Pool<ConnectionManager>>, session: Option<Identity>) -> impl Responder {
+#[get("/kyc-reviews/pending", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn kyc_reviews_pending(pool: web::Data<Pool<ConnectionManager>>, _claims: Claims) -> impl Responder {
+
+    match KycReviewService::pending(pool).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/users/{auth_usernid}/kyc-review", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn kyc_review_history(pool: web::Data<Pool<ConnectionManager>>, _claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match KycReviewService::history(pool, auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/users/{auth_usernid}/kyc-review", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn review_kyc(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<KycReviewRequest>, claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    match KycReviewService::decide(pool, auth_usernid, claims.auth_usernid, request.into_inner()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/users/{auth_usernid}/impersonate", wrap = "RequirePermission::new(Permission::UserImpersonate)")]
+async fn impersonate_user(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ImpersonateRequest>, claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let ip_address = GenericService::get_ip_address(&req);
+    match ImpersonationService::start(pool, claims, auth_usernid, request.into_inner(), &ip_address).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/impersonations", wrap = "RequirePermission::new(Permission::UserImpersonate)")]
+async fn impersonations(pool: web::Data<Pool<ConnectionManager>>, _claims: Claims) -> impl Responder {
+
+    match ImpersonationService::list_active(pool).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/impersonations/{impersonation_id}/end", wrap = "RequirePermission::new(Permission::UserImpersonate)")]
+async fn end_impersonation(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, impersonation_id: web::Path<String>) -> impl Responder {
+
+    let impersonation_id: i64 = match GenericService::parse_param(&impersonation_id.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match ImpersonationService::end(pool, claims.auth_usernid, impersonation_id).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/profile-changes", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn profile_changes(params: web::Query<ClosureListParams>, pool: web::Data<Pool<ConnectionManager>>, _claims: Claims) -> impl Responder {
+
+    match ProfileChangeService::list(pool, None, params.into_inner().status).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/profile-changes/{change_nid}/approve", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn approve_profile_change(pool: web::Data<Pool<ConnectionManager>>, request: Option<web::Json<ClosureReviewRequest>>, claims: Claims, change_nid: web::Path<String>) -> impl Responder {
+
+    let change_nid: i32 = match GenericService::parse_param(&change_nid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    let note = match review_note(request) {
+        Ok(note) => note,
+        Err(response) => return response,
+    };
+
+    match ProfileChangeService::approve(pool, change_nid, claims.auth_usernid, note).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/profile-changes/{change_nid}/reject", wrap = "RequirePermission::new(Permission::KycReview)")]
+async fn reject_profile_change(pool: web::Data<Pool<ConnectionManager>>, request: Option<web::Json<ClosureReviewRequest>>, claims: Claims, change_nid: web::Path<String>) -> impl Responder {
+
+    let change_nid: i32 = match GenericService::parse_param(&change_nid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    let note = match review_note(request) {
+        Ok(note) => note,
+        Err(response) => return response,
+    };
+
+    match ProfileChangeService::reject(pool, change_nid, claims.auth_usernid, note).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/users/{auth_usernid}/kyc-versions", wrap = "RequirePermission::new(Permission::KycRead)")]
+async fn kyc_versions(pool: web::Data<Pool<ConnectionManager>>, _claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match ProfileChangeService::versions(pool, auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/account-closures", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn account_closures(params: web::Query<ClosureListParams>, pool: web::Data<Pool<ConnectionManager>>, _claims: Claims) -> impl Responder {
+
+    match AccountClosureService::list(pool, params.into_inner().status).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/account-closures/{closure_nid}/approve", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn approve_account_closure(pool: web::Data<Pool<ConnectionManager>>, request: Option<web::Json<ClosureReviewRequest>>, claims: Claims, closure_nid: web::Path<String>) -> impl Responder {
+
+    let closure_nid: i64 = match GenericService::parse_param(&closure_nid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    let note = match review_note(request) {
+        Ok(note) => note,
+        Err(response) => return response,
+    };
+
+    // ⚠️ Tidak bisa dibatalkan: data pribadi & dokumen user langsung dihapus
+    match AccountClosureService::approve(pool, closure_nid, claims.auth_usernid, note).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/account-closures/{closure_nid}/reject", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn reject_account_closure(pool: web::Data<Pool<ConnectionManager>>, request: Option<web::Json<ClosureReviewRequest>>, claims: Claims, closure_nid: web::Path<String>) -> impl Responder {
+
+    let closure_nid: i64 = match GenericService::parse_param(&closure_nid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    let note = match review_note(request) {
+        Ok(note) => note,
+        Err(response) => return response,
+    };
+
+    match AccountClosureService::reject(pool, closure_nid, claims.auth_usernid, note).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+/// Catatan review opsional, body boleh tidak dikirim
+fn review_note(request: Option<web::Json<ClosureReviewRequest>>) -> Result<Option<String>, HttpResponse> {
+    let Some(request) = request else { return Ok(None) };
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return Err(HttpResponse::BadRequest().json(result));
+    }
+
+    Ok(request.into_inner().note.filter(|note| !note.trim().is_empty()))
+}
+
+#[get("/users/{auth_usernid}/sessions", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn user_sessions(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    // `current` hanya true jika admin melihat sesinya sendiri
+    match SessionService::list(pool, auth_usernid, claims.sid.as_deref()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/users/{auth_usernid}/unlock", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn unlock_user(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match LoginGuardService::unlock_user(pool, auth_usernid, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/lockouts/ip/unlock", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn unlock_ip(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, request: web::Json<UnlockIpRequest>) -> impl Responder {
+
+    let ip_address = request.into_inner().ip_address.unwrap_or_default().trim().to_string();
+    if ip_address.is_empty() {
+        let result: ActionResult<(), String> = ActionResult {
+            message: "IP address is required".to_string(),
+            ..Default::default()
+        };
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    match LoginGuardService::unlock_ip(pool, &ip_address, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/users/{auth_usernid}/roles", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn assign_roles(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<AssignRoleRequest>, claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match RoleService::assign_roles(pool, auth_usernid, request.into_inner().roles, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/users/{auth_usernid}/sign-out", wrap = "RequirePermission::new(Permission::UserManage)")]
+async fn force_sign_out(pool: web::Data<Pool<ConnectionManager>>, _claims: Claims, auth_usernid: web::Path<String>) -> impl Responder {
+
+    let auth_usernid: i32 = match GenericService::parse_param(&auth_usernid.into_inner()) {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+
+    match AdminService::force_sign_out(pool, auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/get-table", wrap = "RequirePermission::new(Permission::TableRead)")]
+async fn get_table_data(params: web::Query<TableDataParams>, pool: web::Data<Pool<ConnectionManager>>, _claims: Claims) -> impl Responder {
 
     let mut result = ActionResult::default();
 
-    match session.map(|id| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(_) => {
-                    let data: Result<ResultList, Box<dyn std::error::Error>> = AdminService::get_table_data(params.into_inner(), pool).await;
-
-                    match data {
-                        Ok(response) => {
-                            result.result = true;
-                            result.message = "Retrieve data success".to_string();
-                            result.data = Some(response);
-                            return HttpResponse::Ok().json(result);
-                        },
-                        Err(e) => {
-                            result.result = true;
-                            result.message = "Session active".to_string();
-                            result.error = Some(e.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                        
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
+    let data: Result<ResultList, Box<dyn std::error::Error>> = AdminService::get_table_data(params.into_inner(), pool).await;
+
+    match data {
+        Ok(response) => {
+            result.result = true;
+            result.message = "Retrieve data success".to_string();
+            result.data = Some(response);
+            return HttpResponse::Ok().json(result);
         },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+        Err(e) => {
+            result.result = true;
+            result.message = "Session active".to_string();
+            result.error = Some(e.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
+        
     }
 }
 
-#[get("/userinfo")]
-pub async fn get_user_info(pool: web::Data<Pool<ConnectionManager>>, session: Option<Identity>) -> impl Responder {
+#[get("/userinfo", wrap = "RequirePermission::new(Permission::KycRead)")]
+pub async fn get_user_info(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
 
     let mut result: ActionResult<UserInfo, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-
-                    let data: ActionResult<UserInfo, _> = AdminService::get_user_info(pool, claims).await;
-                    
-                    result.result = data.result;
-                    result.message = data.message;
-                    result.data = Some(data.data.unwrap());
-                    result.error = data.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => {
-                            HttpResponse::Ok().json(response)
-                        }, 
-                        response => {
-                            HttpResponse::BadRequest().json(response)
-                        }
-                    }
-                    
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let data: ActionResult<UserInfo, _> = AdminService::get_user_info(pool, claims).await;
+    
+    result.result = data.result;
+    result.message = data.message;
+    result.data = Some(data.data.unwrap());
+    result.error = data.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => {
+            HttpResponse::Ok().json(response)
+        }, 
+        response => {
+            HttpResponse::BadRequest().json(response)
+        }
     }
 }
 
-#[post("/save-cif-file")]
-async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<CIFFileRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/save-cif-file", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<CIFFileRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -134,67 +471,47 @@ async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::J
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
+    let mut request: CIFFileRequest = request.clone(); // Ubah menjadi mutable
+    match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.idcard_file, "KTP").await {
+        Ok(saved_path) => request.idcard_file = saved_path,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let mut request: CIFFileRequest = request.clone(); // Ubah menjadi mutable
-                    match FileService::save_base64_image(&claims.email, &request.idcard_file, "KTP") {
-                        Ok(saved_path) => request.idcard_file = saved_path,
-                        Err(err) => {
-                            result.error = Some(err.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                    }
-                    match FileService::save_base64_image(&claims.email, &request.selfie_file, "Selfie") {
-                        Ok(saved_path) => request.selfie_file = saved_path,
-                        Err(err) => {
-                            result.error = Some(err.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                    }
-                    match FileService::save_base64_image(&claims.email, &request.signature_file, "Signature") {
-                        Ok(saved_path) => request.signature_file = saved_path,
-                        Err(err) => {
-                            result.error = Some(err.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                    }
-
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_cif_file(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
+    }
+    match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.selfie_file, "Selfie").await {
+        Ok(saved_path) => request.selfie_file = saved_path,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+    }
+    match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.signature_file, "Signature").await {
+        Ok(saved_path) => request.signature_file = saved_path,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
     }
+
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_cif_file(pool, request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
 }
 
-#[post("/data-pribadi")]
-async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPribadiRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/data-pribadi", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPribadiRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -211,46 +528,26 @@ async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Js
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let request: DataPribadiRequest = request.clone(); // Ubah menjadi mutable
-
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pribadi(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let request: DataPribadiRequest = request.clone(); // Ubah menjadi mutable
+
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pribadi(pool, request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
-#[post("/data-bank")]
-async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBankRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/data-bank", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBankRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -267,44 +564,24 @@ async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_bank(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_bank(pool, request.into_inner(), claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
-#[post("/data-pekerjaan")]
-async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPekerjaanRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/data-pekerjaan", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPekerjaanRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -321,54 +598,34 @@ async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let mut request: DataPekerjaanRequest = request.clone(); // Ubah menjadi mutable
-
-                    match FileService::save_base64_image(&claims.email, &request.npwp_file, "NPWP") {
-                        Ok(saved_path) => request.npwp_file = saved_path,
-                        Err(err) => {
-                            result.error = Some(err.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                    }
-
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pekerjaan(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+    let mut request: DataPekerjaanRequest = request.clone(); // Ubah menjadi mutable
+
+    match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.npwp_file, "NPWP").await {
+        Ok(saved_path) => request.npwp_file = saved_path,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
     }
+
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pekerjaan(pool, request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
 }
 
-#[post("/data-pendukung")]
-async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPendukungRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/data-pendukung", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPendukungRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -428,44 +685,24 @@ async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::
         return HttpResponse::BadRequest().json(result);
     }
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pendukung(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_pendukung(pool, request.into_inner(), claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
-#[post("/beneficiary-owner")]
-async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBeneficiaryRequest>, session: Option<Identity>) -> impl Responder {
+#[post("/beneficiary-owner", wrap = "RequirePermission::new(Permission::KycWrite)")]
+async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBeneficiaryRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -482,39 +719,19 @@ async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_beneficiary(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = AdminService::save_data_beneficiary(pool, request.into_inner(), claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
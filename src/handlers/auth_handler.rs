@@ -1,115 +1,537 @@
 use actix_identity::Identity;
+use std::collections::HashMap;
 use actix_web::{cookie::{time, Cookie, SameSite}, get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder, Scope};
 use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
 use crate::{
-    contexts::{jwt_session::{create_jwt, validate_jwt}, 
+    contexts::{keyring::jwt_keyring, jwt_session::{access_token_ttl, create_jwt, validate_mfa_token, Claims}, 
     logger::write_log, 
-    model::{ActionResult, ChangePasswordRequest, LoginRequest, RegisterRequest, ResetPasswordRequest, WebUser}}, 
-    services::{auth_service::AuthService, generic_service::GenericService}
+    password_policy::PasswordPolicy,
+    oidc::provider_names,
+    model::{ActionResult, ChangeEmailRequest, ChangePasswordRequest, OidcCallbackQuery, PasswordViolation, LoginRequest, LoginResponse, MfaChallenge, RefreshTokenRequest, RegisterRequest, ResendActivationRequest, ResetPasswordRequest, TwoFactorCodeRequest, TwoFactorLoginRequest, WebUser}}, 
+    services::{auth_service::AuthService, email_change_service::EmailChangeService, generic_service::GenericService, login_guard_service::LoginGuardService, oidc_service::OidcService, revocation_service::RevocationService, session_service::SessionService, token_service::{RotatedToken, TokenService}, two_factor_service::TwoFactorService, validation_service::validator::format_validation_errors}
 };
+use validator::Validate;
 
 pub fn auth_scope() -> Scope {
     
     web::scope("/auth")
         .service(login)
+        .service(login_two_factor)
+        .service(enroll_two_factor)
+        .service(verify_two_factor)
         .service(register)
         .service(check_session)
+        .service(refresh_session)
         .service(logout)
         .service(activation_user)
+        .service(resend_activation)
         .service(forget_password)
         .service(change_password)
+        .service(change_email)
+        .service(confirm_change_email)
+        .service(jwks)
+        .service(oidc_providers)
+        .service(oidc_login)
+        .service(oidc_callback)
+        .service(list_sessions)
+        .service(revoke_all_sessions)
+        .service(revoke_session)
+}
+
+/// 🌐 Public key untuk verifikasi access token oleh service lain (RS256 / EdDSA)
+#[get("/.well-known/jwks.json")]
+async fn jwks() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(jwt_keyring().jwks())
 }
 
 #[post("/login")]
 async fn login(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<LoginRequest>) -> impl Responder {
 
-    let result: ActionResult<WebUser, _> = AuthService::login(pool, request.into_inner()).await;
+    let email = request.email.clone().unwrap_or_default();
+    let ip_address = GenericService::get_ip_address(&req);
+
+    // 🚦 Tolak lebih awal jika email / IP sedang lockout atau masih dalam masa backoff
+    match LoginGuardService::check(pool.clone(), &email, &ip_address).await {
+        Ok(Some(retry_after)) => {
+            let result: ActionResult<(), String> = ActionResult {
+                message: "Too many login attempts".to_string(),
+                error: Some(format!("Try again in {} seconds", retry_after)),
+                ..Default::default()
+            };
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        // Status lockout tidak bisa dicek → tolak, jangan sampai brute-force lolos saat database bermasalah
+        Err(err) => {
+            write_log("ERROR", err.as_str());
+            let result: ActionResult<(), String> = ActionResult {
+                message: "Login temporarily unavailable".to_string(),
+                ..Default::default()
+            };
+            return HttpResponse::ServiceUnavailable().json(result);
+        },
+    }
+
+    let result: ActionResult<WebUser, _> = AuthService::login(pool.clone(), request.into_inner()).await;
 
     match result {
         response if response.error.is_some() => {
             HttpResponse::InternalServerError().json(response)
         }, // Jika error, HTTP 500
         response if response.result => {
-            if let Some(user) = &response.data {
-                // ✅ Buat token JWT
-                match create_jwt(user.clone()) {
-                    Ok(token) => {
-                        Identity::login(&req.extensions(), token.clone()).unwrap(); // ✅ Simpan sesi
-
-                        // ✅ Simpan token dalam cookie
-                        let cookie = Cookie::build("token", token.clone())
-                            .path("/")
-                            .http_only(true)
-                            .same_site(SameSite::Strict)
-                            .secure(false) // Ubah ke `true` jika pakai HTTPS
-                            .finish();
-
-                        return HttpResponse::Ok()
-                            .cookie(cookie)
-                            .json(response);
-                    }
+            if let Err(err) = LoginGuardService::record_success(pool.clone(), &email, &ip_address).await {
+                write_log("ERROR", err.as_str());
+            }
+
+            if let Some(user) = response.data.clone() {
+                // ⏳ Password melewati `PASSWORD_MAX_AGE_DAYS` → wajib reset dulu sebelum dapat sesi
+                let policy = PasswordPolicy::from_env();
+                if policy.is_expired(user.password_changed_at) {
+                    let result: ActionResult<Vec<PasswordViolation>, String> = ActionResult {
+                        message: "Password expired, please reset your password".to_string(),
+                        data: Some(vec![policy.expired_violation()]),
+                        ..Default::default()
+                    };
+                    return HttpResponse::Forbidden().json(result);
+                }
+
+                // 🔐 2FA aktif → belum boleh dapat JWT, kirim token challenge untuk `/login/2fa`
+                match TwoFactorService::is_enabled(pool.clone(), user.auth_usernid).await {
+                    Ok(true) => return mfa_challenge(pool, user.auth_usernid, &user.email).await,
+                    Ok(false) => {},
                     Err(err) => {
-                        write_log("ERROR", format!("Failed to create JWT: {}", err).as_str());
-                        return HttpResponse::InternalServerError().json(response);
+                        write_log("ERROR", err.as_str());
+                        let result: ActionResult<(), String> = ActionResult { error: Some(err), ..Default::default() };
+                        return HttpResponse::InternalServerError().json(result);
                     }
                 }
+
+                // ✅ Login baru = sesi baru (refresh token family baru)
+                return match SessionService::start(pool, user.auth_usernid, false, &ip_address, &GenericService::get_device_info(&req)).await {
+                    Ok((session_id, refresh_token)) => sign_in(&req, WebUser { session_id: Some(session_id), ..user }, refresh_token, response.message),
+                    Err(err) => {
+                        write_log("ERROR", format!("Failed to issue refresh token: {}", err).as_str());
+                        HttpResponse::InternalServerError().json(response)
+                    }
+                };
             }
 
             HttpResponse::BadRequest().json(response) // Jika tidak ada user, return 400
         },
-        response => HttpResponse::BadRequest().json(response), // Jika gagal login, HTTP 400
+        response => {
+            // Gagal yang tidak tercatat tidak ikut lockout → tolak seperti `check` yang error
+            if let Err(err) = LoginGuardService::record_failure(pool, &email, &ip_address, &GenericService::get_device_info(&req)).await {
+                write_log("ERROR", err.as_str());
+                let result: ActionResult<(), String> = ActionResult {
+                    message: "Login temporarily unavailable".to_string(),
+                    ..Default::default()
+                };
+                return HttpResponse::ServiceUnavailable().json(result);
+            }
+            HttpResponse::BadRequest().json(response) // Jika gagal login, HTTP 400
+        },
     }
 }
 
-#[get("/session")]
-async fn check_session(identity: Option<Identity>) -> impl Responder {
+/// Cookie pengikat `state` ke browser yang memulai login (mencegah login CSRF).
+/// `Lax` supaya tetap terkirim saat redirect balik dari provider
+const OIDC_STATE_COOKIE: &str = "oidc_state";
 
-    let mut result = ActionResult::default();
+#[get("/oidc/providers")]
+async fn oidc_providers() -> impl Responder {
+    let result: ActionResult<Vec<String>, String> = ActionResult {
+        result: true,
+        message: "OIDC providers".to_string(),
+        data: Some(provider_names()),
+        error: None,
+    };
+    HttpResponse::Ok().json(result)
+}
 
-    match identity.map(|id| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
+#[get("/oidc/{provider}/login")]
+async fn oidc_login(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, provider: web::Path<String>) -> impl Responder {
+
+    let result = OidcService::start(pool, &provider.into_inner(), &GenericService::get_ip_address(&req)).await;
+
+    match result {
+        response if response.error.is_some() => {
+            write_log("ERROR", response.error.clone().unwrap_or_default().as_str());
+            HttpResponse::InternalServerError().json(response)
+        }, // Jika error, HTTP 500
+        response if response.result => {
+            let Some(redirect) = response.data else {
+                return HttpResponse::InternalServerError().finish();
+            };
+
+            let cookie = Cookie::build(OIDC_STATE_COOKIE, redirect.state)
+                .path("/")
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .secure(false) // Ubah ke `true` jika pakai HTTPS
+                .max_age(time::Duration::minutes(10))
+                .finish();
+
+            HttpResponse::Found()
+                .insert_header(("Location", redirect.authorization_url))
+                .cookie(cookie)
+                .finish()
         },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    result.result = true;
-                    result.message = "Session active".to_string();
-                    result.data = Some(claims);
-                    return HttpResponse::Ok().json(result);
+        response => HttpResponse::NotFound().json(response), // Provider tidak dikenal
+    }
+}
+
+#[get("/oidc/{provider}/callback")]
+async fn oidc_callback(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, provider: web::Path<String>, query: web::Query<OidcCallbackQuery>) -> impl Responder {
+
+    let mut result: ActionResult<(), String> = ActionResult::default();
+    let query = query.into_inner();
+
+    if let Some(error) = query.error {
+        result.message = "Login cancelled by provider".to_string();
+        result.error = Some(error);
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        result.message = "Missing code or state".to_string();
+        return HttpResponse::BadRequest().json(result);
+    };
+
+    // State dari provider harus sama dengan cookie browser yang memulai login
+    if req.cookie(OIDC_STATE_COOKIE).map(|cookie| cookie.value().to_string()) != Some(state.clone()) {
+        result.message = "Invalid or expired login state".to_string();
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let ip_address = GenericService::get_ip_address(&req);
+    let mut response = match OidcService::finish(pool.clone(), &provider.into_inner(), &code, &state, &ip_address).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, // Jika error, HTTP 500
+        response if response.result => {
+            let Some(user) = response.data else {
+                return HttpResponse::InternalServerError().finish();
+            };
+
+            // 🔐 Login lewat provider tetap wajib faktor kedua jika 2FA aktif
+            match TwoFactorService::is_enabled(pool.clone(), user.auth_usernid).await {
+                Ok(true) => mfa_challenge(pool, user.auth_usernid, &user.email).await,
+                Ok(false) => match SessionService::start(pool, user.auth_usernid, false, &ip_address, &GenericService::get_device_info(&req)).await {
+                    Ok((session_id, refresh_token)) => sign_in(&req, WebUser { session_id: Some(session_id), ..user }, refresh_token, response.message),
+                    Err(err) => {
+                        write_log("ERROR", format!("Failed to issue refresh token: {}", err).as_str());
+                        result.error = Some(err);
+                        HttpResponse::InternalServerError().json(result)
+                    }
                 },
                 Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
+                    write_log("ERROR", err.as_str());
+                    result.error = Some(err);
+                    HttpResponse::InternalServerError().json(result)
+                }
             }
         },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    };
+
+    // State sudah terpakai, cookie tidak diperlukan lagi
+    let _ = response.add_cookie(&expired_cookie(OIDC_STATE_COOKIE));
+    response
+}
+
+#[post("/login/2fa")]
+async fn login_two_factor(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<TwoFactorLoginRequest>) -> impl Responder {
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let mut result: ActionResult<(), String> = ActionResult::default();
+    let request = request.into_inner();
+
+    let challenge = match validate_mfa_token(&request.mfa_token) {
+        Ok(challenge) => challenge,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::Unauthorized().json(result);
+        }
+    };
+    let ip_address = GenericService::get_ip_address(&req);
+
+    // 🚦 Kode salah dihitung bersama password salah, lockout berlaku untuk kedua langkah
+    match LoginGuardService::check(pool.clone(), &challenge.email, &ip_address).await {
+        Ok(Some(retry_after)) => {
+            result.message = "Too many login attempts".to_string();
+            result.error = Some(format!("Try again in {} seconds", retry_after));
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        Err(err) => {
+            write_log("ERROR", err.as_str());
+            result.message = "Login temporarily unavailable".to_string();
+            return HttpResponse::ServiceUnavailable().json(result);
+        }
+    }
+
+    match TwoFactorService::start_attempt(pool.clone(), &challenge).await {
+        Ok(true) => {},
+        Ok(false) => {
+            result.message = "Two-factor challenge expired or used, please login again".to_string();
+            return HttpResponse::Unauthorized().json(result);
+        },
+        Err(err) => {
+            write_log("ERROR", err.as_str());
+            result.error = Some(err);
+            return HttpResponse::InternalServerError().json(result);
+        }
+    }
+
+    match TwoFactorService::verify_login(pool.clone(), &challenge, request.code).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => {
+            if let Err(err) = LoginGuardService::record_success(pool.clone(), &challenge.email, &ip_address).await {
+                write_log("ERROR", err.as_str());
+            }
+
+            let user = response.data.unwrap();
+            match SessionService::start(pool, user.auth_usernid, true, &ip_address, &GenericService::get_device_info(&req)).await {
+                Ok((session_id, refresh_token)) => sign_in(&req, WebUser { session_id: Some(session_id), ..user }, refresh_token, response.message),
+                Err(err) => {
+                    write_log("ERROR", format!("Failed to issue refresh token: {}", err).as_str());
+                    result.error = Some(err);
+                    HttpResponse::InternalServerError().json(result)
+                }
+            }
+        },
+        response => {
+            if let Err(err) = LoginGuardService::record_failure(pool, &challenge.email, &ip_address, &GenericService::get_device_info(&req)).await {
+                write_log("ERROR", err.as_str());
+                result.message = "Login temporarily unavailable".to_string();
+                return HttpResponse::ServiceUnavailable().json(result);
+            }
+            HttpResponse::BadRequest().json(response)
+        },
+    }
+}
+
+#[post("/2fa/enroll")]
+async fn enroll_two_factor(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match TwoFactorService::enroll(pool, claims).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
         },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/2fa/verify")]
+async fn verify_two_factor(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<TwoFactorCodeRequest>, claims: Claims) -> impl Responder {
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    match TwoFactorService::verify_enrollment(pool, claims.auth_usernid, request.into_inner().code).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/refresh")]
+async fn refresh_session(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: Option<web::Json<RefreshTokenRequest>>) -> impl Responder {
+
+    let mut result: ActionResult<LoginResponse, String> = ActionResult::default();
+
+    // Mobile kirim lewat body, web lewat cookie `refresh_token`
+    let token = request
+        .and_then(|body| body.into_inner().refresh_token)
+        .or_else(|| req.cookie("refresh_token").map(|c| c.value().to_string()));
+
+    let token = match token {
+        Some(token) if !token.is_empty() => token,
+        _ => {
+            result.error = Some("Refresh token not found".to_string());
+            return HttpResponse::Unauthorized().json(result);
+        }
     };
+
+    let rotated: ActionResult<RotatedToken, String> = TokenService::rotate_refresh_token(pool, token, GenericService::get_ip_address(&req)).await;
+
+    match rotated {
+        response if response.error.is_some() => {
+            result.error = response.error;
+            HttpResponse::InternalServerError().json(result)
+        },
+        response if response.result => {
+            let rotated = response.data.unwrap();
+            sign_in(&req, rotated.user, rotated.refresh_token, response.message)
+        },
+        response => {
+            result.message = response.message;
+            HttpResponse::Unauthorized()
+                .cookie(expired_cookie("refresh_token"))
+                .json(result)
+        },
+    }
 }
 
-#[post("/logout")]
-async fn logout(id: Identity) -> impl Responder {
-    // Hapus sesi dari actix-identity
-    
-    id.logout();
+/// ✅ Buat access token, simpan sesi & cookie, lalu kirim pasangan token ke client
+fn sign_in(req: &HttpRequest, user: WebUser, refresh_token: String, message: String) -> HttpResponse {
 
-    // Hapus cookie dengan setting expired date
-    let cookie = Cookie::build("token", "")
+    let mut result: ActionResult<LoginResponse, String> = ActionResult::default();
+
+    match create_jwt(user.clone()) {
+        Ok(token) => {
+            Identity::login(&req.extensions(), token.clone()).unwrap(); // ✅ Simpan sesi
+
+            // ✅ Simpan token dalam cookie
+            let cookie = Cookie::build("token", token.clone())
+                .path("/")
+                .http_only(true)
+                .same_site(SameSite::Strict)
+                .secure(false) // Ubah ke `true` jika pakai HTTPS
+                .finish();
+
+            let refresh_cookie = Cookie::build("refresh_token", refresh_token.clone())
+                .path("/")
+                .http_only(true)
+                .same_site(SameSite::Strict)
+                .secure(false) // Ubah ke `true` jika pakai HTTPS
+                .max_age(time::Duration::seconds(TokenService::refresh_token_ttl().num_seconds()))
+                .finish();
+
+            result.result = true;
+            result.message = message;
+            result.data = Some(LoginResponse {
+                user,
+                access_token: token,
+                refresh_token,
+                token_type: "Bearer".to_string(),
+                expires_in: access_token_ttl().num_seconds(),
+            });
+
+            HttpResponse::Ok()
+                .cookie(cookie)
+                .cookie(refresh_cookie)
+                .json(result)
+        }
+        Err(err) => {
+            write_log("ERROR", format!("Failed to create JWT: {}", err).as_str());
+            result.error = Some(err.to_string());
+            HttpResponse::InternalServerError().json(result)
+        }
+    }
+}
+
+/// 🔐 Password benar tapi 2FA aktif: kirim token challenge, bukan JWT
+async fn mfa_challenge(pool: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, email: &str) -> HttpResponse {
+
+    let mut result: ActionResult<MfaChallenge, String> = ActionResult::default();
+
+    match TwoFactorService::issue_challenge(pool, auth_usernid, email).await {
+        Ok(challenge) => {
+            result.result = true;
+            result.message = "Two-factor authentication required".to_string();
+            result.data = Some(challenge);
+            HttpResponse::Ok().json(result)
+        }
+        Err(err) => {
+            write_log("ERROR", err.as_str());
+            result.error = Some(err);
+            HttpResponse::InternalServerError().json(result)
+        }
+    }
+}
+
+fn expired_cookie(name: &str) -> Cookie<'_> {
+    Cookie::build(name, "")
         .path("/")
         .http_only(true)
         .same_site(SameSite::Strict)
         .secure(false) // Ubah ke true jika pakai HTTPS
         .max_age(time::Duration::days(-1)) // Set expired
-        .finish();
+        .finish()
+}
+
+#[get("/session")]
+async fn check_session(claims: Claims) -> impl Responder {
+
+    let result: ActionResult<Claims, String> = ActionResult {
+        result: true,
+        message: "Session active".to_string(),
+        data: Some(claims),
+        error: None,
+    };
+
+    HttpResponse::Ok().json(result)
+}
+
+#[post("/logout")]
+async fn logout(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, claims: Option<Claims>, id: Option<Identity>, request: Option<web::Json<RefreshTokenRequest>>) -> impl Responder {
+    // 🚫 Cabut access token yang sedang dipakai (cookie atau Bearer) supaya salinannya tidak bisa dipakai lagi
+    if let Some(claims) = claims {
+        if let Err(err) = RevocationService::revoke_token(pool.clone(), &claims, "logout").await {
+            write_log("ERROR", err.as_str());
+        }
+        // Tandai sesinya berakhir supaya tidak muncul lagi di daftar sesi aktif
+        if let Some(session_id) = claims.sid.as_deref() {
+            if let Some(err) = SessionService::revoke(pool.clone(), claims.auth_usernid, session_id, "logout").await.error {
+                write_log("ERROR", err.as_str());
+            }
+        }
+    }
+
+    // Hapus sesi dari actix-identity (hanya ada untuk login web)
+    if let Some(id) = id {
+        id.logout();
+    }
 
+    // Cabut refresh token family milik sesi ini. Mobile kirim lewat body, web lewat cookie `refresh_token`
+    let refresh_token = request
+        .and_then(|body| body.into_inner().refresh_token)
+        .or_else(|| req.cookie("refresh_token").map(|c| c.value().to_string()));
+    if let Some(refresh_token) = refresh_token.filter(|token| !token.is_empty()) {
+        if let Err(err) = TokenService::revoke_by_token(pool, &refresh_token).await {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    // Hapus cookie dengan setting expired date
     HttpResponse::Ok()
-        .cookie(cookie) // Hapus cookie dengan expired
+        .cookie(expired_cookie("token")) // Hapus cookie dengan expired
+        .cookie(expired_cookie("refresh_token"))
         .json(serde_json::json!({
             "result": true,
             "message": "Logout successful, cookie deleted"
@@ -121,6 +543,10 @@ async fn register(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, mu
 
     request.app_ipaddress = GenericService::get_ip_address(&req);
 
+    if let Some(response) = password_policy_failed(request.password.as_deref().unwrap_or_default()) {
+        return response;
+    }
+
     let result: ActionResult<(), _> = AuthService::register(pool, request.into_inner()).await;
 
     match result {
@@ -132,6 +558,21 @@ async fn register(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, mu
     }
 }
 
+/// 📜 Tolak password yang melanggar aturan statis policy, semua pelanggaran dikirim di `data`
+fn password_policy_failed(password: &str) -> Option<HttpResponse> {
+    let violations = PasswordPolicy::from_env().check(password);
+    if violations.is_empty() {
+        return None;
+    }
+
+    let result: ActionResult<Vec<PasswordViolation>, String> = ActionResult {
+        message: "Password does not meet policy".to_string(),
+        data: Some(violations),
+        ..Default::default()
+    };
+    Some(HttpResponse::BadRequest().json(result))
+}
+
 #[get("/activation/{otp_link}")]
 async fn activation_user(pool: web::Data<Pool<ConnectionManager>>, otp_link: web::Path<String>) -> impl Responder {
 
@@ -146,6 +587,52 @@ async fn activation_user(pool: web::Data<Pool<ConnectionManager>>, otp_link: web
     }
 }
 
+#[post("/resend-activation")]
+async fn resend_activation(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ResendActivationRequest>) -> impl Responder {
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    // 🚦 Batasi kirim ulang per email & IP
+    match AuthService::resend_activation_retry_after(pool.clone(), request.email.as_deref().unwrap_or_default(), &GenericService::get_ip_address(&req)).await {
+        Ok(Some(retry_after)) => {
+            let result: ActionResult<(), String> = ActionResult {
+                message: "Too many requests".to_string(),
+                error: Some(format!("Try again in {} seconds", retry_after)),
+                ..Default::default()
+            };
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        Err(err) => {
+            let result: ActionResult<(), String> = ActionResult { error: Some(err), ..Default::default() };
+            return HttpResponse::InternalServerError().json(result);
+        },
+    }
+
+    let result: ActionResult<(), _> = AuthService::resend_activation(pool, request.into_inner()).await;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, // Jika error, HTTP 500
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
 #[post("/reset-password")]
 async fn forget_password(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ResetPasswordRequest>) -> impl Responder {
 
@@ -163,7 +650,11 @@ async fn forget_password(pool: web::Data<Pool<ConnectionManager>>, request: web:
 #[post("/change-password")]
 async fn change_password(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ChangePasswordRequest>) -> impl Responder {
 
-    let result: ActionResult<(), _> = AuthService::change_password(pool, request.into_inner()).await;
+    if let Some(response) = password_policy_failed(request.password.as_deref().unwrap_or_default()) {
+        return response;
+    }
+
+    let result: ActionResult<Vec<PasswordViolation>, _> = AuthService::change_password(pool, request.into_inner()).await;
 
     match result {
         response if response.error.is_some() => {
@@ -172,4 +663,111 @@ async fn change_password(pool: web::Data<Pool<ConnectionManager>>, request: web:
         response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
         response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
-}
\ No newline at end of file
+}
+
+#[post("/change-email")]
+async fn change_email(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ChangeEmailRequest>, claims: Claims) -> impl Responder {
+
+    let mut result: ActionResult<(), String> = ActionResult::default();
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let ip_address = GenericService::get_ip_address(&req);
+
+    // 🚦 Password dicek ulang di sini, jadi ikut aturan lockout login + batas permintaan per jam
+    let retry_after = match LoginGuardService::check(pool.clone(), &claims.email, &ip_address).await {
+        Ok(None) => EmailChangeService::retry_after(pool.clone(), claims.auth_usernid).await,
+        other => other,
+    };
+    match retry_after {
+        Ok(Some(retry_after)) => {
+            result.message = "Too many requests".to_string();
+            result.error = Some(format!("Try again in {} seconds", retry_after));
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        Err(err) => {
+            result.error = Some(err);
+            return HttpResponse::InternalServerError().json(result);
+        },
+    }
+
+    match EmailChangeService::request_change(pool, claims, request.into_inner(), &ip_address, &GenericService::get_device_info(&req)).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/change-email/{token}")]
+async fn confirm_change_email(pool: web::Data<Pool<ConnectionManager>>, token: web::Path<String>) -> impl Responder {
+
+    let result: ActionResult<(), _> = EmailChangeService::confirm(pool, token.into_inner()).await;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, // Jika error, HTTP 500
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/sessions")]
+async fn list_sessions(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match SessionService::list(pool, claims.auth_usernid, claims.sid.as_deref()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/sessions/{session_id}/revoke")]
+async fn revoke_session(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, session_id: web::Path<String>) -> impl Responder {
+
+    match SessionService::revoke(pool, claims.auth_usernid, &session_id.into_inner(), "user_revoke").await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::NotFound().json(response), // Sesi tidak ada / bukan milik user
+    }
+}
+
+#[post("/sessions/revoke-all")]
+async fn revoke_all_sessions(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    let mut result: ActionResult<(), String> = ActionResult::default();
+
+    if let Err(err) = SessionService::revoke_all(pool, claims.auth_usernid, "user_revoke_all").await {
+        result.error = Some(err);
+        return HttpResponse::InternalServerError().json(result);
+    }
+
+    result.result = true;
+    result.message = "All sessions revoked".to_string();
+
+    // Sesi ini ikut dicabut, hapus juga cookie-nya
+    HttpResponse::Ok()
+        .cookie(expired_cookie("token"))
+        .cookie(expired_cookie("refresh_token"))
+        .json(result)
+}
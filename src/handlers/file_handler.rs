@@ -1,5 +1,4 @@
 use std::{collections::HashMap, env, fs::{self, File}, io::{BufWriter, Cursor, Read as _}, path::Path};
-use actix_identity::Identity;
 use actix_web::{get, http::header, web, HttpResponse, Responder, Scope};
 use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
@@ -7,7 +6,7 @@ use handlebars::Handlebars;
 use printpdf::{BuiltinFont, Mm, PdfDocument};
 use serde_json::json;
 
-use crate::{contexts::{jwt_session::validate_jwt, model::{ActionResult, UserInfo}}, services::user_service::UserService};
+use crate::{contexts::{jwt_session::Claims, model::{ActionResult, UserInfo}}, services::user_service::UserService};
 
 pub fn file_scope() -> Scope {
     
@@ -19,178 +18,136 @@ pub fn file_scope() -> Scope {
 }
 
 #[get("/preview-pdf")]
-pub async fn render_template(pool: web::Data<Pool<ConnectionManager>>, session: Option<Identity> ) -> impl Responder {
+pub async fn render_template(pool: web::Data<Pool<ConnectionManager>>, claims: Claims ) -> impl Responder {
 
     let mut result: ActionResult<UserInfo, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-
-                    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
-                    
-                    result.result = data.result;
-                    result.message = data.message;
-                    result.data = Some(data.data.unwrap());
-                    result.error = data.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => {
-                            // let response = response.data.unwrap();
-                        
-                            let template_path = "./reports/template.mustache";
-                            let data = json!({
-                                "url": "/api/v1",
-                                "header": "Cost Insurance and Freight",
-                                "data": response.data
-                            });
-                        
-                            // Baca file template
-                            let template_content = match fs::read_to_string(template_path) {
-                                Ok(content) => content,
-                                Err(_) => return HttpResponse::InternalServerError().body("Failed to read template file"),
-                            };
-                        
-                            // Register template di handlebars
-                            let mut handlebars = Handlebars::new();
-                            handlebars.register_template_string("report", template_content).unwrap();
-                        
-                            let body = handlebars.render("report", &data).unwrap();
-                            HttpResponse::Ok().content_type("text/html").body(body)
-                        }, 
-                        response => {
-                            HttpResponse::BadRequest().json(response)
-                        }
-                    }
-                    
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
+    
+    result.result = data.result;
+    result.message = data.message;
+    result.data = Some(data.data.unwrap());
+    result.error = data.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => {
+            // let response = response.data.unwrap();
+        
+            let template_path = "./reports/template.mustache";
+            let data = json!({
+                "url": "/api/v1",
+                "header": "Cost Insurance and Freight",
+                "data": response.data
+            });
+        
+            // Baca file template
+            let template_content = match fs::read_to_string(template_path) {
+                Ok(content) => content,
+                Err(_) => return HttpResponse::InternalServerError().body("Failed to read template file"),
+            };
+        
+            // Register template di handlebars
+            let mut handlebars = Handlebars::new();
+            handlebars.register_template_string("report", template_content).unwrap();
+        
+            let body = handlebars.render("report", &data).unwrap();
+            HttpResponse::Ok().content_type("text/html").body(body)
+        }, 
+        response => {
+            HttpResponse::BadRequest().json(response)
+        }
     }
 }
 
 #[get("/download-pdf")]
-pub async fn download_pdf(pool: web::Data<Pool<ConnectionManager>>, session: Option<Identity>) -> impl Responder {
+pub async fn download_pdf(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
     let mut result: ActionResult<UserInfo, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
-
-                    result.result = data.result;
-                    result.message = data.message;
-                    result.data = Some(data.data.unwrap());
-                    result.error = data.error;
+    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
 
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        },
-                        response if response.result => {
-                            let template_path = "./reports/template.mustache";
-                            let json_data = json!({
-                                "url": "/api/v1",
-                                "header": "Cost Insurance and Freight",
-                                "data": response.data
-                            });
+    result.result = data.result;
+    result.message = data.message;
+    result.data = Some(data.data.unwrap());
+    result.error = data.error;
 
-                            // Baca file template
-                            let template_content = match fs::read_to_string(template_path) {
-                                Ok(content) => content,
-                                Err(_) => return HttpResponse::InternalServerError().body("Failed to read template file"),
-                            };
-
-                            // Render template dengan Handlebars
-                            let mut handlebars = Handlebars::new();
-                            handlebars.register_template_string("report", template_content).unwrap();
-                            let html_content = handlebars.render("report", &json_data).unwrap();
-
-                            // Convert HTML ke teks untuk printpdf
-                            let cleaned_text = html_content
-                                .replace("<br>", "\n")
-                                .replace("</p>", "\n")
-                                .replace("<p>", "")
-                                .replace("</tr>", "\n")
-                                .replace("<tr>", "")
-                                .replace("</td>", " | ")
-                                .replace("<td>", " ")
-                                .replace("</th>", " | ")
-                                .replace("<th>", " ")
-                                .replace("</h1>", "\n")
-                                .replace("<h1>", "\n# ")
-                                .replace("</table>", "\n")
-                                .replace("<table>", "\n")
-                                .replace("<html>", "")
-                                .replace("</html>", "")
-                                .replace("<head>", "")
-                                .replace("</head>", "")
-                                .replace("<body>", "")
-                                .replace("</body>", "");
-
-                            // Generate PDF
-                            let (doc, page, layer) = PdfDocument::new("Generated Report", Mm(210.0), Mm(297.0), "Layer 1");
-                            let current_layer = doc.get_page(page).get_layer(layer);
-                            let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => {
+            let template_path = "./reports/template.mustache";
+            let json_data = json!({
+                "url": "/api/v1",
+                "header": "Cost Insurance and Freight",
+                "data": response.data
+            });
+
+            // Baca file template
+            let template_content = match fs::read_to_string(template_path) {
+                Ok(content) => content,
+                Err(_) => return HttpResponse::InternalServerError().body("Failed to read template file"),
+            };
 
-                            let mut y_position = 270.0;
-                            for line in cleaned_text.lines() {
-                                current_layer.use_text(line, 12.0, Mm(10.0), Mm(y_position), &font);
-                                y_position -= 10.0;
-                            }
+            // Render template dengan Handlebars
+            let mut handlebars = Handlebars::new();
+            handlebars.register_template_string("report", template_content).unwrap();
+            let html_content = handlebars.render("report", &json_data).unwrap();
+
+            // Convert HTML ke teks untuk printpdf
+            let cleaned_text = html_content
+                .replace("<br>", "\n")
+                .replace("</p>", "\n")
+                .replace("<p>", "")
+                .replace("</tr>", "\n")
+                .replace("<tr>", "")
+                .replace("</td>", " | ")
+                .replace("<td>", " ")
+                .replace("</th>", " | ")
+                .replace("<th>", " ")
+                .replace("</h1>", "\n")
+                .replace("<h1>", "\n# ")
+                .replace("</table>", "\n")
+                .replace("<table>", "\n")
+                .replace("<html>", "")
+                .replace("</html>", "")
+                .replace("<head>", "")
+                .replace("</head>", "")
+                .replace("<body>", "")
+                .replace("</body>", "");
+
+            // Generate PDF
+            let (doc, page, layer) = PdfDocument::new("Generated Report", Mm(210.0), Mm(297.0), "Layer 1");
+            let current_layer = doc.get_page(page).get_layer(layer);
+            let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
+
+            let mut y_position = 270.0;
+            for line in cleaned_text.lines() {
+                current_layer.use_text(line, 12.0, Mm(10.0), Mm(y_position), &font);
+                y_position -= 10.0;
+            }
 
-                            // Simpan PDF ke buffer menggunakan BufWriter
-                            let mut buffer = Cursor::new(Vec::new());
-                            {
-                                let mut writer = BufWriter::new(&mut buffer);
-                                doc.save(&mut writer).unwrap();
-                            } // Writer keluar dari scope, buffer bisa digunakan
+            // Simpan PDF ke buffer menggunakan BufWriter
+            let mut buffer = Cursor::new(Vec::new());
+            {
+                let mut writer = BufWriter::new(&mut buffer);
+                doc.save(&mut writer).unwrap();
+            } // Writer keluar dari scope, buffer bisa digunakan
 
-                            // Ambil data PDF dari buffer
-                            let pdf_data = buffer.into_inner();
+            // Ambil data PDF dari buffer
+            let pdf_data = buffer.into_inner();
 
-                            // Response PDF
-                            HttpResponse::Ok()
-                                .content_type("application/pdf")
-                                .append_header(("Content-Disposition", "attachment; filename=report.pdf"))
-                                .body(pdf_data)
-                        },
-                        response => {
-                            HttpResponse::BadRequest().json(response)
-                        }
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+            // Response PDF
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .append_header(("Content-Disposition", "attachment; filename=report.pdf"))
+                .body(pdf_data)
         },
+        response => {
+            HttpResponse::BadRequest().json(response)
+        }
     }
 }
 
@@ -1,15 +1,17 @@
 use std::collections::HashMap;
-use actix_identity::Identity;
-use actix_web::{get, post, web, HttpResponse, Responder, Scope};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Scope};
 use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use validator::{Validate, ValidationError};
 
 use crate::{
     contexts::{
-        jwt_session::validate_jwt, 
-        model::{ActionResult, CIFFileRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, UserInfo}}, 
-    services::{file_service::FileService, user_service::UserService, validation_service::validator::format_validation_errors}
+        jwt_session::Claims, 
+        logger::write_log,
+        model::{AccountClosureRequest, ActionResult, CIFFileRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, PhoneOtpIssued, PhoneOtpVerifyRequest, ProfileChangeRequest, SetPhoneRequest, UserInfo}}, 
+    services::{account_closure_service::AccountClosureService, file_service::FileService, generic_service::GenericService, kyc_draft_service::KycDraftService, login_guard_service::LoginGuardService, onboarding_progress_service::OnboardingProgressService, phone_verification_service::PhoneVerificationService, profile_change_service::ProfileChangeService, user_service::UserService, validation_service::validator::format_validation_errors}
 };
 
 pub fn user_scope() -> Scope {
@@ -20,59 +22,153 @@ pub fn user_scope() -> Scope {
         .service(data_pekerjaan)
         .service(data_pendukung)
         .service(get_user_info)
+        .service(onboarding_progress)
         .service(data_beneficiary)
         .service(data_cif_file)
+        .service(set_phone)
+        .service(send_phone_otp)
+        .service(verify_phone_otp)
+        .service(request_account_closure)
+        .service(account_closure_status)
+        .service(cancel_account_closure)
+        .service(submit_draft)
+        .service(discard_draft)
+        .service(get_draft)
+        .service(save_draft)
+        .service(cancel_profile_change)
+        .service(request_profile_change)
+        .service(list_profile_changes)
 }
 
-#[get("/userinfo")]
-pub async fn get_user_info(pool: web::Data<Pool<ConnectionManager>>, session: Option<Identity>) -> impl Responder {
+#[get("/progress")]
+async fn onboarding_progress(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
 
-    let mut result: ActionResult<UserInfo, _> = ActionResult::default();
+    match OnboardingProgressService::progress(pool, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/drafts/{step}")]
+async fn get_draft(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, step: web::Path<String>) -> impl Responder {
+
+    match KycDraftService::get(pool, claims.auth_usernid, &step.into_inner()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/drafts/{step}")]
+async fn save_draft(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<Value>, claims: Claims, step: web::Path<String>) -> impl Responder {
+
+    match KycDraftService::save(pool, claims.auth_usernid, &step.into_inner(), request.into_inner()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
+#[post("/drafts/{step}/discard")]
+async fn discard_draft(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, step: web::Path<String>) -> impl Responder {
+
+    match KycDraftService::discard(pool, claims.auth_usernid, &step.into_inner()).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+/// 📤 Submit draft sebagai form final: isi draft divalidasi penuh & disimpan lewat jalur yang sama dengan route form-nya
+#[post("/drafts/{step}/submit")]
+async fn submit_draft(pool: web::Data<Pool<ConnectionManager>>, claims: Claims, step: web::Path<String>) -> impl Responder {
+
+    let step = step.into_inner();
+    let data = match KycDraftService::get(pool.clone(), claims.auth_usernid, &step).await {
+        response if response.error.is_some() => return HttpResponse::InternalServerError().json(response),
+        response if !response.result => return HttpResponse::BadRequest().json(response),
+        response => response.data.map(|draft| draft.data).unwrap_or_default(),
+    };
+
+    match step.as_str() {
+        "personal_data" => match draft_request::<DataPribadiRequest>(data) {
+            Ok(request) => submit_data_pribadi(pool, request, claims).await,
+            Err(response) => response,
         },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-
-                    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
-                    
-                    result.result = data.result;
-                    result.message = data.message;
-                    result.data = Some(data.data.unwrap());
-                    result.error = data.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => {
-                            HttpResponse::Ok().json(response)
-                        }, 
-                        response => {
-                            HttpResponse::BadRequest().json(response)
-                        }
-                    }
-                    
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
+        "beneficiary_owner" => match draft_request::<DataBeneficiaryRequest>(data) {
+            Ok(request) => submit_data_beneficiary(pool, request, claims).await,
+            Err(response) => response,
         },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+        "bank_account" => match draft_request::<DataBankRequest>(data) {
+            Ok(request) => submit_data_bank(pool, request, claims).await,
+            Err(response) => response,
+        },
+        "occupation" => match draft_request::<DataPekerjaanRequest>(data) {
+            Ok(request) => submit_data_pekerjaan(pool, request, claims).await,
+            Err(response) => response,
         },
+        "supporting_data" => match draft_request::<DataPendukungRequest>(data) {
+            Ok(request) => submit_data_pendukung(pool, request, claims).await,
+            Err(response) => response,
+        },
+        _ => {
+            let result: ActionResult<(), String> = ActionResult {
+                message: format!("Unknown step '{}'", step),
+                ..ActionResult::default()
+            };
+            HttpResponse::BadRequest().json(result)
+        }
+    }
+}
+
+/// Draft belum lengkap (field wajib hilang / tipe salah) ditolak sebelum validasi form
+fn draft_request<T: DeserializeOwned>(data: Value) -> Result<T, HttpResponse> {
+    serde_json::from_value(data).map_err(|err| {
+        let result: ActionResult<(), String> = ActionResult {
+            message: "Draft is incomplete".to_string(),
+            error: Some(err.to_string()),
+            ..ActionResult::default()
+        };
+        HttpResponse::BadRequest().json(result)
+    })
+}
+
+#[get("/userinfo")]
+pub async fn get_user_info(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    let mut result: ActionResult<UserInfo, _> = ActionResult::default();
+
+    let data: ActionResult<UserInfo, _> = UserService::get_user_info(pool, claims).await;
+    
+    result.result = data.result;
+    result.message = data.message;
+    result.data = Some(data.data.unwrap());
+    result.error = data.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => {
+            HttpResponse::Ok().json(response)
+        }, 
+        response => {
+            HttpResponse::BadRequest().json(response)
+        }
     }
 }
 
 #[post("/save-cif-file")]
-async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<CIFFileRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<CIFFileRequest>, claims: Claims) -> impl Responder {
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -89,85 +185,72 @@ async fn data_cif_file(pool: web::Data<Pool<ConnectionManager>>, request: web::J
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let mut request: CIFFileRequest = request.clone(); // Ubah menjadi mutable
-
-                    if request.idcard_file.starts_with("data:image/") {
-                        match FileService::save_base64_image(&claims.email, &request.idcard_file, "KTP") {
-                            Ok(saved_path) => {
-                                request.idcard_file = saved_path;
-                                println!("KTP handler: {}", request.idcard_file);
-                            },
-                            Err(err) => {
-                                result.error = Some(err.to_string());
-                                return HttpResponse::InternalServerError().json(result);
-                            },
-                        }
-                    } 
-
-                    if request.selfie_file.starts_with("data:image/") {
-                        match FileService::save_base64_image(&claims.email, &request.selfie_file, "Selfie") {
-                            Ok(saved_path) => {
-                                request.selfie_file = saved_path;
-                                println!("Selfie hanlder: {}", request.selfie_file);
-                            },
-                            Err(err) => {
-                                result.error = Some(err.to_string());
-                                return HttpResponse::InternalServerError().json(result);
-                            },
-                        }
-                    } 
-
-                    if request.signature_file.starts_with("data:image/") {
-                        match FileService::save_base64_image(&claims.email, &request.signature_file, "Signature") {
-                            Ok(saved_path) => {
-                                request.signature_file = saved_path;
-                                println!("Signature handler: {}", request.signature_file);
-                            },
-                            Err(err) => {
-                                result.error = Some(err.to_string());
-                                return HttpResponse::InternalServerError().json(result);
-                            },
-                        }
-                    } 
-
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_cif_file(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let mut request: CIFFileRequest = request.clone(); // Ubah menjadi mutable
+
+    if request.idcard_file.starts_with("data:image/") {
+        match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.idcard_file, "KTP").await {
+            Ok(saved_path) => {
+                request.idcard_file = saved_path;
+                println!("KTP handler: {}", request.idcard_file);
+            },
+            Err(err) => {
+                result.error = Some(err.to_string());
+                return HttpResponse::InternalServerError().json(result);
+            },
+        }
+    } 
+
+    if request.selfie_file.starts_with("data:image/") {
+        match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.selfie_file, "Selfie").await {
+            Ok(saved_path) => {
+                request.selfie_file = saved_path;
+                println!("Selfie hanlder: {}", request.selfie_file);
+            },
+            Err(err) => {
+                result.error = Some(err.to_string());
+                return HttpResponse::InternalServerError().json(result);
+            },
+        }
+    } 
+
+    if request.signature_file.starts_with("data:image/") {
+        match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.signature_file, "Signature").await {
+            Ok(saved_path) => {
+                request.signature_file = saved_path;
+                println!("Signature handler: {}", request.signature_file);
+            },
+            Err(err) => {
+                result.error = Some(err.to_string());
+                return HttpResponse::InternalServerError().json(result);
+            },
+        }
+    } 
+
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_cif_file(pool, request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
 #[post("/data-pribadi")]
-async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPribadiRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPribadiRequest>, claims: Claims) -> impl Responder {
+    submit_data_pribadi(pool, request.into_inner(), claims).await
+}
+
+/// Simpan final form data pribadi, dipakai route ini dan submit draft `personal_data`
+async fn submit_data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: DataPribadiRequest, claims: Claims) -> HttpResponse {
+
+    let auth_usernid = claims.auth_usernid;
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -184,46 +267,38 @@ async fn data_pribadi(pool: web::Data<Pool<ConnectionManager>>, request: web::Js
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let request: DataPribadiRequest = request.clone(); // Ubah menjadi mutable
-
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pribadi(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pribadi(pool.clone(), request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    // Form final sudah tersimpan, draft-nya tidak dibutuhkan lagi
+    if result.result {
+        if let Some(err) = KycDraftService::discard(pool, auth_usernid, "personal_data").await.error {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
 #[post("/data-bank")]
-async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBankRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBankRequest>, claims: Claims) -> impl Responder {
+    submit_data_bank(pool, request.into_inner(), claims).await
+}
+
+/// Simpan final form data bank, dipakai route ini dan submit draft `bank_account`
+async fn submit_data_bank(pool: web::Data<Pool<ConnectionManager>>, request: DataBankRequest, claims: Claims) -> HttpResponse {
+
+    let auth_usernid = claims.auth_usernid;
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -240,44 +315,38 @@ async fn data_bank(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_bank(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_bank(pool.clone(), request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    // Form final sudah tersimpan, draft-nya tidak dibutuhkan lagi
+    if result.result {
+        if let Some(err) = KycDraftService::discard(pool, auth_usernid, "bank_account").await.error {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
 #[post("/data-pekerjaan")]
-async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPekerjaanRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPekerjaanRequest>, claims: Claims) -> impl Responder {
+    submit_data_pekerjaan(pool, request.into_inner(), claims).await
+}
+
+/// Simpan final form data pekerjaan, dipakai route ini dan submit draft `occupation`
+async fn submit_data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, mut request: DataPekerjaanRequest, claims: Claims) -> HttpResponse {
+
+    let auth_usernid = claims.auth_usernid;
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -294,54 +363,47 @@ async fn data_pekerjaan(pool: web::Data<Pool<ConnectionManager>>, request: web::
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let mut request: DataPekerjaanRequest = request.clone(); // Ubah menjadi mutable
-
-                    match FileService::save_base64_image(&claims.email, &request.npwp_file, "NPWP") {
-                        Ok(saved_path) => request.npwp_file = saved_path,
-                        Err(err) => {
-                            result.error = Some(err.to_string());
-                            return HttpResponse::InternalServerError().json(result);
-                        },
-                    }
-
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pekerjaan(pool, request, claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
+
+    match FileService::save_upload(&pool, claims.auth_usernid, &claims.email, &request.npwp_file, "NPWP").await {
+        Ok(saved_path) => request.npwp_file = saved_path,
+        Err(err) => {
+            result.error = Some(err.to_string());
+            return HttpResponse::InternalServerError().json(result);
         },
     }
+
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pekerjaan(pool.clone(), request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    // Form final sudah tersimpan, draft-nya tidak dibutuhkan lagi
+    if result.result {
+        if let Some(err) = KycDraftService::discard(pool, auth_usernid, "occupation").await.error {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
 }
 
 #[post("/data-pendukung")]
-async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPendukungRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataPendukungRequest>, claims: Claims) -> impl Responder {
+    submit_data_pendukung(pool, request.into_inner(), claims).await
+}
+
+/// Simpan final form data pendukung, dipakai route ini dan submit draft `supporting_data`
+async fn submit_data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: DataPendukungRequest, claims: Claims) -> HttpResponse {
+
+    let auth_usernid = claims.auth_usernid;
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -401,44 +463,38 @@ async fn data_pendukung(pool: web::Data<Pool<ConnectionManager>>, request: web::
         return HttpResponse::BadRequest().json(result);
     }
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
-        },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pendukung(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
-        },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
-            return HttpResponse::BadRequest().json(result);
-        },
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_pendukung(pool.clone(), request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    // Form final sudah tersimpan, draft-nya tidak dibutuhkan lagi
+    if result.result {
+        if let Some(err) = KycDraftService::discard(pool, auth_usernid, "supporting_data").await.error {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
 #[post("/beneficiary-owner")]
-async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBeneficiaryRequest>, session: Option<Identity>) -> impl Responder {
+async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<DataBeneficiaryRequest>, claims: Claims) -> impl Responder {
+    submit_data_beneficiary(pool, request.into_inner(), claims).await
+}
+
+/// Simpan final form beneficiary owner, dipakai route ini dan submit draft `beneficiary_owner`
+async fn submit_data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: DataBeneficiaryRequest, claims: Claims) -> HttpResponse {
+
+    let auth_usernid = claims.auth_usernid;
 
     if let Err(errors) = request.validate() {
         let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
@@ -455,39 +511,232 @@ async fn data_beneficiary(pool: web::Data<Pool<ConnectionManager>>, request: web
 
     let mut result: ActionResult<HashMap<String, String>, _> = ActionResult::default();
 
-    match session.map(|id: Identity| id.id()) {
-        None => {
-            result.error = Some("Token not found".to_string());
-            return HttpResponse::Unauthorized().json(result);
+    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_beneficiary(pool.clone(), request, claims).await;
+
+    result.result = response.result;
+    result.message = response.message;
+    result.data = response.data;
+    result.error = response.error;
+
+    // Form final sudah tersimpan, draft-nya tidak dibutuhkan lagi
+    if result.result {
+        if let Some(err) = KycDraftService::discard(pool, auth_usernid, "beneficiary_owner").await.error {
+            write_log("ERROR", err.as_str());
+        }
+    }
+
+    match result {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        }, 
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/phone")]
+async fn set_phone(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<SetPhoneRequest>, claims: Claims) -> impl Responder {
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let mobile_phone = request.into_inner().mobile_phone.unwrap_or_default();
+    match PhoneVerificationService::set_phone(pool, claims, mobile_phone).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
         },
-        Some(Ok(token)) => {
-            match validate_jwt(&token) {
-                Ok(claims) => {
-                    let response: ActionResult<HashMap<String, String>, String> = UserService::save_data_beneficiary(pool, request.into_inner(), claims).await;
-
-                    result.result = response.result;
-                    result.message = response.message;
-                    result.data = response.data;
-                    result.error = response.error;
-
-                    match result {
-                        response if response.error.is_some() => {
-                            HttpResponse::InternalServerError().json(response)
-                        }, 
-                        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
-                        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
-                    }
-                },
-                Err(err) => {
-                    result.error = Some(err.to_string());
-                    return HttpResponse::Unauthorized().json(result);
-                },
-            }
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/phone/send-otp")]
+async fn send_phone_otp(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    let mut result: ActionResult<PhoneOtpIssued, String> = ActionResult::default();
+
+    // 🚦 Cooldown kirim ulang & batas per jam
+    match PhoneVerificationService::retry_after(pool.clone(), claims.auth_usernid).await {
+        Ok(Some(retry_after)) => {
+            result.message = "Too many requests".to_string();
+            result.error = Some(format!("Try again in {} seconds", retry_after));
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        Err(err) => {
+            result.error = Some(err);
+            return HttpResponse::InternalServerError().json(result);
+        },
+    }
+
+    match PhoneVerificationService::send_otp(pool, claims).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
         },
-        Some(Err(_)) => {
-            result.error = Some("Invalid token".to_string());
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/phone/verify")]
+async fn verify_phone_otp(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<PhoneOtpVerifyRequest>, claims: Claims) -> impl Responder {
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    match PhoneVerificationService::verify_otp(pool, claims, request.into_inner().code).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/close-account")]
+async fn request_account_closure(req: HttpRequest, pool: web::Data<Pool<ConnectionManager>>, request: web::Json<AccountClosureRequest>, claims: Claims) -> impl Responder {
+
+    let mut result: ActionResult<(), String> = ActionResult::default();
+
+    if let Err(errors) = request.validate() {
+        let formatted_errors: HashMap<String, String> = format_validation_errors(&errors);
+
+        let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+            result: false,
+            message: "Validation failed".to_string(),
+            data: None,
+            error: Some(formatted_errors),
+        };
+
+        return HttpResponse::BadRequest().json(result);
+    }
+
+    let ip_address = GenericService::get_ip_address(&req);
+
+    // 🚦 Password dicek ulang di sini, jadi ikut aturan lockout login
+    match LoginGuardService::check(pool.clone(), &claims.email, &ip_address).await {
+        Ok(Some(retry_after)) => {
+            result.message = "Too many requests".to_string();
+            result.error = Some(format!("Try again in {} seconds", retry_after));
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(result);
+        },
+        Ok(None) => {},
+        Err(err) => {
+            result.error = Some(err);
+            return HttpResponse::InternalServerError().json(result);
+        },
+    }
+
+    match AccountClosureService::request(pool, claims, request.into_inner(), &ip_address, &GenericService::get_device_info(&req)).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[get("/close-account")]
+async fn account_closure_status(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match AccountClosureService::status(pool, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::NotFound().json(response), // Belum pernah mengajukan
+    }
+}
+
+#[post("/close-account/cancel")]
+async fn cancel_account_closure(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match AccountClosureService::cancel(pool, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/profile-changes")]
+async fn request_profile_change(pool: web::Data<Pool<ConnectionManager>>, request: web::Json<ProfileChangeRequest>, claims: Claims) -> impl Responder {
+
+    let request = request.into_inner();
+    let changes = match request.validate() {
+        Ok(()) => ProfileChangeService::parse_changes(request.section.as_deref().unwrap_or_default().trim(), &request.changes),
+        Err(errors) => Err(format_validation_errors(&errors)),
+    };
+
+    let changes = match changes {
+        Ok(changes) => changes,
+        Err(formatted_errors) => {
+            let result: ActionResult<HashMap<String, String>, _> = ActionResult {
+                result: false,
+                message: "Validation failed".to_string(),
+                data: None,
+                error: Some(formatted_errors),
+            };
+
             return HttpResponse::BadRequest().json(result);
+        }
+    };
+
+    let section = request.section.unwrap_or_default().trim().to_string();
+    match ProfileChangeService::submit(pool, claims.auth_usernid, &section, changes, request.reason).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
         },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
     }
 }
 
+#[get("/profile-changes")]
+async fn list_profile_changes(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match ProfileChangeService::list(pool, Some(claims.auth_usernid), None).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
+
+#[post("/profile-changes/cancel")]
+async fn cancel_profile_change(pool: web::Data<Pool<ConnectionManager>>, claims: Claims) -> impl Responder {
+
+    match ProfileChangeService::cancel(pool, claims.auth_usernid).await {
+        response if response.error.is_some() => {
+            HttpResponse::InternalServerError().json(response)
+        },
+        response if response.result => HttpResponse::Ok().json(response), // Jika berhasil, HTTP 200
+        response => HttpResponse::BadRequest().json(response), // Jika gagal, HTTP 400
+    }
+}
@@ -2,11 +2,11 @@ use actix_cors::Cors;
 use actix_files::Files;
 use actix_identity::IdentityMiddleware;
 use actix_session::{config::PersistentSession, storage::CookieSessionStore, SessionMiddleware};
-use actix_web::{ cookie::{time::Duration, Key}, get, http::{self}, middleware::{self}, web::{self, route}, App, HttpServer};
+use actix_web::{ cookie::time::Duration, get, http::{self}, middleware::{self}, web::{self, route}, App, HttpServer};
 use contexts::{connection::create_pool, logger::write_log};
 use handlers::{admin_hanlder::admin_scope, auth_handler::auth_scope, file_handler::file_scope, generic_handler::generic_scope, option_handler::option_scope, user_handler::user_scope};
 use log::info;
-use services::generic_service::{self};
+use services::{generic_service::{self}, mail_service::MailService, revocation_service::RevocationService};
 
 mod contexts {
     pub mod connection;
@@ -14,6 +14,18 @@ mod contexts {
     pub mod logger;
     pub mod jwt_session;
     pub mod crypto;
+    pub mod password;
+    pub mod rbac;
+    pub mod authenticated;
+    pub mod impersonation;
+    pub mod onboarding;
+    pub mod totp;
+    pub mod mailer;
+    pub mod sms;
+    pub mod keyring;
+    pub mod oidc;
+    pub mod password_policy;
+    pub mod cli;
 }
 
 mod handlers {
@@ -33,6 +45,26 @@ mod services {
     pub mod validation_service;
     pub mod file_service;
     pub mod admin_service;
+    pub mod token_service;
+    pub mod revocation_service;
+    pub mod role_service;
+    pub mod two_factor_service;
+    pub mod login_guard_service;
+    pub mod mail_service;
+    pub mod phone_verification_service;
+    pub mod session_service;
+    pub mod password_history_service;
+    pub mod email_change_service;
+    pub mod oidc_service;
+    pub mod audit_service;
+    pub mod account_closure_service;
+    pub mod impersonation_service;
+    pub mod kyc_review_service;
+    pub mod kyc_draft_service;
+    pub mod profile_change_service;
+    pub mod onboarding_progress_service;
+    pub mod cif_export_service;
+    pub mod cif_ack_service;
 }
 
 #[get("/")]
@@ -43,10 +75,26 @@ async fn health_check() -> String {
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     env_logger::init(); // Aktifkan logging
-    let secret_key: Key = Key::generate(); 
     dotenvy::dotenv().ok();
+    // 🔑 Kunci JWT & cookie dari konfigurasi, sama di semua instance dan tetap setelah restart
+    let secret_key = contexts::keyring::init().expect("Invalid key configuration");
     let db_pool = create_pool("db12877").await.expect("Failed to create database pool");
 
+    // 🖥️ Command line job (mis. `export-cif`, `ingest-cif-ack`), jalan sekali lalu keluar tanpa start server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = contexts::cli::run(&db_pool, &args).await {
+        std::process::exit(code);
+    }
+
+    // 🚫 Load token yang sudah dicabut + jadwalkan cleanup berkala
+    if let Err(err) = RevocationService::reload(&db_pool).await {
+        write_log("ERROR", err.as_str());
+    }
+    RevocationService::start(db_pool.clone());
+
+    // ✉️ Worker pengiriman email dari outbox
+    MailService::start(db_pool.clone());
+
     write_log("INFO", "Test log message: Logging is working");
     info!("🚀 Application running on http://127.0.0.1:8000");
     
@@ -56,6 +104,7 @@ async fn main() -> std::io::Result<()> {
             .allowed_methods(vec!["GET", "POST"])
             .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
             .allowed_header(http::header::CONTENT_TYPE)
+            .expose_headers(vec![contexts::impersonation::IMPERSONATED_BY_HEADER])
             .supports_credentials()
             .max_age(3600);
         App::new()
@@ -73,6 +122,7 @@ async fn main() -> std::io::Result<()> {
         .service(health_check)
         .default_service(route().to(generic_service::GenericService::not_found))
         .wrap(middleware::Logger::default()) // Logging middleware
+        .wrap(middleware::from_fn(contexts::impersonation::impersonation_guard)) // 🕵️ Batasi & catat request impersonation
         .wrap(IdentityMiddleware::default())
         .wrap(
             SessionMiddleware::builder(CookieSessionStore::default(), secret_key.clone())
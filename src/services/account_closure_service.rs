@@ -0,0 +1,554 @@
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use tiberius::Row;
+use tokio_stream::StreamExt;
+
+use crate::contexts::{
+    connection::Transaction,
+    jwt_session::Claims,
+    logger::write_log,
+    model::{AccountClosureInfo, AccountClosureRequest, ActionResult},
+};
+use super::{
+    audit_service::{AuditService, AUDIT_ACCOUNT_CLOSURE_REJECTED, AUDIT_ACCOUNT_CLOSURE_REQUESTED, AUDIT_ACCOUNT_ERASED},
+    file_service::FileService,
+    login_guard_service::LoginGuardService,
+    session_service::SessionService,
+};
+
+pub const CLOSURE_PENDING: &str = "pending";
+pub const CLOSURE_APPROVED: &str = "approved";
+pub const CLOSURE_REJECTED: &str = "rejected";
+pub const CLOSURE_CANCELLED: &str = "cancelled";
+
+/// Domain pengganti email yang dihapus, `.invalid` tidak akan pernah bisa menerima email
+const ERASED_EMAIL_DOMAIN: &str = "erased.invalid";
+
+const CLOSURE_COLUMNS: &str = "C.ClosureNID, C.AuthUserNID, C.Reason, C.Status, C.RequestedAt, C.ReviewedAt, C.ReviewNote, U.Email";
+
+pub struct AccountClosureService;
+
+impl AccountClosureService {
+
+    fn closure_info(row: &Row) -> AccountClosureInfo {
+        let status = row.get::<&str, _>("Status").unwrap_or_default().to_string();
+        AccountClosureInfo {
+            closure_nid: row.get("ClosureNID").unwrap_or(0),
+            auth_usernid: row.get("AuthUserNID").unwrap_or(0),
+            // Setelah dihapus yang tersisa hanya email pengganti, tidak perlu ditampilkan
+            email: if status == CLOSURE_APPROVED { String::new() } else { row.get::<&str, _>("Email").unwrap_or_default().to_string() },
+            reason: row.get::<&str, _>("Reason").unwrap_or_default().to_string(),
+            requested_at: row.get::<NaiveDateTime, _>("RequestedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+            reviewed_at: row.get::<NaiveDateTime, _>("ReviewedAt").map(|dt| dt.and_utc()),
+            review_note: row.get::<&str, _>("ReviewNote").map(|s| s.to_string()),
+            status,
+        }
+    }
+
+    /// 🗑️ User minta akun ditutup & data pribadinya dihapus. Password wajib benar, eksekusi menunggu persetujuan admin
+    pub async fn request(connection: web::Data<Pool<ConnectionManager>>, session: Claims, request: AccountClosureRequest, ip_address: &str, device_info: &str) -> ActionResult<AccountClosureInfo, String> {
+        let mut result: ActionResult<AccountClosureInfo, String> = ActionResult::default();
+        let password = request.password.unwrap_or_default();
+        let reason = request.reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+
+        match connection.clone().get().await {
+            Ok(mut conn) => {
+                let row = match conn.query(
+                    r#"SELECT Email, Password FROM AuthUser WHERE AuthUserNID = @P1 AND ClosedAt IS NULL"#, &[&session.auth_usernid]).await {
+                    Ok(rows) => match rows.into_row().await {
+                        Ok(Some(row)) => row,
+                        _ => {
+                            result.message = "No user found".to_string();
+                            return result;
+                        }
+                    },
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    }
+                };
+
+                let email = row.get::<&str, _>("Email").unwrap_or_default();
+                match LoginGuardService::reauthenticate(connection.clone(), email, &password, row.get::<&str, _>("Password").unwrap_or_default(), ip_address, device_info).await {
+                    Ok(true) => {},
+                    Ok(false) => {
+                        result.message = "Invalid password".to_string();
+                        return result;
+                    },
+                    Err(err) => {
+                        result.error = err.into();
+                        return result;
+                    }
+                }
+
+                match conn.query(
+                    r#"SELECT ClosureNID FROM AuthAccountClosure WHERE AuthUserNID = @P1 AND Status = @P2"#,
+                    &[&session.auth_usernid, &CLOSURE_PENDING],
+                ).await {
+                    Ok(rows) => {
+                        if let Ok(Some(_)) = rows.into_row().await {
+                            result.message = "Account closure already requested".to_string();
+                            return result;
+                        }
+                    }
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    }
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        }
+
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let closure_nid: i64 = match conn.query(
+                                r#"INSERT INTO [dbo].[AuthAccountClosure] ([AuthUserNID],[Reason],[Status],[RequestedAt])
+                                OUTPUT INSERTED.ClosureNID
+                                VALUES (@P1,@P2,@P3,@P4)"#,
+                                &[&session.auth_usernid, &reason, &CLOSURE_PENDING, &now],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get("ClosureNID").unwrap_or(0),
+                                    _ => {
+                                        result.error = Some("Failed to insert AuthAccountClosure".to_string());
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to insert AuthAccountClosure: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            let detail = serde_json::json!({ "closure_nid": closure_nid });
+                            if let Err(err) = AuditService::record(conn, AUDIT_ACCOUNT_CLOSURE_REQUESTED, Some(session.auth_usernid), Some(session.auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            result.data = Some(AccountClosureInfo {
+                                closure_nid,
+                                auth_usernid: session.auth_usernid,
+                                email: session.email.clone(),
+                                reason: reason.unwrap_or_default(),
+                                status: CLOSURE_PENDING.to_string(),
+                                requested_at: now,
+                                reviewed_at: None,
+                                review_note: None,
+                            });
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Account closure requested, waiting for approval".to_string();
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// 🔎 Permintaan tutup akun terakhir milik user
+    pub async fn status(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<AccountClosureInfo, String> {
+        let mut result: ActionResult<AccountClosureInfo, String> = ActionResult::default();
+
+        match connection.get().await {
+            Ok(mut conn) => match conn.query(
+                format!(r#"SELECT TOP 1 {} FROM AuthAccountClosure C JOIN AuthUser U ON U.AuthUserNID = C.AuthUserNID
+                WHERE C.AuthUserNID = @P1 ORDER BY C.RequestedAt DESC, C.ClosureNID DESC"#, CLOSURE_COLUMNS),
+                &[&auth_usernid],
+            ).await {
+                Ok(rows) => match rows.into_row().await {
+                    Ok(Some(row)) => {
+                        result.result = true;
+                        result.message = "Retrieve successfully".to_string();
+                        result.data = Some(Self::closure_info(&row));
+                    }
+                    Ok(None) => result.message = "No account closure request".to_string(),
+                    Err(err) => result.error = format!("Query execution failed: {:?}", err).into(),
+                },
+                Err(err) => result.error = format!("Query execution failed: {:?}", err).into(),
+            },
+            Err(err) => result.error = format!("Internal Server error: {:?}", err).into(),
+        }
+
+        result
+    }
+
+    /// ↩️ User membatalkan permintaan yang belum diproses
+    pub async fn cancel(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match connection.get().await {
+            Ok(mut conn) => match conn.execute(
+                r#"UPDATE [dbo].[AuthAccountClosure] SET [Status] = @P2, [ReviewedAt] = @P3 WHERE AuthUserNID = @P1 AND Status = @P4"#,
+                &[&auth_usernid, &CLOSURE_CANCELLED, &Utc::now(), &CLOSURE_PENDING],
+            ).await.map(|done| done.total()) {
+                Ok(0) => result.message = "No pending account closure request".to_string(),
+                Ok(_) => {
+                    result.result = true;
+                    result.message = "Account closure request cancelled".to_string();
+                }
+                Err(err) => result.error = format!("Failed to update AuthAccountClosure: {:?}", err).into(),
+            },
+            Err(err) => result.error = format!("Internal Server error: {:?}", err).into(),
+        }
+
+        result
+    }
+
+    /// 📋 Antrian permintaan tutup akun untuk admin (default hanya yang pending)
+    pub async fn list(connection: web::Data<Pool<ConnectionManager>>, status: Option<String>) -> ActionResult<Vec<AccountClosureInfo>, String> {
+        let mut result: ActionResult<Vec<AccountClosureInfo>, String> = ActionResult::default();
+        let status = status.unwrap_or_else(|| CLOSURE_PENDING.to_string());
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            format!(r#"SELECT {} FROM AuthAccountClosure C JOIN AuthUser U ON U.AuthUserNID = C.AuthUserNID
+            WHERE C.Status = @P1 ORDER BY C.RequestedAt"#, CLOSURE_COLUMNS),
+            &[&status],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut closures: Vec<AccountClosureInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        closures.push(Self::closure_info(row));
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} account closure request(s)", closures.len());
+        result.data = Some(closures);
+        result
+    }
+
+    /// ❌ Admin menolak permintaan (misalnya masih ada kewajiban yang belum selesai)
+    pub async fn reject(connection: web::Data<Pool<ConnectionManager>>, closure_nid: i64, reviewed_by: i32, note: Option<String>) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let auth_usernid: i32 = match conn.query(
+                                r#"UPDATE [dbo].[AuthAccountClosure] SET [Status] = @P2, [ReviewedBy] = @P3, [ReviewedAt] = @P4, [ReviewNote] = @P5
+                                OUTPUT INSERTED.AuthUserNID
+                                WHERE ClosureNID = @P1 AND Status = @P6"#,
+                                &[&closure_nid, &CLOSURE_REJECTED, &reviewed_by, &Utc::now(), &note, &CLOSURE_PENDING],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get("AuthUserNID").unwrap_or(0),
+                                    _ => {
+                                        result.message = "No pending account closure request".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update AuthAccountClosure: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            let detail = serde_json::json!({ "closure_nid": closure_nid });
+                            if let Err(err) = AuditService::record(conn, AUDIT_ACCOUNT_CLOSURE_REJECTED, Some(reviewed_by), Some(auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Account closure request rejected".to_string();
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// ✅ Admin menyetujui: data pribadi di AuthUser, UserKyc & TableRequest dikosongkan, tabel pendukung yang
+    /// menyimpan email / IP dibersihkan, dokumen upload dihapus dan semua sesi dicabut.
+    /// Yang tersisa hanya baris dengan ID, CIF/Client ID, tanggal & status sebagai catatan retensi
+    pub async fn approve(connection: web::Data<Pool<ConnectionManager>>, closure_nid: i64, reviewed_by: i32, note: Option<String>) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        let (auth_usernid, email, referenced) = match connection.clone().get().await {
+            Ok(mut conn) => match conn.query(
+                r#"SELECT C.AuthUserNID, U.Email, K.IDCardFile, K.SelfieFile, K.SignatureFile, K.NPWPFile
+                FROM AuthAccountClosure C
+                JOIN AuthUser U ON U.AuthUserNID = C.AuthUserNID
+                LEFT JOIN UserKyc K ON K.AutoNID = U.WebCIFNID
+                WHERE C.ClosureNID = @P1 AND C.Status = @P2"#,
+                &[&closure_nid, &CLOSURE_PENDING],
+            ).await {
+                Ok(rows) => match rows.into_row().await {
+                    Ok(Some(row)) => {
+                        let files: Vec<String> = ["IDCardFile", "SelfieFile", "SignatureFile", "NPWPFile"]
+                            .iter()
+                            .filter_map(|column| row.get::<&str, _>(*column))
+                            .filter(|path| !path.trim().is_empty())
+                            .map(|path| path.to_string())
+                            .collect();
+                        (row.get::<i32, _>("AuthUserNID").unwrap_or(0), row.get::<&str, _>("Email").unwrap_or_default().to_string(), files)
+                    }
+                    _ => {
+                        result.message = "No pending account closure request".to_string();
+                        return result;
+                    }
+                },
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        };
+
+        // 📎 Yang dihapus hanya file yang tercatat diupload user ini. Path di UserKyc yang bukan miliknya
+        // (bisa menunjuk upload applicant lain) tidak disentuh
+        let files: Vec<String> = match connection.clone().get().await {
+            Ok(mut conn) => match conn.query(r#"SELECT AssetPath FROM UserAsset WHERE AuthUserNID = @P1"#, &[&auth_usernid]).await {
+                Ok(rows) => match rows.into_first_result().await {
+                    Ok(rows) => rows.iter().filter_map(|row| row.get::<&str, _>("AssetPath")).map(|path| path.to_string()).collect(),
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    }
+                },
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        };
+        let refused: Vec<&String> = referenced.iter().filter(|path| !files.contains(path)).collect();
+        for path in &refused {
+            write_log("WARN", format!("Account closure {}: {} is not an upload of user {}, not deleted", closure_nid, path, auth_usernid).as_str());
+        }
+
+        let erased_email = format!("closed-{}@{}", auth_usernid, ERASED_EMAIL_DOMAIN);
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            match conn.execute(
+                                r#"UPDATE [dbo].[AuthAccountClosure] SET [Status] = @P2, [ReviewedBy] = @P3, [ReviewedAt] = @P4, [ReviewNote] = @P5, [CompletedAt] = @P4
+                                WHERE ClosureNID = @P1 AND Status = @P6"#,
+                                &[&closure_nid, &CLOSURE_APPROVED, &reviewed_by, &now, &note, &CLOSURE_PENDING],
+                            ).await.map(|done| done.total()) {
+                                Ok(0) => {
+                                    result.message = "No pending account closure request".to_string();
+                                    break 'tx None;
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update AuthAccountClosure: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+
+                            // 🔴 Parameter: @P1 AuthUserNID, @P2 email pengganti, @P3 waktu, @P4 email lama (tabel tanpa AuthUserNID).
+                            // UserKyc & TableRequest dicari lewat WebCIFNID yang tidak ikut dihapus
+                            let statements: [(&str, &str); 4] = [
+                                ("UserKyc", r#"UPDATE [dbo].[UserKyc] SET
+                                    [Email] = @P2, [MobilePhone] = '', [Fullname] = '', [SpouseName] = NULL, [MotherName] = NULL,
+                                    [IDCardNumber] = NULL, [IDCardExpireDate] = NULL, [BirthPlace] = NULL, [BirthDate] = NULL,
+                                    [IDCardDistrict] = NULL, [IDCardSubdistrict] = NULL, [IDCardRT] = NULL, [IDCardRW] = NULL,
+                                    [IDCardZipcode] = NULL, [IDCardAddress] = NULL,
+                                    [DomicileDistrict] = NULL, [DomicileSubdistrict] = NULL, [DomicileRT] = NULL, [DomicileRW] = NULL,
+                                    [DomicileZipcode] = NULL, [DomicileAddress] = NULL,
+                                    [BankName] = NULL, [BankBranch] = NULL, [BankAccountHolder] = NULL, [BankAccountNumber] = NULL,
+                                    [NPWPNumber] = NULL, [NPWPReason] = NULL, [CompanyName] = NULL, [CompanyAddress] = NULL,
+                                    [SpouseCompanyName] = NULL, [SpouseCompanyCity] = NULL, [SpouseCompanyZipcode] = NULL, [SpouseCompanyAddress] = NULL,
+                                    [IDCardFile] = NULL, [SelfieFile] = NULL, [SignatureFile] = NULL, [NPWPFile] = NULL,
+                                    [SaveIpAddress] = NULL, [LastUpdate] = @P3
+                                WHERE AutoNID = (SELECT WebCIFNID FROM AuthUser WHERE AuthUserNID = @P1)"#),
+                                ("TableRequest", r#"UPDATE [dbo].[TableRequest] SET
+                                    [ContactPersonName] = NULL, [ContactPersonRelation] = NULL, [ContactPersonHomePhone] = NULL,
+                                    [ContactPersonMobilePhone] = NULL, [ContactPersonAddress] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerName] = NULL, [CIFInvestorBeneficiaryMothersMaidenName] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerBirthPlace] = NULL, [CIFInvestorBeneficiaryOwnerBirthDate] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerIDCardNumber] = NULL, [CIFInvestorBeneficiaryOwnerIDCardExpiredDate] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerEmail] = NULL, [CIFInvestorBeneficiaryOwnerNPWPNumber] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerAddress1] = NULL, [CIFInvestorBeneficiaryOwnerAddress2] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerAddress3] = NULL, [CIFInvestorBeneficiaryOwnerKelurahan] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerKecamatan] = NULL, [CIFInvestorBeneficiaryOwnerRT] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerRW] = NULL, [CIFInvestorBeneficiaryOwnerPostalCode] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerMobilePhone] = NULL, [CIFInvestorBeneficiaryOwnerCompanyName] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerCompanyAddress] = NULL, [CIFInvestorBeneficiaryOwnerCompanyAddress2] = NULL,
+                                    [CIFInvestorBeneficiaryOwnerCompanyAddress3] = NULL, [CIFInvestorBeneficiaryOwnerCompanyPostalCode] = NULL
+                                WHERE WebCIFNID = (SELECT WebCIFNID FROM AuthUser WHERE AuthUserNID = @P1)"#),
+                                ("AuthUser", r#"UPDATE [dbo].[AuthUser] SET
+                                    [Email] = @P2, [Handphone] = '', [Password] = NULL, [Picture] = NULL, [Sub] = NULL, [OidcProvider] = NULL,
+                                    [OTPGeneratedLink] = NULL, [ResetPasswordKey] = NULL, [PhoneVerified] = NULL, [PhoneVerifiedAt] = NULL,
+                                    [disableLogin] = 1, [ClosedAt] = @P3
+                                WHERE AuthUserNID = @P1"#),
+                                ("related tables", r#"
+                                    DELETE FROM [dbo].[AuthPasswordHistory] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[AuthEmailChange] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[PhoneVerification] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[KycDraft] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[KycChangeRequest] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[UserKycVersion] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[AuthUserTotp] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[AuthRecoveryCode] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[UserAsset] WHERE AuthUserNID = @P1;
+                                    DELETE FROM [dbo].[AuthLoginAttempt] WHERE Email = @P4;
+                                    DELETE FROM [dbo].[AuthLockout] WHERE Email = @P4;
+                                    DELETE FROM [dbo].[ActivationResendRequest] WHERE Email = @P4;
+                                    DELETE FROM [dbo].[MailOutbox] WHERE Recipient = @P4;
+                                    UPDATE [dbo].[AuthSession] SET [IpAddress] = NULL, [UserAgent] = NULL WHERE AuthUserNID = @P1;
+                                    UPDATE [dbo].[AuthRefreshToken] SET [IpAddress] = NULL WHERE AuthUserNID = @P1"#),
+                            ];
+
+                            for (table, statement) in statements {
+                                if let Err(err) = conn.execute(statement, &[&auth_usernid, &erased_email, &now, &email]).await {
+                                    result.error = Some(format!("Failed to erase {}: {:?}", table, err));
+                                    break 'tx None;
+                                }
+                            }
+
+                            let detail = serde_json::json!({ "closure_nid": closure_nid, "files": files.len(), "refused_files": refused.len() });
+                            if let Err(err) = AuditService::record(conn, AUDIT_ACCOUNT_ERASED, Some(reviewed_by), Some(auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        }
+
+        // File dihapus setelah commit: jika transaksi gagal dokumen masih utuh dan bisa diulang
+        let mut failed_files: usize = refused.len();
+        for path in &files {
+            if let Err(err) = FileService::delete_asset(path) {
+                write_log("ERROR", format!("Account closure {}: {}", closure_nid, err).as_str());
+                failed_files += 1;
+            }
+        }
+
+        if let Err(err) = SessionService::revoke_all(connection, auth_usernid, "account_closed").await {
+            write_log("ERROR", err.as_str());
+        }
+
+        result.result = true;
+        result.message = if failed_files == 0 {
+            "Account closed and personal data erased".to_string()
+        } else {
+            format!("Account closed, {} uploaded file(s) could not be deleted, see log", failed_files)
+        };
+        result
+    }
+}
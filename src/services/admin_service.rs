@@ -7,14 +7,52 @@ use serde_json::{json, Value as JsonValue};
 use tiberius::{QueryStream, Row};
 use std::fmt::Write;
 
+use super::{session_service::SessionService, user_service::{UserService, KYC_STAGE_QUERY}};
 use crate::contexts::{
-    connection::Transaction, jwt_session::Claims, model::{ActionResult, CIFFileRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, QueryClass, ResultList, TableDataParams, UserInfo}
+    connection::Transaction, jwt_session::Claims, onboarding::{transition, KycStep}, model::{ActionResult, CIFFileRequest, DataBankRequest, DataBeneficiaryRequest, DataPekerjaanRequest, DataPendukungRequest, DataPribadiRequest, QueryClass, ResultList, TableDataParams, UserInfo}
 };
 
 pub struct AdminService;
 
 impl AdminService {
 
+    /// 🚫 Paksa user keluar dari semua sesi (access token + refresh token dicabut)
+    pub async fn force_sign_out(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match connection.clone().get().await {
+            Ok(mut conn) => {
+                let query_result: Result<QueryStream, _> = conn.query(
+                    r#"SELECT AuthUserNID FROM AuthUser WHERE AuthUserNID = @P1"#, &[&auth_usernid]).await;
+                match query_result {
+                    Ok(rows) => {
+                        if let Ok(None) | Err(_) = rows.into_row().await {
+                            result.message = "No user found".to_string();
+                            return result;
+                        }
+                    },
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    },
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        }
+
+        if let Err(err) = SessionService::revoke_all(connection, auth_usernid, "admin_sign_out").await {
+            result.error = Some(err);
+            return result;
+        }
+
+        result.result = true;
+        result.message = "User signed out from all sessions".to_string();
+        result
+    }
+
     pub async fn get_table_data( allparams: TableDataParams, connection: web::Data<Pool<ConnectionManager>>) -> Result<ResultList, Box<dyn std::error::Error>> {
         let mut result = ResultList {
             total: 0,
@@ -213,11 +251,12 @@ impl AdminService {
                             result.data = Some(UserInfo {
                                 autonid: row.get::<i32, _>("AutoNID").unwrap_or(0),
                                 stage: row.get::<i32, _>("Stage").unwrap_or(0),
-                                client_id: row.get::<&str, _>("CIFID").map_or_else(|| "".to_string(), |s| s.to_string()),
-                                cif_id: row.get::<&str, _>("ClientID").map_or_else(|| "".to_string(), |s| s.to_string()),
+                                client_id: row.get::<&str, _>("ClientID").map_or_else(|| "".to_string(), |s| s.to_string()),
+                                cif_id: row.get::<&str, _>("CIFID").map_or_else(|| "".to_string(), |s| s.to_string()),
                                 is_revised: row.get("IsRevised").unwrap_or(false),
                                 is_rejected: row.get("IsRejected").unwrap_or(false),
                                 is_finished: row.get("IsFinished").unwrap_or(false),
+                                is_approved: row.get("IsApproved").unwrap_or(false),
                                 account_status: row.get::<i32, _>("AccountStatus").unwrap_or(0),
                                 mobile_phone: row.get::<&str, _>("MobilePhone").map_or_else(|| "".to_string(), |s| s.to_string()),
                                 email: row.get::<&str, _>("Email").map_or_else(|| "".to_string(), |s| s.to_string()),
@@ -340,24 +379,24 @@ impl AdminService {
     pub async fn save_cif_file(connection: web::Data<Pool<ConnectionManager>>, request: CIFFileRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let current_stage: i32 = 1;
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
 
-                            if stage < current_stage {
-                                result.message = "Stage has ben second or 2".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::Documents, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -366,9 +405,10 @@ impl AdminService {
                                         Some(conn) => {
                                             if let Err(err) = conn.execute(
                                             r#"UPDATE [dbo].[UserKYC]
-                                                SET [IDCardFile] = @P1, [SelfieFile] = @P2, [SignatureFile] = @P3
-                                            WHERE AutoNID = @P4"#,
+                                                SET [Stage] = @P1, [IDCardFile] = @P2, [SelfieFile] = @P3, [SignatureFile] = @P4
+                                            WHERE AutoNID = @P5"#,
                                                 &[
+                                                    &next_stage.as_i32(),
                                                     &request.idcard_file,
                                                     &request.selfie_file,
                                                     &request.signature_file,
@@ -420,34 +460,24 @@ impl AdminService {
     pub async fn save_data_pribadi(connection: web::Data<Pool<ConnectionManager>>, request: DataPribadiRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let current_stage: i32 = 1;
-        let set_stage: i32;
         
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
 
-                            if stage < current_stage {
-                                result.message = "Stage has ben first or 1".to_owned();
-                                return result;
-                            }
-                            
-                            if request.beneficiary_owner == 1 {
-                                set_stage = 2;
-                            } else if request.beneficiary_owner == 2 {
-                                set_stage = 1;
-                            } else {
-                                result.message = "Invalid beneficiary owner".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::PersonalData { beneficiary_owner: request.beneficiary_owner }, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -465,7 +495,7 @@ impl AdminService {
                                                 [DomicileAddress] = @P28, IDCardCountry = @P29
                                             WHERE AutoNID = @P30"#,
                                                 &[
-                                                    &set_stage,
+                                                    &next_stage.as_i32(),
                                                     &request.full_name,
                                                     &request.nationality,
                                                     &request.idcard_number,
@@ -558,24 +588,24 @@ impl AdminService {
     pub async fn save_data_bank(connection: web::Data<Pool<ConnectionManager>>, request: DataBankRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let current_stage: i32 = 2;
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
 
-                            if stage < current_stage {
-                                result.message = "Stage has ben second or 2".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::BankAccount, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -587,7 +617,7 @@ impl AdminService {
                                                 SET [Stage] = @P1, [QuestionRDN] = @P2, [BankName] = @P3, [BankAccountHolder] = @P4, [BankAccountNumber] = @P5, [BankBranch] = @P6
                                             WHERE AutoNID = @P7"#,
                                                 &[
-                                                    &3i32,
+                                                    &next_stage.as_i32(),
                                                     &request.question_rdn,
                                                     &request.bank_name,
                                                     &request.bank_account_holder,
@@ -641,24 +671,24 @@ impl AdminService {
     pub async fn save_data_pekerjaan(connection: web::Data<Pool<ConnectionManager>>, request: DataPekerjaanRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let curent_stage: i32 = 3;
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
 
-                            if stage < curent_stage {
-                                result.message = "Stage has ben second or 3".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::Occupation, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -678,7 +708,7 @@ impl AdminService {
                                                 [SpouseRelationship] = @P28, [NPWPFile] = @P29
                                                 WHERE AutoNID = @P30"#,
                                                 &[
-                                                    &4i32,
+                                                    &next_stage.as_i32(),
                                                     &request.question_npwp,
                                                     &request.npwp_number,
                                                     &request.npwp_reason,
@@ -755,24 +785,24 @@ impl AdminService {
     pub async fn save_data_pendukung(connection: web::Data<Pool<ConnectionManager>>, request: DataPendukungRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let current_stage: i32 = 4;
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
 
-                            if stage < current_stage {
-                                result.message = "Stage has ben second or 4".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::SupportingData, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -790,7 +820,7 @@ impl AdminService {
                                                 [QuestionFATCA] = @P16, [FATCA1] = @P17, [FATCA2] = @P18, [FATCA3] = @P19, IsFinished = 1 
                                                 WHERE AutoNID = @P20"#,
                                                 &[
-                                                    &5i32,
+                                                    &next_stage.as_i32(),
                                                     &request.question_1,
                                                     &request.question_1text,
                                                     &request.question_2,
@@ -883,29 +913,24 @@ impl AdminService {
     pub async fn save_data_beneficiary(connection: web::Data<Pool<ConnectionManager>>, request: DataBeneficiaryRequest, session: Claims) -> ActionResult<HashMap<String, String>, String> {
 
         let mut result: ActionResult<HashMap<String, String>, String> = ActionResult::default();
-        let current_stage: i32 = 1;
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AutoNID, Stage 
-                    FROM UserKyc 
-                    WHERE AutoNID = @P1"#, &[&session.auth_usernid]).await;
+                    KYC_STAGE_QUERY, &[&session.auth_usernid]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
                             let stage: i32 = row.get("Stage").unwrap_or(0);
                             let auto_nid: i32 = row.get("AutoNID").unwrap_or(0); 
 
-                            if stage < current_stage {
-                                result.message = "Stage has ben second or 1".to_owned();
-                                return result;
-                            }
-
-                            if request.beneficiary_owner == 1 {
-                                result.message = "Invalid beneficiary owner".to_owned();
-                                return result;
-                            }
+                            let next_stage = match transition(stage, KycStep::BeneficiaryOwner { beneficiary_owner: request.beneficiary_owner }, UserService::kyc_facts(&row)) {
+                                Ok(next_stage) => next_stage,
+                                Err(err) => {
+                                    result.message = err.to_string();
+                                    return result;
+                                }
+                            };
 
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
@@ -988,40 +1013,24 @@ impl AdminService {
                                         }
                                     }
 
-                                    match Transaction::begin(&connection).await {
-                                        Ok(trans) => {
-                                            // 🔴 Scope ketiga: Insert ke TableRequest
-                                            match trans.conn.lock().await.as_mut() {
-                                                Some(conn) => {
-                                                    if let Err(err) = conn.execute(
-                                                    r#"UPDATE [dbo].[UserKYC]
-                                                        SET [Stage] = @P1 WHERE AutoNID = @P2"#,
-                                                        &[
-                                                            &2i32,
-                                                            &auto_nid
-                                                        ],
-                                                    ).await {
-                                                        result.error = Some(format!("Fauled: {:?}", err));
-                                                        return result;
-                                                    }
-                                                }
-                                                None => {
-                                                    result.error = Some("Failed to get database connection".into());
-                                                    return result;
-                                                }
-                                            }
-        
-                                            // 🔵 Commit transaksi
-                                            if let Err(err) = trans.commit().await {
-                                                result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                                    // Stage ikut transaksi yang sama supaya data beneficiary & stage tidak pernah setengah tersimpan
+                                    match trans.conn.lock().await.as_mut() {
+                                        Some(conn) => {
+                                            if let Err(err) = conn.execute(
+                                            r#"UPDATE [dbo].[UserKYC]
+                                                SET [Stage] = @P1 WHERE AutoNID = @P2"#,
+                                                &[
+                                                    &next_stage.as_i32(),
+                                                    &auto_nid
+                                                ],
+                                            ).await {
+                                                result.error = Some(format!("Fauled: {:?}", err));
                                                 return result;
                                             }
-                            
-                                            result.result = true;
-                                            result.message = "Update personal data successfully".to_string();
                                         }
-                                        Err(err) => {
-                                            result.error = Some(format!("Failed to start transaction: {:?}", err));
+                                        None => {
+                                            result.error = Some("Failed to get database connection".into());
+                                            return result;
                                         }
                                     }
 
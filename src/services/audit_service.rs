@@ -0,0 +1,30 @@
+use bb8::PooledConnection;
+use bb8_tiberius::ConnectionManager;
+use chrono::Utc;
+use serde_json::Value;
+
+/// Aksi yang dicatat di `AuditLog`
+pub const AUDIT_ACCOUNT_CLOSURE_REQUESTED: &str = "account.closure_requested";
+pub const AUDIT_ACCOUNT_CLOSURE_REJECTED: &str = "account.closure_rejected";
+pub const AUDIT_ACCOUNT_ERASED: &str = "account.erased";
+pub const AUDIT_IMPERSONATION_STARTED: &str = "impersonation.started";
+pub const AUDIT_IMPERSONATION_ENDED: &str = "impersonation.ended";
+pub const AUDIT_IMPERSONATION_REQUEST: &str = "impersonation.request";
+pub const AUDIT_PROFILE_CHANGE_APPROVED: &str = "profile_change.approved";
+pub const AUDIT_PROFILE_CHANGE_REJECTED: &str = "profile_change.rejected";
+
+pub struct AuditService;
+
+impl AuditService {
+
+    /// 📝 Catat satu aksi. `actor` = yang melakukan (admin / user sendiri), `subject` = akun yang terdampak.
+    /// Bisa dipanggil di dalam transaksi supaya audit ikut rollback jika aksinya gagal
+    pub async fn record(conn: &mut PooledConnection<'_, ConnectionManager>, action: &str, actor: Option<i32>, subject: Option<i32>, detail: &Value) -> Result<(), String> {
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuditLog] ([Action],[ActorNID],[SubjectNID],[Detail],[CreatedAt]) VALUES (@P1,@P2,@P3,@P4,@P5)"#,
+            &[&action, &actor, &subject, &detail.to_string(), &Utc::now()],
+        ).await.map_err(|e| format!("Failed to insert AuditLog: {:?}", e))?;
+
+        Ok(())
+    }
+}
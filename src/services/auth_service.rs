@@ -1,56 +1,149 @@
 use actix_web::web;
-use bb8::Pool;
+use bb8::{Pool, PooledConnection};
 use bb8_tiberius::ConnectionManager;
-use chrono::{NaiveDateTime, TimeZone, Utc};
+use std::env;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 use tiberius::QueryStream;
 use crate::contexts::{
     connection::Transaction, 
-    crypto::encrypt_text, 
-    model::{ActionResult, ChangePasswordRequest, LoginRequest, RegisterRequest, ResetPasswordRequest, WebUser}
+    crypto::hash_token, 
+    logger::write_log, 
+    password::{hash_password, verify_dummy_password, verify_password}, 
+    password_policy::PasswordPolicy,
+    model::{ActionResult, ChangePasswordRequest, LoginRequest, PasswordViolation, RegisterRequest, ResendActivationRequest, ResetPasswordRequest, WebUser}
 };
-use super::generic_service::GenericService;
+use super::{generic_service::GenericService, mail_service::{MailService, TEMPLATE_ACTIVATION, TEMPLATE_RESET_PASSWORD}, password_history_service::PasswordHistoryService, role_service::RoleService, session_service::SessionService};
 
 pub struct AuthService;
 
 impl AuthService {
+
+    /// ⏳ Masa berlaku link aktivasi (default 24 jam)
+    fn activation_token_ttl() -> Duration {
+        let hours: i64 = env::var("ACTIVATION_TOKEN_TTL_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(24);
+        Duration::hours(hours)
+    }
+
+    /// ⏳ Masa berlaku link reset password (default 60 menit)
+    fn reset_password_token_ttl() -> Duration {
+        let minutes: i64 = env::var("RESET_PASSWORD_TTL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+        Duration::minutes(minutes)
+    }
+
+    fn is_expired(issued_at: Option<NaiveDateTime>, ttl: Duration) -> bool {
+        issued_at.is_none_or(|issued_at| issued_at.and_utc() + ttl < Utc::now())
+    }
+
+    /// 👤 Ambil WebUser (beserta role & permission) berdasarkan AuthUserNID, dipakai setelah login tanpa password (refresh, 2FA)
+    pub async fn get_web_user(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32) -> Result<Option<WebUser>, String> {
+        let row = match conn.query(
+            r#"SELECT AuthUserNID, Email, Handphone, disableLogin, Picture, RegisterDate, PasswordChangedAt FROM AuthUser
+            WHERE AuthUserNID = @P1"#, &[&auth_usernid]).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row,
+                Ok(None) => return Ok(None),
+                Err(err) => return Err(format!("Query execution failed: {:?}", err)),
+            },
+            Err(err) => return Err(format!("Query execution failed: {:?}", err)),
+        };
+
+        let (roles, permissions) = RoleService::get_user_access(conn, auth_usernid).await?;
+
+        Ok(Some(WebUser {
+            auth_usernid,
+            email: row.get::<&str, _>("Email").map_or_else(|| "".to_string(), |s| s.to_string()),
+            mobile_phone: row.get::<&str, _>("Handphone").map_or_else(|| "".to_string(), |s| s.to_string()),
+            disabled_login: row.get("disableLogin").unwrap_or(false),
+            picture: Some(row.get::<&str, _>("Picture").map_or_else(|| "".to_string(), |s| s.to_string())),
+            register_date: row
+                .get::<NaiveDateTime, _>("RegisterDate")
+                .map(|dt| dt.and_utc())
+                .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+            roles,
+            permissions,
+            mfa: false,
+            session_id: None,
+            password_changed_at: row.get::<NaiveDateTime, _>("PasswordChangedAt").map(|dt| dt.and_utc()),
+        }))
+    }
+
     pub async fn login(connection: web::Data<Pool<ConnectionManager>>,request: LoginRequest) -> ActionResult<WebUser, String> {
         
         let mut result: ActionResult<WebUser, String> = ActionResult::default();
-        let enc_password = encrypt_text(request.password.unwrap_or_default());
+        let password = request.password.unwrap_or_default();
 
         match connection.clone().get().await {
             Ok(mut conn) => {
-                let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AuthUserNID, Email, Handphone, disableLogin, Picture, RegisterDate FROM AuthUser 
-                    WHERE Email = @P1 AND Password = @P2"#, &[&request.email, &enc_password]).await;
-                match query_result {
-                    Ok(rows) => {
-                        if let Ok(Some(row)) = rows.into_row().await {
-                            result.result = true;
-                            result.message = format!("Welcome {}", request.email.unwrap_or_default());
-                            result.data = Some(WebUser{
-                                auth_usernid: row.get("AuthUserNID").unwrap_or(0),
-                                email: row.get::<&str, _>("Email").map_or_else(|| "".to_string(), |s| s.to_string()),
-                                mobile_phone: row.get::<&str, _>("Handphone").map_or_else(|| "".to_string(), |s| s.to_string()),
-                                disabled_login: row.get("disableLogin").unwrap_or(false),
-                                picture: Some(row.get::<&str, _>("Picture").map_or_else(|| "".to_string(), |s| s.to_string())),
-                                register_date: row
-                                    .get::<NaiveDateTime, _>("RegisterDate")
-                                    .map(|dt| dt.and_utc()) // 🔥 Konversi ke DateTime<Utc>
-                                    .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()), // Default jika kosong
-                            }); 
-
-                            return result;
-                        } else {
+                let row = match conn.query(
+                    r#"SELECT AuthUserNID, Email, Handphone, disableLogin, Picture, RegisterDate, PasswordChangedAt, Password FROM AuthUser 
+                    WHERE Email = @P1"#, &[&request.email]).await {
+                    Ok(rows) => match rows.into_row().await {
+                        Ok(Some(row)) => row,
+                        _ => {
+                            // ⏱️ Tetap hitung Argon2 supaya waktu respons sama dengan password salah
+                            verify_dummy_password(&password).await;
                             result.message = format!("No user found for email: {}", request.email.unwrap_or_default());
                             return result;
-                        } 
+                        }
                     },
                     Err(err) => {
                         result.error = format!("Query execution failed: {:?}", err).into();
                         return result;
                     },
+                };
+
+                let check = verify_password(&password, row.get::<&str, _>("Password").unwrap_or_default()).await;
+
+                if !check.valid {
+                    result.message = format!("No user found for email: {}", request.email.unwrap_or_default());
+                    return result;
                 }
+
+                let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+
+                let (roles, permissions) = match RoleService::get_user_access(&mut conn, auth_usernid).await {
+                    Ok(access) => access,
+                    Err(err) => {
+                        result.error = Some(err);
+                        return result;
+                    }
+                };
+
+                result.result = true;
+                result.message = format!("Welcome {}", request.email.unwrap_or_default());
+                result.data = Some(WebUser{
+                    auth_usernid,
+                    email: row.get::<&str, _>("Email").map_or_else(|| "".to_string(), |s| s.to_string()),
+                    mobile_phone: row.get::<&str, _>("Handphone").map_or_else(|| "".to_string(), |s| s.to_string()),
+                    disabled_login: row.get("disableLogin").unwrap_or(false),
+                    picture: Some(row.get::<&str, _>("Picture").map_or_else(|| "".to_string(), |s| s.to_string())),
+                    register_date: row
+                        .get::<NaiveDateTime, _>("RegisterDate")
+                        .map(|dt| dt.and_utc()) // 🔥 Konversi ke DateTime<Utc>
+                        .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()), // Default jika kosong
+                    roles,
+                    permissions,
+                    mfa: false,
+                    session_id: None,
+                    password_changed_at: row.get::<NaiveDateTime, _>("PasswordChangedAt").map(|dt| dt.and_utc()),
+                }); 
+
+                // 🔁 Migrasi hash lama (AES) / parameter lama ke Argon2id terbaru
+                if check.needs_rehash {
+                    match hash_password(&password).await {
+                        Ok(new_hash) => {
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[AuthUser] SET [Password] = @P1 WHERE AuthUserNID = @P2"#,
+                                &[&new_hash, &auth_usernid],
+                            ).await {
+                                write_log("ERROR", format!("Failed to rehash password for {}: {:?}", auth_usernid, err).as_str());
+                            }
+                        }
+                        Err(err) => write_log("ERROR", err.as_str()),
+                    }
+                }
+
+                return result;
             },
             Err(err) => {
                 result.error = format!("Internal Server error: {:?}", err).into();
@@ -62,7 +155,13 @@ impl AuthService {
     pub async fn register(connection: web::Data<Pool<ConnectionManager>>, request: RegisterRequest) -> ActionResult<(), String> {
         
         let mut result: ActionResult<(), String> = ActionResult::default();
-        let enc_password = encrypt_text(request.password.unwrap_or_default());
+        let enc_password = match hash_password(&request.password.unwrap_or_default()).await {
+            Ok(hash) => hash,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
 
         match connection.clone().get().await {
             Ok(mut conn) => {
@@ -91,6 +190,8 @@ impl AuthService {
                 return result;
             }
         }
+        let otp_link = GenericService::random_string(70);
+
         match Transaction::begin(&connection).await {
             Ok(trans) => {
                 let auto_nid: i32;
@@ -140,19 +241,47 @@ impl AuthService {
                 // 🔴 Scope kedua: Insert ke AuthUser
                 match trans.conn.lock().await.as_mut() {
                     Some(conn) => {
-                        if let Err(err) = conn.execute(
+                        let auth_usernid: i32 = match conn.query(
                             r#"INSERT INTO [dbo].[AuthUser] 
                             ([WebCIFNID],[Email],[Handphone],[ActivateCode],[Password],[RegisterDate],
-                            [disableLogin],[OTPGeneratedLink],[OTPGeneratedLinkDate],[Picture],[Sub], [ClientNCategory])
-                            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8,@P9,@P10,@P11,@P12)"#,
+                            [disableLogin],[OTPGeneratedLink],[OTPGeneratedLinkDate],[Picture],[Sub], [ClientNCategory],[PasswordChangedAt])
+                            OUTPUT INSERTED.AuthUserNID
+                            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8,@P9,@P10,@P11,@P12,@P6)"#,
                             &[
                                 &auto_nid, &request.email, &request.mobile_phone, &GenericService::random_string(20),
                                 &enc_password, &chrono::Utc::now(), &true,
-                                &GenericService::random_string(70), &chrono::Utc::now(),
+                                &hash_token(&otp_link), &chrono::Utc::now(),
                                 &"", &"", &request.client_category,
                             ],
                         ).await {
-                            result.error = Some(format!("Failed to insert AuthUser: {:?}", err));
+                            Ok(rows) => match rows.into_row().await {
+                                Ok(Some(row)) => row.get("AuthUserNID").unwrap_or(0),
+                                _ => {
+                                    result.error = Some("Failed to get AuthUserNID from AuthUser".into());
+                                    return result;
+                                }
+                            },
+                            Err(err) => {
+                                result.error = Some(format!("Failed to insert AuthUser: {:?}", err));
+                                return result;
+                            }
+                        };
+
+                        // 📜 Password pertama langsung masuk history
+                        if let Err(err) = PasswordHistoryService::record(conn, auth_usernid, PasswordPolicy::from_env().history_count).await {
+                            result.error = Some(err);
+                            return result;
+                        }
+
+                        // ✉️ Email aktivasi lewat outbox, ikut rollback jika register gagal
+                        let email = request.email.clone().unwrap_or_default();
+                        let data = serde_json::json!({
+                            "email": email,
+                            "full_name": request.full_name,
+                            "link": MailService::activation_link(&otp_link),
+                        });
+                        if let Err(err) = MailService::enqueue(conn, &email, "Aktivasi akun Anda", TEMPLATE_ACTIVATION, &data).await {
+                            result.error = Some(err);
                             return result;
                         }
                     }
@@ -203,31 +332,44 @@ impl AuthService {
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AuthUserNID 
+                    r#"SELECT AuthUserNID, OTPGeneratedLinkDate 
                     FROM AuthUser 
-                    WHERE OTPGeneratedLink = @P1"#, &[&otp_link]).await;
+                    WHERE OTPGeneratedLink = @P1"#, &[&hash_token(&otp_link)]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
+                            if Self::is_expired(row.get::<NaiveDateTime, _>("OTPGeneratedLinkDate"), Self::activation_token_ttl()) {
+                                result.message = "Activation link expired".to_string();
+                                return result;
+                            }
+
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
-                                    // 🔴 Scope ketiga: Insert ke TableRequest
+                                    // 🔴 Link hanya bisa dipakai sekali: hash dihapus di update yang sama
                                     match trans.conn.lock().await.as_mut() {
                                         Some(conn) => {
-                                            if let Err(err) = conn.execute(
+                                            match conn.execute(
                                                 r#"UPDATE [dbo].[AuthUser]
-                                                    set [OTPGeneratedLink] = @P2, [disableLogin] = @P3,
+                                                    set [OTPGeneratedLink] = NULL, [disableLogin] = @P3,
                                                     [ActivateTime] = @P4
-                                                    WHERE AuthUserNID = @P1"#,
+                                                    WHERE AuthUserNID = @P1 AND OTPGeneratedLink = @P2"#,
                                                 &[
                                                     &row.get("AuthUserNID").unwrap_or(0),
-                                                    &otp_link,
+                                                    &hash_token(&otp_link),
                                                     &false,
                                                     &chrono::Utc::now(),
                                                 ],
                                             ).await {
-                                                result.error = Some(format!("Fauled: {:?}", err));
-                                                return result;
+                                                Ok(done) => {
+                                                    if done.total() == 0 {
+                                                        result.message = "Activation link already used".to_string();
+                                                        return result;
+                                                    }
+                                                }
+                                                Err(err) => {
+                                                    result.error = Some(format!("Fauled: {:?}", err));
+                                                    return result;
+                                                }
                                             }
                                         }
                                         None => {
@@ -284,6 +426,7 @@ impl AuthService {
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
+                            let reset_password_key = GenericService::random_string(70);
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
                                     // 🔴 Scope ketiga: Insert ke TableRequest
@@ -295,7 +438,7 @@ impl AuthService {
                                                     WHERE AuthUserNID = @P1"#,
                                                 &[
                                                     &row.get("AuthUserNID").unwrap_or(0),
-                                                    &GenericService::random_string(70),
+                                                    &hash_token(&reset_password_key),
                                                     &true,
                                                     &chrono::Utc::now(),
                                                 ],
@@ -303,6 +446,17 @@ impl AuthService {
                                                 result.error = Some(format!("Fauled: {:?}", err));
                                                 return result;
                                             }
+
+                                            // ✉️ Email link reset password lewat outbox
+                                            let email = request.email.clone().unwrap_or_default();
+                                            let data = serde_json::json!({
+                                                "email": email,
+                                                "link": MailService::reset_password_link(&email, &reset_password_key),
+                                            });
+                                            if let Err(err) = MailService::enqueue(conn, &email, "Reset password", TEMPLATE_RESET_PASSWORD, &data).await {
+                                                result.error = Some(err);
+                                                return result;
+                                            }
                                         }
                                         None => {
                                             result.error = Some("Failed to get database connection".into());
@@ -345,44 +499,99 @@ impl AuthService {
         
     }
 
-    pub async  fn change_password(connection: web::Data<Pool<ConnectionManager>>, request: ChangePasswordRequest) -> ActionResult<(), String> {
+    /// 🔑 Ganti password lewat link reset. Aturan statis policy dicek di handler, history dicek di sini
+    /// setelah reset key valid supaya endpoint ini tidak bisa dipakai menebak password lama
+    pub async  fn change_password(connection: web::Data<Pool<ConnectionManager>>, request: ChangePasswordRequest) -> ActionResult<Vec<PasswordViolation>, String> {
 
-        let mut result: ActionResult<(), String> = ActionResult::default();
-        let enc_password = encrypt_text(request.password.unwrap_or_default());
+        let mut result: ActionResult<Vec<PasswordViolation>, String> = ActionResult::default();
+        let policy = PasswordPolicy::from_env();
+        let password = request.password.unwrap_or_default();
+        let enc_password = match hash_password(&password).await {
+            Ok(hash) => hash,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
 
         match connection.clone().get().await {
             Ok(mut conn) => {
                 let query_result: Result<QueryStream, _> = conn.query(
-                    r#"SELECT AuthUserNID 
+                    r#"SELECT AuthUserNID, ResetPasswordDate 
                     FROM AuthUser 
-                    WHERE Email = @P1 and ResetPasswordKey = @P2"#, &[&request.email, &request.reset_password_key]).await;
+                    WHERE Email = @P1 and ResetPasswordKey = @P2 and ResetPasswordFlag = 1"#, &[&request.email, &hash_token(&request.reset_password_key)]).await;
                 match query_result {
                     Ok(rows) => {
                         if let Ok(Some(row)) = rows.into_row().await {
+                            if Self::is_expired(row.get::<NaiveDateTime, _>("ResetPasswordDate"), Self::reset_password_token_ttl()) {
+                                result.message = "Reset password link expired".to_string();
+                                return result;
+                            }
+
                             match Transaction::begin(&connection).await {
                                 Ok(trans) => {
-                                    // 🔴 Scope ketiga: Insert ke TableRequest
-                                    match trans.conn.lock().await.as_mut() {
-                                        Some(conn) => {
-                                            if let Err(err) = conn.execute(
-                                                r#"UPDATE [dbo].[AuthUser]
-                                                    set [ResetPasswordKey] = @P2, [ResetPasswordFlag] = @P3, [Password] = @P4
-                                                    WHERE AuthUserNID = @P1"#,
-                                                &[
-                                                    &row.get("AuthUserNID").unwrap_or(0),
-                                                    &request.reset_password_key,
-                                                    &true,
-                                                    &enc_password,
-                                                ],
-                                            ).await {
-                                                result.error = Some(format!("Fauled: {:?}", err));
-                                                return result;
+                                    let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+
+                                    // 🔴 Key hanya bisa dipakai sekali: hash dihapus di update yang sama
+                                    let applied = 'tx: {
+                                        match trans.conn.lock().await.as_mut() {
+                                            Some(conn) => {
+                                                match PasswordHistoryService::is_reused(conn, auth_usernid, &password, policy.history_count).await {
+                                                    Ok(true) => {
+                                                        result.message = "Password does not meet policy".to_string();
+                                                        result.data = Some(vec![policy.reused_violation()]);
+                                                        break 'tx None;
+                                                    }
+                                                    Ok(false) => {}
+                                                    Err(err) => {
+                                                        result.error = Some(err);
+                                                        break 'tx None;
+                                                    }
+                                                }
+
+                                                match conn.execute(
+                                                    r#"UPDATE [dbo].[AuthUser]
+                                                        set [ResetPasswordKey] = NULL, [ResetPasswordFlag] = @P3, [Password] = @P4, [PasswordChangedAt] = @P5
+                                                        WHERE AuthUserNID = @P1 AND ResetPasswordKey = @P2"#,
+                                                    &[
+                                                        &auth_usernid,
+                                                        &hash_token(&request.reset_password_key),
+                                                        &false,
+                                                        &enc_password,
+                                                        &Utc::now(),
+                                                    ],
+                                                ).await {
+                                                    Ok(done) => {
+                                                        if done.total() == 0 {
+                                                            result.message = "Reset password link already used".to_string();
+                                                            break 'tx None;
+                                                        }
+                                                    }
+                                                    Err(err) => {
+                                                        result.error = Some(format!("Fauled: {:?}", err));
+                                                        break 'tx None;
+                                                    }
+                                                }
+
+                                                if let Err(err) = PasswordHistoryService::record(conn, auth_usernid, policy.history_count).await {
+                                                    result.error = Some(err);
+                                                    break 'tx None;
+                                                }
+                                                Some(())
+                                            }
+                                            None => {
+                                                result.error = Some("Failed to get database connection".into());
+                                                break 'tx None;
                                             }
                                         }
-                                        None => {
-                                            result.error = Some("Failed to get database connection".into());
-                                            return result;
+                                    };
+
+                                    // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                                    if applied.is_none() {
+                                        if let Err(err) = trans.rollback().await {
+                                            write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
                                         }
+                                        return result;
                                     }
                     
                                     // 🔵 Commit transaksi
@@ -391,6 +600,11 @@ impl AuthService {
                                         return result;
                                     }
                     
+                                    // 🚫 Semua sesi lama harus login ulang dengan password baru
+                                    if let Err(err) = SessionService::revoke_all(connection.clone(), auth_usernid, "password_change").await {
+                                        write_log("ERROR", err.as_str());
+                                    }
+
                                     result.result = true;
                                     result.message = "Change password successfully".to_string();
                                 }
@@ -420,4 +634,145 @@ impl AuthService {
         
     }
 
-}
\ No newline at end of file
+
+    /// 🚦 Rate limit kirim ulang aktivasi per email & per IP, `Some(detik)` berarti harus menunggu.
+    /// Permintaan yang lolos langsung dicatat, baik email terdaftar maupun tidak, jadi 429 tidak membocorkan email yang ada
+    pub async fn resend_activation_retry_after(connection: web::Data<Pool<ConnectionManager>>, email: &str, ip_address: &str) -> Result<Option<i64>, String> {
+        let cooldown = Duration::seconds(env::var("RESEND_ACTIVATION_COOLDOWN_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(60));
+        let max_per_hour: i32 = env::var("RESEND_ACTIVATION_MAX_PER_HOUR").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+        let max_per_hour_ip: i32 = env::var("RESEND_ACTIVATION_MAX_PER_HOUR_IP").ok().and_then(|v| v.parse().ok()).unwrap_or(20);
+        let now = Utc::now();
+        let since = now - Duration::hours(1);
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let row = conn.query(
+            r#"SELECT COUNT(*) AS Sent, MIN(RequestedAt) AS FirstSent, MAX(RequestedAt) AS LastSent,
+                (SELECT COUNT(*) FROM ActivationResendRequest WHERE IpAddress = @P2 AND RequestedAt > @P3) AS SentFromIp,
+                (SELECT MIN(RequestedAt) FROM ActivationResendRequest WHERE IpAddress = @P2 AND RequestedAt > @P3) AS FirstSentFromIp
+            FROM ActivationResendRequest WHERE Email = @P1 AND RequestedAt > @P3"#,
+            &[&email, &ip_address, &since],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        if let Some(row) = row {
+            let sent: i32 = row.get("Sent").unwrap_or(0);
+            let sent_from_ip: i32 = row.get("SentFromIp").unwrap_or(0);
+            let first_sent: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("FirstSent").map(|dt| dt.and_utc());
+            let last_sent: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("LastSent").map(|dt| dt.and_utc());
+            let first_sent_from_ip: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("FirstSentFromIp").map(|dt| dt.and_utc());
+
+            if let Some(first_sent) = first_sent_from_ip.filter(|_| sent_from_ip >= max_per_hour_ip) {
+                return Ok(Some((first_sent + Duration::hours(1) - now).num_seconds().max(1)));
+            }
+            if let Some(first_sent) = first_sent.filter(|_| sent >= max_per_hour) {
+                return Ok(Some((first_sent + Duration::hours(1) - now).num_seconds().max(1)));
+            }
+            if let Some(last_sent) = last_sent.filter(|last_sent| *last_sent + cooldown > now) {
+                return Ok(Some((last_sent + cooldown - now).num_seconds().max(1)));
+            }
+        }
+
+        conn.execute(
+            r#"INSERT INTO [dbo].[ActivationResendRequest] ([Email],[IpAddress],[RequestedAt]) VALUES (@P1,@P2,@P3)"#,
+            &[&email, &ip_address, &now],
+        ).await.map_err(|e| format!("Failed to insert ActivationResendRequest: {:?}", e))?;
+
+        Ok(None)
+    }
+
+    /// ✉️ Buat link aktivasi baru (link lama otomatis tidak berlaku) dan kirim ulang lewat outbox.
+    /// Response selalu sama supaya tidak bisa dipakai untuk menebak email yang terdaftar.
+    pub async fn resend_activation(connection: web::Data<Pool<ConnectionManager>>, request: ResendActivationRequest) -> ActionResult<(), String> {
+
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let email = request.email.unwrap_or_default();
+
+        let row = match connection.clone().get().await {
+            Ok(mut conn) => {
+                match conn.query(
+                    r#"SELECT A.AuthUserNID, K.Fullname
+                    FROM AuthUser A LEFT JOIN UserKyc K ON K.AutoNID = A.WebCIFNID
+                    WHERE A.Email = @P1 AND A.disableLogin = 1 AND A.ActivateTime IS NULL"#, &[&email]).await {
+                    Ok(rows) => match rows.into_row().await {
+                        Ok(row) => row,
+                        Err(err) => {
+                            result.error = format!("Query execution failed: {:?}", err).into();
+                            return result;
+                        }
+                    },
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    },
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        };
+
+        result.result = true;
+        result.message = "If the account is waiting for activation, a new activation email has been sent".to_string();
+
+        let Some(row) = row else { return result };
+
+        let otp_link = GenericService::random_string(70);
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[AuthUser] SET [OTPGeneratedLink] = @P2, [OTPGeneratedLinkDate] = @P3 WHERE AuthUserNID = @P1"#,
+                                &[&row.get::<i32, _>("AuthUserNID").unwrap_or(0), &hash_token(&otp_link), &Utc::now()],
+                            ).await {
+                                result.result = false;
+                                result.error = Some(format!("Failed to update AuthUser: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            let data = serde_json::json!({
+                                "email": email,
+                                "full_name": row.get::<&str, _>("Fullname").unwrap_or_default(),
+                                "link": MailService::activation_link(&otp_link),
+                            });
+                            if let Err(err) = MailService::enqueue(conn, &email, "Aktivasi akun Anda", TEMPLATE_ACTIVATION, &data).await {
+                                result.result = false;
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+                            Some(())
+                        }
+                        None => {
+                            result.result = false;
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.result = false;
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                }
+            }
+            Err(err) => {
+                result.result = false;
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, env, fs, path::Path};
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::contexts::{connection::Transaction, logger::write_log};
+
+pub const ACK_MATCHED: &str = "matched";
+pub const ACK_UNMATCHED: &str = "unmatched";
+pub const ACK_CONFLICTING: &str = "conflicting";
+
+/// ⚙️ Konfigurasi ingest dari `.env`
+#[derive(Debug, Clone)]
+pub struct CifAckConfig {
+    pub delimiter: char,
+    pub report_dir: String,
+}
+
+impl CifAckConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let delimiter = env::var("CIF_ACK_DELIMITER").unwrap_or_else(|_| "|".to_string());
+        let report_dir = env::var("CIF_ACK_REPORT_DIR").unwrap_or_else(|_| "./cif/report".to_string());
+
+        let mut chars = delimiter.chars();
+        match (chars.next(), chars.next()) {
+            (Some(delimiter), None) if !delimiter.is_alphanumeric() && delimiter != ' ' => Ok(CifAckConfig { delimiter, report_dir }),
+            _ => Err(format!("CIF_ACK_DELIMITER must be a single symbol, got '{}'", delimiter)),
+        }
+    }
+}
+
+/// Satu baris detail file acknowledgment: `D|web_cif_nid|idcard_number|cif_id|client_id|account_status`
+#[derive(Debug, Clone)]
+struct AckRecord {
+    line: usize,
+    web_cif_nid: Option<i32>,
+    idcard_number: String,
+    cif_id: String,
+    client_id: String,
+    account_status: i32,
+}
+
+/// Hasil reconciliation satu baris
+#[derive(Debug, Clone)]
+pub struct AckLine {
+    pub line: usize,
+    /// `matched`, `unmatched` atau `conflicting`
+    pub result: &'static str,
+    /// `AutoNID` yang cocok (jika ada)
+    pub auto_nid: Option<i32>,
+    pub idcard_number: String,
+    pub cif_id: String,
+    pub client_id: String,
+    pub reason: String,
+}
+
+/// 📊 Hasil satu kali ingest
+#[derive(Debug, Default)]
+pub struct CifAckSummary {
+    pub dry_run: bool,
+    pub checksum: String,
+    pub report_path: Option<String>,
+    pub lines: Vec<AckLine>,
+}
+
+impl CifAckSummary {
+    pub fn count(&self, result: &str) -> usize {
+        self.lines.iter().filter(|line| line.result == result).count()
+    }
+}
+
+/// Data `UserKyc` yang dibandingkan dengan isi file
+struct KycMatch {
+    auto_nid: i32,
+    idcard_number: String,
+    cif_id: String,
+    client_id: String,
+    is_imported: bool,
+}
+
+pub struct CifAckService;
+
+impl CifAckService {
+
+    /// 🔎 Parse file: header `H|ACK|...`, detail `D|...`, trailer `T|<jumlah detail>`. Kesalahan struktur membatalkan
+    /// seluruh file, baris detail yang tidak lengkap masuk report sebagai `unmatched`
+    fn parse(content: &str, delimiter: char) -> Result<(Vec<AckRecord>, Vec<AckLine>), String> {
+        let lines: Vec<(usize, &str)> = content.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+
+        let (Some((_, header)), Some((_, trailer))) = (lines.first(), lines.last()) else {
+            return Err("File is empty".to_string());
+        };
+        let header: Vec<&str> = header.split(delimiter).map(str::trim).collect();
+        if header.len() < 2 || header[0] != "H" || header[1] != "ACK" {
+            return Err("Missing header 'H|ACK'".to_string());
+        }
+        let trailer: Vec<&str> = trailer.split(delimiter).map(str::trim).collect();
+        let expected: usize = match (trailer.first(), trailer.get(1)) {
+            (Some(&"T"), Some(count)) => count.parse().map_err(|_| format!("Invalid trailer record count '{}'", count))?,
+            _ => return Err("Missing trailer 'T|<record count>'".to_string()),
+        };
+
+        let details = &lines[1..lines.len() - 1];
+        if details.len() != expected {
+            return Err(format!("Trailer expects {} record(s), file has {}", expected, details.len()));
+        }
+
+        let mut records: Vec<AckRecord> = Vec::new();
+        let mut malformed: Vec<AckLine> = Vec::new();
+        for (line, text) in details {
+            let values: Vec<&str> = text.split(delimiter).map(str::trim).collect();
+            let get = |index: usize| values.get(index).copied().unwrap_or_default();
+            let mut reject = |reason: String| malformed.push(AckLine {
+                line: *line,
+                result: ACK_UNMATCHED,
+                auto_nid: None,
+                idcard_number: get(2).to_string(),
+                cif_id: get(3).to_string(),
+                client_id: get(4).to_string(),
+                reason,
+            });
+
+            if get(0) != "D" || values.len() != 6 {
+                reject("Expected 'D|web_cif_nid|idcard_number|cif_id|client_id|account_status'".to_string());
+                continue;
+            }
+            let web_cif_nid = match get(1) {
+                "" => None,
+                value => match value.parse::<i32>() {
+                    Ok(value) if value > 0 => Some(value),
+                    _ => {
+                        reject(format!("Invalid web_cif_nid '{}'", value));
+                        continue;
+                    }
+                },
+            };
+            if web_cif_nid.is_none() && get(2).is_empty() {
+                reject("Either web_cif_nid or idcard_number is required".to_string());
+                continue;
+            }
+            if get(3).is_empty() {
+                reject("cif_id is required".to_string());
+                continue;
+            }
+            let Ok(account_status) = get(5).parse::<i32>() else {
+                reject(format!("Invalid account_status '{}'", get(5)));
+                continue;
+            };
+
+            records.push(AckRecord {
+                line: *line,
+                web_cif_nid,
+                idcard_number: get(2).to_string(),
+                cif_id: get(3).to_string(),
+                client_id: get(4).to_string(),
+                account_status,
+            });
+        }
+
+        Ok((records, malformed))
+    }
+
+    /// 📥 Ingest file acknowledgment back-office: cocokkan tiap baris ke `UserKyc` lewat `AutoNID` (web_cif_nid) atau NIK,
+    /// lalu isi `CIFID`, `ClientID` & `AccountStatus`. CIFID / ClientID yang sudah terisi dengan nilai lain tidak ditimpa
+    /// (`conflicting`). Semua perubahan di satu transaksi. `dry_run` tidak menjalankan UPDATE dan transaksinya di-rollback. Report selalu ditulis
+    pub async fn ingest(connection: &Pool<ConnectionManager>, config: &CifAckConfig, file: &str, dry_run: bool) -> Result<CifAckSummary, String> {
+        let now = Utc::now();
+        let content = fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+        let checksum: String = Sha256::digest(content.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let (records, malformed) = Self::parse(&content, config.delimiter)?;
+
+        let mut summary = CifAckSummary { dry_run, checksum, ..CifAckSummary::default() };
+        summary.lines.extend(malformed);
+
+        let trans = Transaction::begin(connection).await.map_err(|e| format!("Failed to start transaction: {:?}", e))?;
+        let mut guard = trans.conn.lock().await;
+        let staged = match guard.as_mut() {
+            Some(conn) => Self::apply(conn, config, file, records, now, dry_run, &mut summary).await,
+            None => Err("Failed to get database connection".to_string()),
+        };
+        drop(guard);
+
+        match staged {
+            Ok(true) => {
+                trans.commit().await.map_err(|e| format!("Failed to commit transaction: {:?}", e))?;
+                Ok(summary)
+            }
+            // 🔙 Dry run / gagal: rollback eksplisit, jangan andalkan `Drop`
+            other => {
+                if let Err(err) = trans.rollback().await {
+                    write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                }
+                other.map(|_| summary)
+            }
+        }
+    }
+
+    /// Cocokkan & update tiap record, tulis report, lalu catat `CifAckImport` di dalam transaksi yang sudah dibuka.
+    /// `Ok(false)`: dry run, tidak ada yang perlu di-commit
+    async fn apply(conn: &mut PooledConnection<'_, ConnectionManager>, config: &CifAckConfig, file: &str, records: Vec<AckRecord>, now: DateTime<Utc>, dry_run: bool, summary: &mut CifAckSummary) -> Result<bool, String> {
+        let ingested = conn.query("SELECT TOP 1 FileName FROM CifAckImport WHERE Checksum = @P1", &[&summary.checksum])
+            .await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+        if let Some(row) = ingested {
+            return Err(format!("File was already ingested as {}", row.get::<&str, _>("FileName").unwrap_or_default()));
+        }
+
+        let mut seen: HashMap<i32, usize> = HashMap::new();
+        for record in records {
+            let query = match record.web_cif_nid {
+                Some(auto_nid) => conn.query(
+                    r#"SELECT AutoNID, IDCardNumber, CONVERT(NVARCHAR(50), CIFID) AS CIFID, CONVERT(NVARCHAR(50), ClientID) AS ClientID, IsImported
+                    FROM UserKyc WITH (UPDLOCK, ROWLOCK) WHERE AutoNID = @P1"#,
+                    &[&auto_nid],
+                ).await,
+                None => conn.query(
+                    r#"SELECT AutoNID, IDCardNumber, CONVERT(NVARCHAR(50), CIFID) AS CIFID, CONVERT(NVARCHAR(50), ClientID) AS ClientID, IsImported
+                    FROM UserKyc WITH (UPDLOCK, ROWLOCK) WHERE IDCardNumber = @P1"#,
+                    &[&record.idcard_number],
+                ).await,
+            };
+            let candidates: Vec<KycMatch> = query.map_err(|e| format!("Query execution failed: {:?}", e))?
+                .into_first_result().await.map_err(|e| format!("Query execution failed: {:?}", e))?
+                .iter()
+                .map(|row| KycMatch {
+                    auto_nid: row.get("AutoNID").unwrap_or(0),
+                    idcard_number: row.get::<&str, _>("IDCardNumber").unwrap_or_default().trim().to_string(),
+                    cif_id: row.get::<&str, _>("CIFID").unwrap_or_default().trim().to_string(),
+                    client_id: row.get::<&str, _>("ClientID").unwrap_or_default().trim().to_string(),
+                    is_imported: row.get("IsImported").unwrap_or(false),
+                })
+                .collect();
+
+            let mut line = AckLine {
+                line: record.line,
+                result: ACK_CONFLICTING,
+                auto_nid: None,
+                idcard_number: record.idcard_number.clone(),
+                cif_id: record.cif_id.clone(),
+                client_id: record.client_id.clone(),
+                reason: String::new(),
+            };
+
+            let kyc = match candidates.as_slice() {
+                [] => {
+                    line.result = ACK_UNMATCHED;
+                    line.reason = match record.web_cif_nid {
+                        Some(auto_nid) => format!("No applicant with web_cif_nid {}", auto_nid),
+                        None => "No applicant with this idcard_number".to_string(),
+                    };
+                    summary.lines.push(line);
+                    continue;
+                }
+                [kyc] => kyc,
+                _ => {
+                    line.reason = format!("idcard_number matches {} applicants", candidates.len());
+                    summary.lines.push(line);
+                    continue;
+                }
+            };
+            line.auto_nid = Some(kyc.auto_nid);
+
+            let conflict = if !record.idcard_number.is_empty() && record.idcard_number != kyc.idcard_number {
+                Some("idcard_number does not match web_cif_nid".to_string())
+            } else if let Some(first) = seen.get(&kyc.auto_nid) {
+                Some(format!("Duplicate of line {}", first))
+            } else if !kyc.is_imported {
+                Some("Applicant has not been exported".to_string())
+            } else if !kyc.cif_id.is_empty() && kyc.cif_id != record.cif_id {
+                Some(format!("CIFID is already {}", kyc.cif_id))
+            } else if !kyc.client_id.is_empty() && !record.client_id.is_empty() && kyc.client_id != record.client_id {
+                Some(format!("ClientID is already {}", kyc.client_id))
+            } else {
+                None
+            };
+            seen.entry(kyc.auto_nid).or_insert(record.line);
+            if let Some(reason) = conflict {
+                line.reason = reason;
+                summary.lines.push(line);
+                continue;
+            }
+
+            // ClientID kosong di file = belum diberikan back-office, nilai lama dipertahankan. Dry run hanya mengisi report
+            if !dry_run {
+                conn.execute(
+                    r#"UPDATE [dbo].[UserKyc] SET [CIFID] = @P2, [ClientID] = COALESCE(NULLIF(@P3, ''), [ClientID]), [AccountStatus] = @P4, [LastUpdate] = @P5
+                    WHERE AutoNID = @P1"#,
+                    &[&kyc.auto_nid, &record.cif_id, &record.client_id, &record.account_status, &now],
+                ).await.map_err(|e| format!("Failed to update UserKyc: {:?}", e))?;
+            }
+
+            line.result = ACK_MATCHED;
+            line.reason = if kyc.cif_id.is_empty() { "Assigned".to_string() } else { "Already assigned".to_string() };
+            summary.lines.push(line);
+        }
+        summary.lines.sort_by_key(|line| line.line);
+
+        // 📝 Report reconciliation, satu baris per baris detail file
+        let file_stem = Path::new(file).file_stem().and_then(|stem| stem.to_str()).unwrap_or("ack");
+        let report_name = format!("{}_report_{}{}.txt", file_stem, now.format("%Y%m%d%H%M%S"), if dry_run { "_dryrun" } else { "" });
+        let mut report = format!(
+            "# {} sha256={} matched={} unmatched={} conflicting={}{}\nline|result|web_cif_nid|idcard_number|cif_id|client_id|reason\n",
+            file, summary.checksum, summary.count(ACK_MATCHED), summary.count(ACK_UNMATCHED), summary.count(ACK_CONFLICTING),
+            if dry_run { " (dry run)" } else { "" },
+        );
+        for line in &summary.lines {
+            report.push_str(&format!(
+                "{}|{}|{}|{}|{}|{}|{}\n",
+                line.line, line.result, line.auto_nid.map(|nid| nid.to_string()).unwrap_or_default(),
+                line.idcard_number, line.cif_id, line.client_id, line.reason,
+            ));
+        }
+        fs::create_dir_all(&config.report_dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+        let report_path = Path::new(&config.report_dir).join(&report_name);
+        fs::write(&report_path, report).map_err(|e| format!("Failed to write {}: {}", report_path.display(), e))?;
+        summary.report_path = Some(report_path.display().to_string());
+
+        if dry_run {
+            return Ok(false);
+        }
+
+        let file_name = Path::new(file).file_name().and_then(|name| name.to_str()).unwrap_or(file).to_string();
+        conn.execute(
+            r#"INSERT INTO [dbo].[CifAckImport] ([FileName],[Checksum],[Matched],[Unmatched],[Conflicting],[ReportFile],[CreatedAt])
+            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7)"#,
+            &[
+                &file_name,
+                &summary.checksum,
+                &(summary.count(ACK_MATCHED) as i32),
+                &(summary.count(ACK_UNMATCHED) as i32),
+                &(summary.count(ACK_CONFLICTING) as i32),
+                &report_name,
+                &now,
+            ],
+        ).await.map_err(|e| format!("Failed to insert CifAckImport: {:?}", e))?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,416 @@
+use std::{env, fs, path::{Path, PathBuf}};
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use tokio_stream::StreamExt;
+
+use crate::contexts::{connection::Transaction, logger::write_log};
+
+/// Nasabah baru, `UserKyc.IsImported` 0 → 1
+pub const CIF_RECORD_NEW: &str = "N";
+/// Perubahan data nasabah yang sudah di-import (profile change yang disetujui), `UserKyc.PendingCIFNID` → 0
+pub const CIF_RECORD_UPDATE: &str = "U";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CifFileFormat {
+    Delimited(char),
+    FixedWidth,
+}
+
+impl CifFileFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            CifFileFormat::Delimited(_) => "delimited",
+            CifFileFormat::FixedWidth => "fixed",
+        }
+    }
+
+    /// Gabungkan satu baris. Delimited: nilai tidak boleh berisi delimiter, fixed: nilai tidak boleh melebihi lebar kolom
+    fn line(self, values: &[(&str, String, usize)]) -> Result<String, String> {
+        let mut line = String::new();
+        for (index, (name, value, width)) in values.iter().enumerate() {
+            let value = value.replace(['\r', '\n'], " ");
+            let value = value.trim();
+            match self {
+                CifFileFormat::Delimited(delimiter) => {
+                    if value.contains(delimiter) {
+                        return Err(format!("'{}' contains the delimiter '{}'", name, delimiter));
+                    }
+                    if index > 0 {
+                        line.push(delimiter);
+                    }
+                    line.push_str(value);
+                }
+                CifFileFormat::FixedWidth => {
+                    let length = value.chars().count();
+                    if length > *width {
+                        return Err(format!("'{}' is longer than {} characters", name, width));
+                    }
+                    line.push_str(value);
+                    line.extend(std::iter::repeat_n(' ', width - length));
+                }
+            }
+        }
+        Ok(line)
+    }
+}
+
+/// ⚙️ Konfigurasi export dari `.env`
+#[derive(Debug, Clone)]
+pub struct CifExportConfig {
+    pub dir: String,
+    pub format: CifFileFormat,
+    /// Maksimum record per file
+    pub batch_size: i32,
+    /// Record yang dilewati baru dipilih lagi setelah datanya berubah atau setelah selang ini
+    pub skip_retry: Duration,
+}
+
+impl CifExportConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let dir = env::var("CIF_EXPORT_DIR").unwrap_or_else(|_| "./cif/export".to_string());
+        let batch_size: i32 = env::var("CIF_EXPORT_BATCH").ok().and_then(|v| v.parse().ok()).unwrap_or(500);
+        let skip_retry = Duration::hours(env::var("CIF_EXPORT_SKIP_RETRY_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(24));
+
+        let format = match env::var("CIF_EXPORT_FORMAT").unwrap_or_else(|_| "delimited".to_string()).as_str() {
+            "delimited" => {
+                let delimiter = env::var("CIF_EXPORT_DELIMITER").unwrap_or_else(|_| "|".to_string());
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(delimiter), None) if !delimiter.is_alphanumeric() && delimiter != ' ' => CifFileFormat::Delimited(delimiter),
+                    _ => return Err(format!("CIF_EXPORT_DELIMITER must be a single symbol, got '{}'", delimiter)),
+                }
+            }
+            "fixed" => CifFileFormat::FixedWidth,
+            other => return Err(format!("Unknown CIF_EXPORT_FORMAT '{}', expected 'delimited' or 'fixed'", other)),
+        };
+
+        if batch_size <= 0 {
+            return Err("CIF_EXPORT_BATCH must be greater than 0".to_string());
+        }
+
+        Ok(CifExportConfig { dir, format, batch_size, skip_retry })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    /// Kolom apa adanya (`A.` = `UserKyc`, `B.` = `TableRequest`)
+    Column(&'static str),
+    /// Tanggal `yyyymmdd`
+    Date(&'static str),
+    /// `CIFLookup.CIFLookupDescription` untuk `CIFLookupID` tersebut
+    Lookup(&'static str, &'static str),
+    /// `Country.CIFISOCode`
+    Country(&'static str),
+    /// `ProvinceCity.SBRCityName`
+    City(&'static str),
+    /// `ProvinceCity.SBRProvinceName`
+    Province(&'static str),
+}
+
+impl Source {
+    fn select(self) -> String {
+        match self {
+            Source::Column(column) => format!("CONVERT(NVARCHAR(4000), {})", column),
+            Source::Date(column) => format!("CONVERT(NVARCHAR(8), {}, 112)", column),
+            Source::Lookup(lookup_id, column) => format!(
+                "(SELECT TOP 1 L.CIFLookupDescription FROM CIFLookup L WHERE L.CIFLookupID = '{}' AND L.CIFLookupInteger = {})",
+                lookup_id, column
+            ),
+            Source::Country(column) => format!("(SELECT TOP 1 C.CIFISOCode FROM Country C WHERE C.CountryNID = {})", column),
+            Source::City(column) => format!("(SELECT TOP 1 P.SBRCityName FROM ProvinceCity P WHERE P.ProvinceCityNID = {})", column),
+            Source::Province(column) => format!("(SELECT TOP 1 P.SBRProvinceName FROM ProvinceCity P WHERE P.ProvinceCityNID = {})", column),
+        }
+    }
+
+    /// Kolom ID asli untuk sumber yang lewat tabel lookup
+    fn raw_column(self) -> Option<&'static str> {
+        match self {
+            Source::Column(_) | Source::Date(_) => None,
+            Source::Lookup(_, column) | Source::Country(column) | Source::City(column) | Source::Province(column) => Some(column),
+        }
+    }
+}
+
+struct CifField {
+    name: &'static str,
+    width: usize,
+    source: Source,
+}
+
+const fn field(name: &'static str, width: usize, source: Source) -> CifField {
+    CifField { name, width, source }
+}
+
+/// 📄 Layout record detail setelah `record_type`, `web_cif_nid` & `cif_id`. Lebar kolom hanya dipakai format fixed
+const CIF_LAYOUT: &[CifField] = &[
+    field("full_name", 100, Source::Column("A.Fullname")),
+    field("mother_name", 100, Source::Column("A.MotherName")),
+    field("idcard_number", 30, Source::Column("A.IDCardNumber")),
+    field("idcard_expire_date", 8, Source::Date("A.IDCardExpireDate")),
+    field("idcard_country", 50, Source::Column("A.IDCardCountry")),
+    field("nationality", 3, Source::Country("A.Nationality")),
+    field("sex", 10, Source::Lookup("Sex", "A.Sex")),
+    field("birth_place", 50, Source::Column("A.BirthPlace")),
+    field("birth_date", 8, Source::Date("A.BirthDate")),
+    field("birth_country", 50, Source::Column("A.BirthCountry")),
+    field("religion", 10, Source::Lookup("Religion", "A.Religion")),
+    field("marital_status", 10, Source::Lookup("MaritalStatus", "A.MaritalStatus")),
+    field("education", 10, Source::Lookup("Educational", "A.Education")),
+    field("idcard_address", 150, Source::Column("A.IDCardAddress")),
+    field("idcard_rt", 5, Source::Column("A.IDCardRT")),
+    field("idcard_rw", 5, Source::Column("A.IDCardRW")),
+    field("idcard_subdistrict", 50, Source::Column("A.IDCardSubdistrict")),
+    field("idcard_district", 50, Source::Column("A.IDCardDistrict")),
+    field("idcard_city", 50, Source::City("A.IDCardCity")),
+    field("idcard_province", 50, Source::Province("A.IDCardCity")),
+    field("idcard_zipcode", 10, Source::Column("A.IDCardZipcode")),
+    field("domicile_address", 150, Source::Column("A.DomicileAddress")),
+    field("domicile_rt", 5, Source::Column("A.DomicileRT")),
+    field("domicile_rw", 5, Source::Column("A.DomicileRW")),
+    field("domicile_subdistrict", 50, Source::Column("A.DomicileSubdistrict")),
+    field("domicile_district", 50, Source::Column("A.DomicileDistrict")),
+    field("domicile_city", 50, Source::City("A.DomicileCity")),
+    field("domicile_province", 50, Source::Province("A.DomicileCity")),
+    field("domicile_zipcode", 10, Source::Column("A.DomicileZipcode")),
+    field("residency_status", 10, Source::Lookup("ResidencyStatus", "B.ResidencyNStatus")),
+    field("beneficiary_owner", 10, Source::Lookup("AssetOwner", "B.CIFInvestorBeneficiaryOwner")),
+    field("mobile_phone", 20, Source::Column("A.MobilePhone")),
+    field("email", 100, Source::Column("A.Email")),
+    field("npwp_number", 20, Source::Column("A.NPWPNumber")),
+    field("company_name", 100, Source::Column("A.CompanyName")),
+    field("company_address", 150, Source::Column("A.CompanyAddress")),
+    // Kode pekerjaan, jabatan & bidang usaha sudah berupa kode JabatanMapping
+    field("occupation", 10, Source::Column("A.Occupation")),
+    field("position", 10, Source::Column("A.Position")),
+    field("nature_of_business", 10, Source::Column("A.NatureOfBusiness")),
+    field("income_per_annum", 10, Source::Lookup("IncomePerAnnum", "A.IncomePerAnnum")),
+    field("fund_source", 10, Source::Lookup("FundSource", "A.Fundsource")),
+    field("bank_name", 50, Source::Column("A.BankName")),
+    field("bank_branch", 50, Source::Column("A.BankBranch")),
+    field("bank_account_number", 30, Source::Column("A.BankAccountNumber")),
+    field("bank_account_holder", 100, Source::Column("A.BankAccountHolder")),
+    field("investment_objective", 10, Source::Lookup("InvestmentObjectives", "A.InvestmentObjectives")),
+    field("risk", 10, Source::Lookup("Risk", "A.Risk")),
+];
+
+/// Satu record yang siap ditulis
+struct CifRecord {
+    auto_nid: i32,
+    record_type: &'static str,
+    change_nid: Option<i32>,
+    line: String,
+}
+
+/// 📊 Hasil satu kali export
+#[derive(Debug, Default)]
+pub struct CifExportSummary {
+    pub dry_run: bool,
+    pub batch_nid: Option<i32>,
+    pub file_path: Option<String>,
+    pub new_records: usize,
+    pub update_records: usize,
+    /// SHA-256 (hex) seluruh baris detail, sama dengan isi trailer
+    pub checksum: String,
+    /// `AutoNID` + alasan record tidak diexport. Ditandai di `UserKyc.ExportSkippedAt` supaya batch berikutnya tetap maju
+    pub skipped: Vec<(i32, String)>,
+}
+
+pub struct CifExportService;
+
+impl CifExportService {
+
+    /// 📤 Export aplikasi yang sudah disetujui & belum di-import, plus perubahan data yang belum terkirim, ke satu file CIF.
+    /// Record yang gagal di-resolve dilewati dan ditandai `ExportSkippedAt`. `dry_run`: file tidak ditulis dan tidak ada yang ditandai
+    pub async fn export(connection: &Pool<ConnectionManager>, config: &CifExportConfig, dry_run: bool) -> Result<CifExportSummary, String> {
+        let mut summary = CifExportSummary { dry_run, ..CifExportSummary::default() };
+        let now = Utc::now();
+
+        let mut columns: Vec<String> = Vec::new();
+        for (index, field) in CIF_LAYOUT.iter().enumerate() {
+            columns.push(format!("{} AS [F{}]", field.source.select(), index));
+            if let Some(raw) = field.source.raw_column() {
+                columns.push(format!("CONVERT(NVARCHAR(50), {}) AS [R{}]", raw, index));
+            }
+        }
+
+        let trans = Transaction::begin(connection).await.map_err(|e| format!("Failed to start transaction: {:?}", e))?;
+        let mut guard = trans.conn.lock().await;
+        let staged = match guard.as_mut() {
+            Some(conn) => Self::stage(conn, config, &columns, now, dry_run, &mut summary).await,
+            None => Err("Failed to get database connection".to_string()),
+        };
+        drop(guard);
+
+        // 🔙 Dry run / tidak ada yang ditandai / gagal: rollback eksplisit, jangan andalkan `Drop`
+        let staged = match staged {
+            Ok(staged) if !dry_run && (staged.is_some() || !summary.skipped.is_empty()) => staged,
+            other => {
+                if let Err(err) = trans.rollback().await {
+                    write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                }
+                return other.map(|_| summary);
+            }
+        };
+        if let Err(err) = trans.commit().await {
+            if let Some((_, _, tmp_path)) = &staged {
+                let _ = fs::remove_file(tmp_path);
+            }
+            return Err(format!("Failed to commit transaction: {:?}", err));
+        }
+
+        // Hanya record yang dilewati, tidak ada file
+        let Some((batch_nid, path, tmp_path)) = staged else { return Ok(summary) };
+        if let Err(err) = fs::rename(&tmp_path, &path) {
+            // Baris sudah ditandai, file .tmp dibiarkan supaya bisa di-rename manual
+            let message = format!("Batch {} committed but {} could not be renamed: {}", batch_nid, tmp_path.display(), err);
+            write_log("ERROR", message.as_str());
+            return Err(message);
+        }
+
+        summary.batch_nid = Some(batch_nid);
+        summary.file_path = Some(path.display().to_string());
+        Ok(summary)
+    }
+
+    /// Pilih & kunci record, tandai, lalu tulis file `.tmp` di dalam transaksi yang sudah dibuka.
+    /// `Ok(None)`: tidak ada file (dry run / tidak ada record yang lolos), record yang dilewati tetap ditandai
+    async fn stage(conn: &mut PooledConnection<'_, ConnectionManager>, config: &CifExportConfig, columns: &[String], now: DateTime<Utc>, dry_run: bool, summary: &mut CifExportSummary) -> Result<Option<(i32, PathBuf, PathBuf)>, String> {
+        let mut records: Vec<CifRecord> = Vec::new();
+        {
+            // 🔒 Kunci baris yang dipilih supaya export paralel / profile change tidak menyelip
+            let mut rows = conn.query(
+                format!(r#"SELECT TOP (@P1) A.AutoNID, A.IsImported, A.PendingCIFNID, CONVERT(NVARCHAR(50), A.CIFID) AS CIFID, {}
+                FROM UserKyc A WITH (UPDLOCK, ROWLOCK)
+                LEFT JOIN TableRequest B ON B.WebCIFNID = A.AutoNID
+                WHERE A.IsFinished = 1 AND A.IsApproved = 1 AND A.IsRejected = 0
+                    AND (A.IsImported = 0 OR A.PendingCIFNID <> 0)
+                    AND (A.ExportSkippedAt IS NULL OR A.LastUpdate > A.ExportSkippedAt OR A.ExportSkippedAt < @P2)
+                ORDER BY A.AutoNID"#, columns.join(", ")),
+                &[&config.batch_size, &(now - config.skip_retry)],
+            ).await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+            while let Some(item) = rows.try_next().await.map_err(|e| format!("Query execution failed: {:?}", e))? {
+                let Some(row) = item.as_row() else { continue };
+
+                let auto_nid: i32 = row.get("AutoNID").unwrap_or(0);
+                let pending_cif: i32 = row.get("PendingCIFNID").unwrap_or(0);
+                let (record_type, change_nid) = if row.get::<bool, _>("IsImported").unwrap_or(false) {
+                    (CIF_RECORD_UPDATE, Some(pending_cif))
+                } else {
+                    (CIF_RECORD_NEW, None)
+                };
+
+                let mut values: Vec<(&str, String, usize)> = vec![
+                    ("record_type", record_type.to_string(), 1),
+                    ("web_cif_nid", format!("{:010}", auto_nid), 10),
+                    ("cif_id", row.get::<&str, _>("CIFID").unwrap_or_default().to_string(), 20),
+                ];
+                let mut unresolved: Option<String> = None;
+                for (index, field) in CIF_LAYOUT.iter().enumerate() {
+                    let value = row.get::<&str, _>(format!("F{}", index).as_str()).unwrap_or_default().trim().to_string();
+                    let raw = row.get::<&str, _>(format!("R{}", index).as_str()).unwrap_or_default().trim();
+                    if value.is_empty() && !raw.is_empty() && raw != "0" {
+                        unresolved = Some(format!("Unresolved lookup for '{}': {}", field.name, raw));
+                        break;
+                    }
+                    let value = if matches!(field.source, Source::Date(_)) && value == "19000101" { String::new() } else { value };
+                    values.push((field.name, value, field.width));
+                }
+
+                match unresolved.map_or_else(|| config.format.line(&values), Err) {
+                    Ok(line) => records.push(CifRecord { auto_nid, record_type, change_nid, line }),
+                    Err(reason) => summary.skipped.push((auto_nid, reason)),
+                }
+            }
+        }
+
+        // ⏭️ Tandai yang dilewati supaya tidak terpilih lagi di batch berikutnya sampai datanya diperbaiki
+        if !dry_run {
+            for (auto_nid, reason) in &summary.skipped {
+                let reason: String = reason.chars().take(500).collect();
+                conn.execute(
+                    r#"UPDATE [dbo].[UserKyc] SET [ExportSkippedAt] = @P2, [ExportSkipReason] = @P3 WHERE AutoNID = @P1"#,
+                    &[auto_nid, &now, &reason],
+                ).await.map_err(|e| format!("Failed to update UserKyc: {:?}", e))?;
+            }
+        }
+
+        summary.new_records = records.iter().filter(|record| record.record_type == CIF_RECORD_NEW).count();
+        summary.update_records = records.len() - summary.new_records;
+
+        let mut hasher = Sha256::new();
+        for record in &records {
+            hasher.update(record.line.as_bytes());
+            hasher.update(b"\n");
+        }
+        summary.checksum = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        if dry_run || records.is_empty() {
+            return Ok(None);
+        }
+
+        let file_name = format!("CIF_{}", now.format("%Y%m%d%H%M%S"));
+        let batch_nid: i32 = match conn.query(
+            r#"INSERT INTO [dbo].[CifExportBatch] ([FileName],[Format],[RecordCount],[Checksum],[CreatedAt])
+            OUTPUT INSERTED.BatchNID
+            VALUES (@P1,@P2,@P3,@P4,@P5)"#,
+            &[&file_name, &config.format.as_str(), &(records.len() as i32), &summary.checksum, &now],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row.get("BatchNID").unwrap_or(0),
+                _ => return Err("Failed to insert CifExportBatch".to_string()),
+            },
+            Err(err) => return Err(format!("Failed to insert CifExportBatch: {:?}", err)),
+        };
+        let file_name = format!("{}_{:06}.txt", file_name, batch_nid);
+
+        conn.execute(
+            r#"UPDATE [dbo].[CifExportBatch] SET [FileName] = @P2 WHERE BatchNID = @P1"#,
+            &[&batch_nid, &file_name],
+        ).await.map_err(|e| format!("Failed to update CifExportBatch: {:?}", e))?;
+
+        for record in &records {
+            conn.execute(
+                r#"INSERT INTO [dbo].[CifExportItem] ([BatchNID],[AutoNID],[RecordType],[ChangeNID]) VALUES (@P1,@P2,@P3,@P4)"#,
+                &[&batch_nid, &record.auto_nid, &record.record_type, &record.change_nid],
+            ).await.map_err(|e| format!("Failed to insert CifExportItem: {:?}", e))?;
+
+            // Record baru sudah membawa data terbaru, perubahan yang pending ikut terkirim
+            conn.execute(
+                r#"UPDATE [dbo].[UserKyc] SET [IsImported] = 1, [PendingCIFNID] = 0, [ExportSkippedAt] = NULL, [ExportSkipReason] = NULL WHERE AutoNID = @P1"#,
+                &[&record.auto_nid],
+            ).await.map_err(|e| format!("Failed to update UserKyc: {:?}", e))?;
+        }
+
+        // 📝 Header + detail + trailer (jumlah record & checksum)
+        let format = config.format;
+        let header = format.line(&[
+            ("record_type", "H".to_string(), 1),
+            ("file_type", "CIF".to_string(), 3),
+            ("batch_nid", format!("{:010}", batch_nid), 10),
+            ("created_at", now.format("%Y%m%d%H%M%S").to_string(), 14),
+        ])?;
+        let trailer = format.line(&[
+            ("record_type", "T".to_string(), 1),
+            ("record_count", format!("{:09}", records.len()), 9),
+            ("checksum", summary.checksum.clone(), 64),
+        ])?;
+        let mut content = format!("{}\n", header);
+        for record in &records {
+            content.push_str(&record.line);
+            content.push('\n');
+        }
+        content.push_str(&trailer);
+        content.push('\n');
+
+        // File ditulis ke .tmp lalu di-rename supaya back-office tidak pernah membaca file setengah jadi
+        fs::create_dir_all(&config.dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+        let path = Path::new(&config.dir).join(&file_name);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        Ok(Some((batch_nid, path, tmp_path)))
+    }
+}
@@ -0,0 +1,324 @@
+use std::env;
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+use crate::contexts::{
+    connection::Transaction,
+    crypto::hash_token,
+    jwt_session::Claims,
+    logger::write_log,
+    model::{ActionResult, ChangeEmailRequest},
+};
+use super::{
+    generic_service::GenericService,
+    login_guard_service::LoginGuardService,
+    mail_service::{MailService, TEMPLATE_CHANGE_EMAIL, TEMPLATE_EMAIL_CHANGED},
+    session_service::SessionService,
+};
+
+pub struct EmailChangeService;
+
+impl EmailChangeService {
+
+    /// ⏳ Masa berlaku link konfirmasi email baru (default 24 jam)
+    fn token_ttl() -> Duration {
+        let hours: i64 = env::var("EMAIL_CHANGE_TTL_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(24);
+        Duration::hours(hours)
+    }
+
+    /// 🚦 Batasi jumlah permintaan per jam supaya tidak dipakai untuk spam ke alamat orang lain. `Some(detik)` berarti harus menunggu
+    pub async fn retry_after(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> Result<Option<i64>, String> {
+        let max_per_hour: i32 = env::var("EMAIL_CHANGE_MAX_PER_HOUR").ok().and_then(|v| v.parse().ok()).unwrap_or(3);
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let row = conn.query(
+            r#"SELECT COUNT(*) AS Sent, MIN(RequestedAt) AS FirstSent FROM AuthEmailChange
+            WHERE AuthUserNID = @P1 AND RequestedAt > @P2"#,
+            &[&auth_usernid, &(now - Duration::hours(1))],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        let Some(row) = row else { return Ok(None) };
+        let sent: i32 = row.get("Sent").unwrap_or(0);
+        let first_sent: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("FirstSent").map(|dt| dt.and_utc());
+
+        if let Some(first_sent) = first_sent.filter(|_| sent >= max_per_hour) {
+            return Ok(Some((first_sent + Duration::hours(1) - now).num_seconds().max(1)));
+        }
+
+        Ok(None)
+    }
+
+    /// ✉️ Minta ganti email: password sekarang wajib benar, link konfirmasi dikirim ke email baru.
+    /// Email login belum berubah sampai link dibuka; permintaan sebelumnya yang belum dikonfirmasi dibatalkan
+    pub async fn request_change(connection: web::Data<Pool<ConnectionManager>>, session: Claims, request: ChangeEmailRequest, ip_address: &str, device_info: &str) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let new_email = request.new_email.unwrap_or_default().trim().to_string();
+        let password = request.password.unwrap_or_default();
+
+        let old_email = match connection.clone().get().await {
+            Ok(mut conn) => {
+                let row = match conn.query(
+                    r#"SELECT Email, Password FROM AuthUser WHERE AuthUserNID = @P1"#, &[&session.auth_usernid]).await {
+                    Ok(rows) => match rows.into_row().await {
+                        Ok(Some(row)) => row,
+                        _ => {
+                            result.message = "No user found".to_string();
+                            return result;
+                        }
+                    },
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    }
+                };
+                let old_email = row.get::<&str, _>("Email").unwrap_or_default().to_string();
+
+                match LoginGuardService::reauthenticate(connection.clone(), &old_email, &password, row.get::<&str, _>("Password").unwrap_or_default(), ip_address, device_info).await {
+                    Ok(true) => {},
+                    Ok(false) => {
+                        result.message = "Invalid password".to_string();
+                        return result;
+                    },
+                    Err(err) => {
+                        result.error = err.into();
+                        return result;
+                    }
+                }
+
+                if old_email.eq_ignore_ascii_case(&new_email) {
+                    result.message = "New email must be different from current email".to_string();
+                    return result;
+                }
+
+                match conn.query(r#"SELECT AuthUserNID FROM AuthUser WHERE Email = @P1"#, &[&new_email]).await {
+                    Ok(rows) => {
+                        if let Ok(Some(_)) = rows.into_row().await {
+                            result.message = "Email already exists".to_string();
+                            return result;
+                        }
+                    }
+                    Err(err) => {
+                        result.error = format!("Query execution failed: {:?}", err).into();
+                        return result;
+                    }
+                }
+
+                old_email
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        };
+
+        let token = GenericService::random_string(70);
+        let ttl = Self::token_ttl();
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[AuthEmailChange] SET [CancelledAt] = @P2, [TokenHash] = NULL
+                                WHERE AuthUserNID = @P1 AND ConfirmedAt IS NULL AND CancelledAt IS NULL"#,
+                                &[&session.auth_usernid, &now],
+                            ).await {
+                                result.error = Some(format!("Failed to update AuthEmailChange: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            if let Err(err) = conn.execute(
+                                r#"INSERT INTO [dbo].[AuthEmailChange] ([AuthUserNID],[OldEmail],[NewEmail],[TokenHash],[IpAddress],[RequestedAt],[ExpiresAt])
+                                VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7)"#,
+                                &[&session.auth_usernid, &old_email, &new_email, &hash_token(&token), &ip_address, &now, &(now + ttl)],
+                            ).await {
+                                result.error = Some(format!("Failed to insert AuthEmailChange: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            let data = serde_json::json!({
+                                "email": new_email,
+                                "old_email": old_email,
+                                "link": MailService::change_email_link(&token),
+                                "ttl_hours": ttl.num_hours(),
+                            });
+                            if let Err(err) = MailService::enqueue(conn, &new_email, "Konfirmasi email baru Anda", TEMPLATE_CHANGE_EMAIL, &data).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = format!("Confirmation link sent to {}", new_email);
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// ✅ Buka link konfirmasi: email di AuthUser & UserKyc diganti, email lama diberi notifikasi,
+    /// lalu semua sesi dicabut karena claim `email` di token lama sudah tidak berlaku
+    pub async fn confirm(connection: web::Data<Pool<ConnectionManager>>, token: String) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        let row = match connection.clone().get().await {
+            Ok(mut conn) => match conn.query(
+                r#"SELECT EmailChangeNID, AuthUserNID, OldEmail, NewEmail, ExpiresAt FROM AuthEmailChange
+                WHERE TokenHash = @P1 AND ConfirmedAt IS NULL AND CancelledAt IS NULL"#, &[&hash_token(&token)]).await {
+                Ok(rows) => match rows.into_row().await {
+                    Ok(Some(row)) => row,
+                    _ => {
+                        result.message = "Invalid or already used link".to_string();
+                        return result;
+                    }
+                },
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            },
+        };
+
+        if row.get::<NaiveDateTime, _>("ExpiresAt").is_none_or(|dt| dt.and_utc() < Utc::now()) {
+            result.message = "Change email link expired".to_string();
+            return result;
+        }
+
+        let email_change_nid: i64 = row.get("EmailChangeNID").unwrap_or(0);
+        let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+        let old_email = row.get::<&str, _>("OldEmail").unwrap_or_default().to_string();
+        let new_email = row.get::<&str, _>("NewEmail").unwrap_or_default().to_string();
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            // 🔴 Link hanya bisa dipakai sekali: hash dihapus di update yang sama
+                            match conn.execute(
+                                r#"UPDATE [dbo].[AuthEmailChange] SET [ConfirmedAt] = @P2, [TokenHash] = NULL
+                                WHERE EmailChangeNID = @P1 AND ConfirmedAt IS NULL AND CancelledAt IS NULL"#,
+                                &[&email_change_nid, &now],
+                            ).await.map(|done| done.total()) {
+                                Ok(0) => {
+                                    result.message = "Invalid or already used link".to_string();
+                                    break 'tx None;
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update AuthEmailChange: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+
+                            // Email baru bisa saja sudah dipakai akun lain sejak link dikirim
+                            match conn.execute(
+                                r#"UPDATE [dbo].[AuthUser] SET [Email] = @P2
+                                WHERE AuthUserNID = @P1 AND Email = @P3 AND NOT EXISTS (SELECT 1 FROM AuthUser WHERE Email = @P2)"#,
+                                &[&auth_usernid, &new_email, &old_email],
+                            ).await.map(|done| done.total()) {
+                                Ok(0) => {
+                                    result.message = "Email already exists".to_string();
+                                    break 'tx None;
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update AuthUser: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[UserKyc] SET [Email] = @P2, [LastUpdate] = @P3
+                                WHERE AutoNID = (SELECT WebCIFNID FROM AuthUser WHERE AuthUserNID = @P1)"#,
+                                &[&auth_usernid, &new_email, &now],
+                            ).await {
+                                result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            let data = serde_json::json!({
+                                "email": new_email,
+                                "old_email": old_email,
+                                "changed_at": now.format("%Y-%m-%d %H:%M").to_string(),
+                            });
+                            if let Err(err) = MailService::enqueue(conn, &old_email, "Email akun Anda telah diganti", TEMPLATE_EMAIL_CHANGED, &data).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Link tetap bisa dipakai jika gagal di tengah jalan (misalnya email sudah dipakai akun lain)
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        }
+
+        // 🚫 Token lama masih membawa email lama, semua sesi harus login ulang
+        if let Err(err) = SessionService::revoke_all(connection, auth_usernid, "email_change").await {
+            write_log("ERROR", err.as_str());
+        }
+
+        result.result = true;
+        result.message = "Email changed successfully".to_string();
+        result
+    }
+}
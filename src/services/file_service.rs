@@ -1,7 +1,9 @@
 
 use base64::{decode, DecodeError};
-use chrono::Local;
-use std::{env, fs, io::Write, path::PathBuf};
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{Local, Utc};
+use std::{env, fs, io::{ErrorKind, Write}, path::{Component, Path, PathBuf}};
 
 use super::generic_service::GenericService;
 pub struct FileService;
@@ -47,4 +49,51 @@ impl FileService {
         Ok(format!("{}/{}.{}", format!("{}{}", random_str, date_str), new_file_name, format))
     }
 
-}
\ No newline at end of file
+    /// 📎 Simpan upload lalu catat pemiliknya di `UserAsset`. Hanya file yang tercatat milik user yang boleh
+    /// dihapus saat akunnya ditutup, path di `UserKyc` bisa diisi apa saja oleh client
+    pub async fn save_upload(connection: &Pool<ConnectionManager>, auth_usernid: i32, email: &str, base64_str: &str, file_name: &str) -> Result<String, String> {
+        let saved_path = Self::save_base64_image(email, base64_str, file_name)?;
+
+        let recorded = match connection.get().await {
+            Ok(mut conn) => conn.execute(
+                r#"IF NOT EXISTS (SELECT 1 FROM [dbo].[UserAsset] WHERE AssetPath = @P1 AND AuthUserNID = @P2)
+                INSERT INTO [dbo].[UserAsset] ([AssetPath],[AuthUserNID],[CreatedAt]) VALUES (@P1,@P2,@P3)"#,
+                &[&saved_path, &auth_usernid, &Utc::now()],
+            ).await.map(|_| ()).map_err(|e| format!("Failed to insert UserAsset: {:?}", e)),
+            Err(err) => Err(format!("Internal Server error: {:?}", err)),
+        };
+        if let Err(err) = recorded {
+            // File tanpa pemilik tidak akan pernah ikut terhapus, buang sekarang
+            let _ = Self::delete_asset(&saved_path);
+            return Err(err);
+        }
+
+        Ok(saved_path)
+    }
+
+    /// 🗑️ Hapus file upload (path relatif seperti yang disimpan di database) beserta foldernya jika sudah kosong.
+    /// Return `false` jika file memang sudah tidak ada
+    pub fn delete_asset(relative_path: &str) -> Result<bool, String> {
+        let path_env = env::var("PATH_ASSET").expect("PATH_ASSET harus diatur");
+
+        // Path dari database tidak boleh keluar dari PATH_ASSET
+        let relative = Path::new(relative_path.trim());
+        if relative.as_os_str().is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(format!("Invalid asset path: {}", relative_path));
+        }
+
+        let full_path = PathBuf::from(&path_env).join(relative);
+        let deleted = match fs::remove_file(&full_path) {
+            Ok(()) => true,
+            Err(err) if err.kind() == ErrorKind::NotFound => false,
+            Err(err) => return Err(format!("Failed to delete {}: {}", relative_path, err)),
+        };
+
+        // Folder upload dibuat per file, gagal hapus berarti masih ada isinya dan dibiarkan
+        if let Some(folder) = full_path.parent().filter(|folder| *folder != Path::new(&path_env)) {
+            let _ = fs::remove_dir(folder);
+        }
+
+        Ok(deleted)
+    }
+}
@@ -1,13 +1,30 @@
+use std::{env, net::IpAddr};
 use actix_web::{error, web, HttpRequest, HttpResponse, Responder, Result};
 use bb8::Pool;
 use bb8_tiberius::ConnectionManager;
 use serde_json::json;
 use sha2::{Digest, Sha256};
 use tiberius::QueryStream;
+use once_cell::sync::Lazy;
 use rand::{rng, Rng};
 
 use crate::contexts::model::{ActionResult, Company};
 
+/// Panjang maksimum User-Agent yang disimpan (`AuthLockout.DeviceInfo` NVARCHAR(500))
+pub const DEVICE_INFO_MAX_LENGTH: usize = 500;
+
+/// Panjang kolom IP (`AuthSession`, `AuthRefreshToken`, dsb. NVARCHAR(64))
+pub const IP_ADDRESS_MAX_LENGTH: usize = 64;
+
+/// Reverse proxy yang boleh mengisi `X-Forwarded-For`, dari `TRUSTED_PROXIES` (IP dipisah koma)
+static TRUSTED_PROXIES: Lazy<Vec<IpAddr>> = Lazy::new(|| {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect()
+});
+
 pub struct GenericService;
 
 impl GenericService {
@@ -119,25 +136,48 @@ impl GenericService {
         hashed_string
     }
 
+    /// 🌐 IP client. `X-Forwarded-For` hanya dipercaya jika request datang dari proxy di `TRUSTED_PROXIES`,
+    /// diambil entry paling kanan yang bukan proxy tepercaya. Hasilnya selalu IP valid (maks 45 karakter) atau `Unknown IP`
     pub fn get_ip_address(req: &HttpRequest) -> String {
+        let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else {
+            return "Unknown IP".to_string();
+        };
+        if !TRUSTED_PROXIES.contains(&peer) {
+            return peer.to_string();
+        }
+
         req.headers()
-            .get("X-Forwarded-For") // Jika pakai reverse proxy seperti Nginx
-            .and_then(|ip| ip.to_str().ok())
-            .map_or_else(
-                || req.peer_addr()
-                    .map(|addr| addr.ip().to_string())
-                    .unwrap_or_else(|| "Unknown IP".to_string()),
-                |ip| ip.to_string(),
-            )
+            .get_all("X-Forwarded-For")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect::<Vec<&str>>()
+            .into_iter()
+            .rev()
+            .map(|hop| hop.trim().parse::<IpAddr>())
+            .take_while(|hop| hop.is_ok())
+            .filter_map(Result::ok)
+            .find(|hop| !TRUSTED_PROXIES.contains(hop))
+            .unwrap_or(peer)
+            .to_string()
     }
     
-    // Function untuk ambil User-Agent (Device Info)
+    /// 🧹 IP yang akan disimpan: hanya IP valid (selain itu `Unknown IP`), dipotong ke [`IP_ADDRESS_MAX_LENGTH`]
+    pub fn ip_address_column(ip_address: &str) -> String {
+        match ip_address.trim().parse::<IpAddr>() {
+            Ok(ip) => ip.to_string().chars().take(IP_ADDRESS_MAX_LENGTH).collect(),
+            Err(_) => "Unknown IP".to_string(),
+        }
+    }
+
+    // Function untuk ambil User-Agent (Device Info), dipotong supaya muat di kolom `DeviceInfo`
     pub fn get_device_info(req: &HttpRequest) -> String {
         req.headers()
             .get("User-Agent")
             .and_then(|ua| ua.to_str().ok())
             .unwrap_or("Unknown Device")
-            .to_string()
+            .chars()
+            .take(DEVICE_INFO_MAX_LENGTH)
+            .collect()
     }
 
 }
\ No newline at end of file
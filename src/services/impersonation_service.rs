@@ -0,0 +1,295 @@
+use std::env;
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use tokio_stream::StreamExt;
+
+use crate::contexts::{
+    connection::Transaction,
+    jwt_session::{Claims, Impersonator},
+    keyring::jwt_keyring,
+    logger::write_log,
+    model::{ActionResult, ImpersonateRequest, ImpersonationInfo, ImpersonationToken},
+    rbac::Permission,
+};
+use super::{
+    audit_service::{AuditService, AUDIT_IMPERSONATION_ENDED, AUDIT_IMPERSONATION_REQUEST, AUDIT_IMPERSONATION_STARTED},
+    auth_service::AuthService,
+    revocation_service::RevocationService,
+};
+
+pub struct ImpersonationService;
+
+impl ImpersonationService {
+
+    /// ⏳ Masa berlaku token impersonation (default 30 menit), tidak bisa di-refresh
+    fn token_ttl() -> Duration {
+        let minutes: i64 = env::var("IMPERSONATION_TTL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        Duration::minutes(minutes)
+    }
+
+    /// 🕵️ Terbitkan access token atas nama applicant untuk admin. Token membawa hak applicant + identitas admin
+    /// di claim `impersonator`, tidak membuat sesi / refresh token dan tidak mengubah cookie admin
+    pub async fn start(connection: web::Data<Pool<ConnectionManager>>, admin: Claims, auth_usernid: i32, request: ImpersonateRequest, ip_address: &str) -> ActionResult<ImpersonationToken, String> {
+        let mut result: ActionResult<ImpersonationToken, String> = ActionResult::default();
+        let reason = request.reason.unwrap_or_default().trim().to_string();
+        let read_only = !request.write;
+
+        if auth_usernid == admin.auth_usernid {
+            result.message = "Cannot impersonate yourself".to_string();
+            return result;
+        }
+
+        if !read_only && !admin.has_permission(Permission::KycWrite) {
+            result.message = "Write access requires permission 'kyc.write'".to_string();
+            return result;
+        }
+
+        let user = match connection.clone().get().await {
+            Ok(mut conn) => match AuthService::get_web_user(&mut conn, auth_usernid).await {
+                Ok(Some(user)) => user,
+                Ok(None) => {
+                    result.message = "No user found".to_string();
+                    return result;
+                }
+                Err(err) => {
+                    result.error = Some(err);
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        // Sesama staff tidak boleh di-impersonate, token-nya akan membawa hak staff tersebut
+        if user.permissions.iter().any(|permission| permission == Permission::AdminAccess.as_str()) {
+            result.message = "Cannot impersonate staff accounts".to_string();
+            return result;
+        }
+
+        let ttl = Self::token_ttl();
+        let now = Utc::now();
+        let expires_at = now + ttl;
+
+        let mut claims = Claims::new(user);
+        claims.exp = expires_at.timestamp() as usize;
+        claims.expired_token = expires_at.timestamp();
+        claims.expired_date = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let access_token = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let impersonation_id: i64 = match conn.query(
+                                r#"INSERT INTO [dbo].[AuthImpersonation] ([AdminNID],[AuthUserNID],[Jti],[Reason],[ReadOnly],[IpAddress],[StartedAt],[ExpiresAt])
+                                OUTPUT INSERTED.ImpersonationNID
+                                VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8)"#,
+                                &[&admin.auth_usernid, &auth_usernid, &claims.jti, &reason, &read_only, &ip_address, &now, &expires_at],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get("ImpersonationNID").unwrap_or(0),
+                                    _ => {
+                                        result.error = Some("Failed to insert AuthImpersonation".to_string());
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to insert AuthImpersonation: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            let detail = serde_json::json!({ "impersonation_id": impersonation_id, "read_only": read_only, "reason": reason });
+                            if let Err(err) = AuditService::record(conn, AUDIT_IMPERSONATION_STARTED, Some(admin.auth_usernid), Some(auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            claims.impersonator = Some(Impersonator {
+                                id: impersonation_id,
+                                auth_usernid: admin.auth_usernid,
+                                email: admin.email.clone(),
+                                read_only,
+                            });
+
+                            // Token ditandatangani sebelum commit: gagal tanda tangan → catatan impersonation ikut batal
+                            match jwt_keyring().sign(&claims) {
+                                Ok(token) => Some(token),
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to create token: {}", err));
+                                    None
+                                }
+                            }
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            None
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                let Some(access_token) = access_token else {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                };
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = format!("Impersonating user {}", auth_usernid);
+                result.data = Some(ImpersonationToken {
+                    impersonation_id: claims.impersonator.as_ref().map(|impersonator| impersonator.id).unwrap_or_default(),
+                    auth_usernid,
+                    access_token,
+                    token_type: "Bearer".to_string(),
+                    expires_in: ttl.num_seconds(),
+                    read_only,
+                });
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// ⏹️ Akhiri sesi impersonation sebelum kedaluwarsa, token-nya langsung dicabut
+    pub async fn end(connection: web::Data<Pool<ConnectionManager>>, ended_by: i32, impersonation_id: i64) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let now = Utc::now();
+
+        let (jti, auth_usernid, expires_at) = match connection.clone().get().await {
+            Ok(mut conn) => match conn.query(
+                r#"UPDATE [dbo].[AuthImpersonation] SET [EndedAt] = @P2, [EndedBy] = @P3
+                OUTPUT INSERTED.Jti, INSERTED.AuthUserNID, INSERTED.ExpiresAt
+                WHERE ImpersonationNID = @P1 AND EndedAt IS NULL AND ExpiresAt > @P2"#,
+                &[&impersonation_id, &now, &ended_by],
+            ).await {
+                Ok(rows) => match rows.into_row().await {
+                    Ok(Some(row)) => (
+                        row.get::<&str, _>("Jti").unwrap_or_default().to_string(),
+                        row.get::<i32, _>("AuthUserNID").unwrap_or(0),
+                        row.get::<NaiveDateTime, _>("ExpiresAt").map(|dt| dt.and_utc()).unwrap_or(now),
+                    ),
+                    _ => {
+                        result.message = "No active impersonation session".to_string();
+                        return result;
+                    }
+                },
+                Err(err) => {
+                    result.error = format!("Failed to update AuthImpersonation: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if let Err(err) = RevocationService::revoke_jti(connection.clone(), &jti, auth_usernid, expires_at, "impersonation_ended").await {
+            result.error = Some(err);
+            return result;
+        }
+
+        match connection.get().await {
+            Ok(mut conn) => {
+                let detail = serde_json::json!({ "impersonation_id": impersonation_id });
+                if let Err(err) = AuditService::record(&mut conn, AUDIT_IMPERSONATION_ENDED, Some(ended_by), Some(auth_usernid), &detail).await {
+                    write_log("ERROR", err.as_str());
+                }
+            }
+            Err(err) => write_log("ERROR", format!("Internal Server error: {:?}", err).as_str()),
+        }
+
+        result.result = true;
+        result.message = "Impersonation ended".to_string();
+        result
+    }
+
+    /// 📋 Sesi impersonation yang masih berjalan (semua admin)
+    pub async fn list_active(connection: web::Data<Pool<ConnectionManager>>) -> ActionResult<Vec<ImpersonationInfo>, String> {
+        let mut result: ActionResult<Vec<ImpersonationInfo>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            r#"SELECT ImpersonationNID, AdminNID, AuthUserNID, Reason, ReadOnly, StartedAt, ExpiresAt FROM AuthImpersonation
+            WHERE EndedAt IS NULL AND ExpiresAt > @P1 ORDER BY StartedAt DESC"#,
+            &[&Utc::now()],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut sessions: Vec<ImpersonationInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        sessions.push(ImpersonationInfo {
+                            impersonation_id: row.get("ImpersonationNID").unwrap_or(0),
+                            admin_usernid: row.get("AdminNID").unwrap_or(0),
+                            auth_usernid: row.get("AuthUserNID").unwrap_or(0),
+                            reason: row.get::<&str, _>("Reason").unwrap_or_default().to_string(),
+                            read_only: row.get("ReadOnly").unwrap_or(true),
+                            started_at: row.get::<NaiveDateTime, _>("StartedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                            expires_at: row.get::<NaiveDateTime, _>("ExpiresAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                        });
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} active impersonation session(s)", sessions.len());
+        result.data = Some(sessions);
+        result
+    }
+
+    /// 📝 Satu baris `AuditLog` per request yang dibuat dengan token impersonation (termasuk yang ditolak)
+    pub async fn log_request(connection: web::Data<Pool<ConnectionManager>>, impersonator: &Impersonator, subject: i32, method: &str, path: &str, status: u16) {
+        let detail = serde_json::json!({
+            "impersonation_id": impersonator.id,
+            "method": method,
+            "path": path,
+            "status": status,
+        });
+
+        let logged = match connection.get().await {
+            Ok(mut conn) => AuditService::record(&mut conn, AUDIT_IMPERSONATION_REQUEST, Some(impersonator.auth_usernid), Some(subject), &detail).await,
+            Err(err) => Err(format!("Internal Server error: {:?}", err)),
+        };
+
+        // Jangan sampai jejak hilang diam-diam: fallback ke file log
+        if let Err(err) = logged {
+            write_log("ERROR", format!("{} {}: {}", AUDIT_IMPERSONATION_REQUEST, detail, err).as_str());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+
+use crate::contexts::{
+    model::{ActionResult, KycDraftInfo},
+    onboarding::KycStage,
+};
+
+/// Form onboarding yang bisa disimpan sebagai draft, nama sama dengan `KycStep::as_str`
+pub const DRAFT_STEPS: [&str; 5] = ["personal_data", "beneficiary_owner", "bank_account", "occupation", "supporting_data"];
+
+pub struct KycDraftService;
+
+impl KycDraftService {
+
+    fn valid_step(step: &str) -> bool {
+        DRAFT_STEPS.contains(&step)
+    }
+
+    /// 💾 Simpan (timpa) draft satu form. Isi tidak divalidasi dan stage tidak berubah,
+    /// tapi draft ikut terkunci setelah onboarding `completed`
+    pub async fn save(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, step: &str, data: Value) -> ActionResult<KycDraftInfo, String> {
+        let mut result: ActionResult<KycDraftInfo, String> = ActionResult::default();
+
+        if !Self::valid_step(step) {
+            result.message = format!("Unknown step '{}'", step);
+            return result;
+        }
+        if !data.is_object() {
+            result.message = "Draft must be a JSON object".to_string();
+            return result;
+        }
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let stage: i32 = match conn.query(
+            r#"SELECT K.Stage FROM UserKyc K
+            JOIN AuthUser U ON U.WebCIFNID = K.AutoNID
+            WHERE U.AuthUserNID = @P1"#,
+            &[&auth_usernid],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row.get("Stage").unwrap_or(0),
+                _ => {
+                    result.message = "No user found".to_string();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if KycStage::from_i32(stage) == Some(KycStage::Completed) {
+            result.message = format!("Cannot save draft '{}': onboarding is already {}", step, KycStage::Completed);
+            return result;
+        }
+
+        let now = Utc::now();
+        if let Err(err) = conn.execute(
+            r#"MERGE [dbo].[KycDraft] AS T
+            USING (SELECT @P1 AS AuthUserNID, @P2 AS Step) AS S ON T.AuthUserNID = S.AuthUserNID AND T.Step = S.Step
+            WHEN MATCHED THEN UPDATE SET [Data] = @P3, [UpdatedAt] = @P4
+            WHEN NOT MATCHED THEN INSERT ([AuthUserNID],[Step],[Data],[UpdatedAt]) VALUES (@P1,@P2,@P3,@P4);"#,
+            &[&auth_usernid, &step, &data.to_string(), &now],
+        ).await {
+            result.error = format!("Failed to save KycDraft: {:?}", err).into();
+            return result;
+        }
+
+        result.result = true;
+        result.message = "Draft saved".to_string();
+        result.data = Some(KycDraftInfo { step: step.to_string(), data, updated_at: now });
+        result
+    }
+
+    /// 📖 Ambil draft satu form
+    pub async fn get(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, step: &str) -> ActionResult<KycDraftInfo, String> {
+        let mut result: ActionResult<KycDraftInfo, String> = ActionResult::default();
+
+        if !Self::valid_step(step) {
+            result.message = format!("Unknown step '{}'", step);
+            return result;
+        }
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        match conn.query(
+            "SELECT Data, UpdatedAt FROM KycDraft WHERE AuthUserNID = @P1 AND Step = @P2",
+            &[&auth_usernid, &step],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => {
+                    let data = row.get::<&str, _>("Data").and_then(|data| serde_json::from_str(data).ok()).unwrap_or(Value::Null);
+                    result.result = true;
+                    result.message = "Retrieve successfully".to_string();
+                    result.data = Some(KycDraftInfo {
+                        step: step.to_string(),
+                        data,
+                        updated_at: row.get::<NaiveDateTime, _>("UpdatedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                    });
+                }
+                Ok(None) => result.message = "No draft found".to_string(),
+                Err(err) => result.error = format!("Query execution failed: {:?}", err).into(),
+            },
+            Err(err) => result.error = format!("Query execution failed: {:?}", err).into(),
+        }
+
+        result
+    }
+
+    /// 🗑️ Hapus draft, dipanggil juga setelah form berhasil di-submit
+    pub async fn discard(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, step: &str) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        if !Self::valid_step(step) {
+            result.message = format!("Unknown step '{}'", step);
+            return result;
+        }
+
+        match connection.get().await {
+            Ok(mut conn) => match conn.execute(
+                "DELETE FROM [dbo].[KycDraft] WHERE AuthUserNID = @P1 AND Step = @P2",
+                &[&auth_usernid, &step],
+            ).await {
+                Ok(_) => {
+                    result.result = true;
+                    result.message = "Draft discarded".to_string();
+                }
+                Err(err) => result.error = format!("Failed to delete KycDraft: {:?}", err).into(),
+            },
+            Err(err) => result.error = format!("Internal Server error: {:?}", err).into(),
+        }
+
+        result
+    }
+}
@@ -0,0 +1,286 @@
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use tokio_stream::StreamExt;
+
+use crate::contexts::{
+    connection::Transaction,
+    logger::write_log,
+    model::{ActionResult, KycPendingReview, KycReviewInfo, KycReviewRequest},
+    onboarding::{KycStage, ReviewDecision},
+};
+use super::mail_service::{MailService, TEMPLATE_KYC_REVIEW};
+
+pub struct KycReviewService;
+
+impl KycReviewService {
+
+    /// ⚖️ Simpan keputusan reviewer. Hanya aplikasi yang sudah `completed` dan belum diputuskan yang bisa direview,
+    /// selama itu semua form onboarding terkunci. Keputusan, riwayat & email ke applicant ada di satu transaksi
+    pub async fn decide(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, reviewed_by: i32, request: KycReviewRequest) -> ActionResult<KycReviewInfo, String> {
+        let mut result: ActionResult<KycReviewInfo, String> = ActionResult::default();
+        let reason = request.reason.unwrap_or_default().trim().to_string();
+
+        let decision = match ReviewDecision::parse(request.decision.as_deref().unwrap_or_default().trim(), &request.steps) {
+            Ok(decision) => decision,
+            Err(err) => {
+                result.message = err;
+                return result;
+            }
+        };
+        let reopened_stage = match decision {
+            ReviewDecision::Revise { reopen } => Some(reopen),
+            _ => None,
+        };
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let review_nid: Option<i64> = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            // 🔒 Kunci baris UserKyc supaya dua reviewer tidak memutuskan aplikasi yang sama
+                            let (auto_nid, stage, is_finished, is_approved, is_rejected, email, full_name) = match conn.query(
+                                r#"SELECT K.AutoNID, K.Stage, K.IsFinished, K.IsApproved, K.IsRejected, U.Email, K.Fullname
+                                FROM UserKyc K WITH (UPDLOCK, ROWLOCK)
+                                JOIN AuthUser U ON U.WebCIFNID = K.AutoNID
+                                WHERE U.AuthUserNID = @P1"#,
+                                &[&auth_usernid],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => (
+                                        row.get::<i32, _>("AutoNID").unwrap_or(0),
+                                        row.get::<i32, _>("Stage").unwrap_or(0),
+                                        row.get::<bool, _>("IsFinished").unwrap_or(false),
+                                        row.get::<bool, _>("IsApproved").unwrap_or(false),
+                                        row.get::<bool, _>("IsRejected").unwrap_or(false),
+                                        row.get::<&str, _>("Email").unwrap_or_default().to_string(),
+                                        row.get::<&str, _>("Fullname").unwrap_or_default().to_string(),
+                                    ),
+                                    _ => {
+                                        result.message = "No user found".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Query execution failed: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            if is_approved || is_rejected {
+                                result.message = "Application has already been decided".to_string();
+                                break 'tx None;
+                            }
+                            if !is_finished || KycStage::from_i32(stage) != Some(KycStage::Completed) {
+                                let current = KycStage::from_i32(stage).map_or_else(|| stage.to_string(), |stage| stage.to_string());
+                                result.message = format!("Application is not pending review: current stage is {}, expected stage {}", current, KycStage::Completed);
+                                break 'tx None;
+                            }
+
+                            let updated = match decision {
+                                ReviewDecision::Approve => conn.execute(
+                                    r#"UPDATE [dbo].[UserKyc] SET [IsApproved] = 1, [LastUpdate] = @P2 WHERE AutoNID = @P1"#,
+                                    &[&auto_nid, &now],
+                                ).await,
+                                ReviewDecision::Reject => conn.execute(
+                                    r#"UPDATE [dbo].[UserKyc] SET [IsRejected] = 1, [LastUpdate] = @P2 WHERE AutoNID = @P1"#,
+                                    &[&auto_nid, &now],
+                                ).await,
+                                // Buka lagi form mulai dari stage paling awal yang harus diperbaiki
+                                ReviewDecision::Revise { reopen } => conn.execute(
+                                    r#"UPDATE [dbo].[UserKyc] SET [Stage] = @P3, [IsFinished] = 0, [IsRevised] = 1, [LastUpdate] = @P2 WHERE AutoNID = @P1"#,
+                                    &[&auto_nid, &now, &reopen.as_i32()],
+                                ).await,
+                            };
+                            if let Err(err) = updated {
+                                result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            let review_nid: i64 = match conn.query(
+                                r#"INSERT INTO [dbo].[KycReviewHistory] ([AuthUserNID],[Decision],[Reason],[ReopenedStage],[ReviewedBy],[CreatedAt])
+                                OUTPUT INSERTED.ReviewNID
+                                VALUES (@P1,@P2,@P3,@P4,@P5,@P6)"#,
+                                &[&auth_usernid, &decision.as_str(), &reason, &reopened_stage.map(KycStage::as_i32), &reviewed_by, &now],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get("ReviewNID").unwrap_or(0),
+                                    _ => {
+                                        result.error = Some("Failed to insert KycReviewHistory".to_string());
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to insert KycReviewHistory: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            // ✉️ Kabari applicant, ikut transaksi supaya email hanya terkirim jika keputusan tersimpan
+                            let data = serde_json::json!({
+                                "full_name": full_name,
+                                "approved": matches!(decision, ReviewDecision::Approve),
+                                "rejected": matches!(decision, ReviewDecision::Reject),
+                                "revision": matches!(decision, ReviewDecision::Revise { .. }),
+                                "reopened_stage": reopened_stage.map(KycStage::as_str),
+                                "reason": reason,
+                            });
+                            if let Err(err) = MailService::enqueue(conn, &email, "Hasil review pembukaan rekening", TEMPLATE_KYC_REVIEW, &data).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            Some(review_nid)
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            None
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                let Some(review_nid) = review_nid else {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                };
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = format!("Application {}", decision.as_str());
+                result.data = Some(KycReviewInfo {
+                    review_nid,
+                    auth_usernid,
+                    decision: decision.as_str().to_string(),
+                    reason,
+                    reopened_stage,
+                    reviewed_by,
+                    created_at: now,
+                });
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// 📜 Riwayat keputusan review satu applicant, terbaru di atas
+    pub async fn history(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<Vec<KycReviewInfo>, String> {
+        let mut result: ActionResult<Vec<KycReviewInfo>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            r#"SELECT ReviewNID, AuthUserNID, Decision, Reason, ReopenedStage, ReviewedBy, CreatedAt FROM KycReviewHistory
+            WHERE AuthUserNID = @P1 ORDER BY CreatedAt DESC"#,
+            &[&auth_usernid],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut reviews: Vec<KycReviewInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        reviews.push(KycReviewInfo {
+                            review_nid: row.get("ReviewNID").unwrap_or(0),
+                            auth_usernid: row.get("AuthUserNID").unwrap_or(0),
+                            decision: row.get::<&str, _>("Decision").unwrap_or_default().to_string(),
+                            reason: row.get::<&str, _>("Reason").unwrap_or_default().to_string(),
+                            reopened_stage: row.get::<i32, _>("ReopenedStage").and_then(KycStage::from_i32),
+                            reviewed_by: row.get("ReviewedBy").unwrap_or(0),
+                            created_at: row.get::<NaiveDateTime, _>("CreatedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                        });
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} review(s)", reviews.len());
+        result.data = Some(reviews);
+        result
+    }
+
+    /// 📥 Antrian review: aplikasi `completed` yang belum disetujui / ditolak, paling lama di atas
+    pub async fn pending(connection: web::Data<Pool<ConnectionManager>>) -> ActionResult<Vec<KycPendingReview>, String> {
+        let mut result: ActionResult<Vec<KycPendingReview>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            r#"SELECT U.AuthUserNID, U.Email, K.Fullname, K.IsRevised, K.LastUpdate
+            FROM UserKyc K
+            JOIN AuthUser U ON U.WebCIFNID = K.AutoNID
+            WHERE K.Stage = @P1 AND K.IsFinished = 1 AND K.IsApproved = 0 AND K.IsRejected = 0
+            ORDER BY K.LastUpdate"#,
+            &[&KycStage::Completed.as_i32()],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut applications: Vec<KycPendingReview> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        applications.push(KycPendingReview {
+                            auth_usernid: row.get("AuthUserNID").unwrap_or(0),
+                            email: row.get::<&str, _>("Email").unwrap_or_default().to_string(),
+                            full_name: row.get::<&str, _>("Fullname").unwrap_or_default().to_string(),
+                            is_revised: row.get("IsRevised").unwrap_or(false),
+                            last_update: row.get::<NaiveDateTime, _>("LastUpdate").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                        });
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} application(s) pending review", applications.len());
+        result.data = Some(applications);
+        result
+    }
+}
@@ -0,0 +1,240 @@
+use std::env;
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+use crate::contexts::{logger::write_log, model::ActionResult, password::verify_password};
+
+pub struct LoginGuardService;
+
+/// ⚙️ Konfigurasi brute-force protection, bisa diatur lewat `.env`
+struct GuardConfig {
+    /// Gagal berturut-turut per email sebelum lockout
+    max_attempts: i32,
+    /// Gagal per IP (semua email) dalam window sebelum IP di-lockout
+    max_attempts_per_ip: i32,
+    lockout: Duration,
+    window: Duration,
+    /// Jeda minimum setelah gagal ke-n: base * 2^(n-1), maksimal `backoff_max`
+    backoff_base_seconds: i64,
+    backoff_max_seconds: i64,
+}
+
+impl GuardConfig {
+    fn load() -> Self {
+        let read = |key: &str, default: i64| -> i64 { env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default) };
+
+        Self {
+            max_attempts: read("LOGIN_MAX_ATTEMPTS", 5) as i32,
+            max_attempts_per_ip: read("LOGIN_MAX_ATTEMPTS_PER_IP", 20) as i32,
+            lockout: Duration::minutes(read("LOGIN_LOCKOUT_MINUTES", 15)),
+            window: Duration::minutes(read("LOGIN_ATTEMPT_WINDOW_MINUTES", 15)),
+            backoff_base_seconds: read("LOGIN_BACKOFF_BASE_SECONDS", 1),
+            backoff_max_seconds: read("LOGIN_BACKOFF_MAX_SECONDS", 60),
+        }
+    }
+
+    fn backoff(&self, failures: i32) -> Duration {
+        if failures <= 0 {
+            return Duration::zero();
+        }
+        let exponent = (failures - 1).min(30) as u32;
+        let seconds = self.backoff_base_seconds.saturating_mul(2i64.saturating_pow(exponent));
+        Duration::seconds(seconds.min(self.backoff_max_seconds))
+    }
+}
+
+impl LoginGuardService {
+
+    /// 🚦 Cek sebelum password diverifikasi. `Some(detik)` berarti login harus ditolak dan dicoba lagi setelah sekian detik
+    pub async fn check(connection: web::Data<Pool<ConnectionManager>>, email: &str, ip_address: &str) -> Result<Option<i64>, String> {
+        let config = GuardConfig::load();
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+
+        // 🔴 Lockout aktif untuk email atau IP ini
+        let row = conn.query(
+            r#"SELECT MAX(LockedUntil) AS LockedUntil FROM AuthLockout
+            WHERE UnlockedAt IS NULL AND LockedUntil > @P1 AND (Email = @P2 OR (Email IS NULL AND IpAddress = @P3))"#,
+            &[&now, &email, &ip_address],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        if let Some(locked_until) = row.and_then(|row| row.get::<NaiveDateTime, _>("LockedUntil")) {
+            return Ok(Some(Self::seconds_until(locked_until.and_utc(), now)));
+        }
+
+        // ⏳ Exponential backoff dari gagal terakhir
+        let (failures, last_failure) = Self::recent_failures(&mut conn, email, now - config.window).await?;
+        if let Some(last_failure) = last_failure {
+            let retry_at = last_failure + config.backoff(failures);
+            if retry_at > now {
+                return Ok(Some(Self::seconds_until(retry_at, now)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// ❌ Catat login gagal, lockout email / IP jika melewati ambang batas
+    pub async fn record_failure(connection: web::Data<Pool<ConnectionManager>>, email: &str, ip_address: &str, device_info: &str) -> Result<(), String> {
+        let config = GuardConfig::load();
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthLoginAttempt] ([Email],[IpAddress],[Success],[AttemptedAt]) VALUES (@P1,@P2,0,@P3)"#,
+            &[&email, &ip_address, &now],
+        ).await.map_err(|e| format!("Failed to insert AuthLoginAttempt: {:?}", e))?;
+
+        let (failures, _) = Self::recent_failures(&mut conn, email, now - config.window).await?;
+        if failures >= config.max_attempts {
+            conn.execute(
+                r#"INSERT INTO [dbo].[AuthLockout] ([Email],[IpAddress],[DeviceInfo],[Reason],[LockedAt],[LockedUntil])
+                VALUES (@P1,@P2,@P3,'email_attempts',@P4,@P5)"#,
+                &[&email, &ip_address, &device_info, &now, &(now + config.lockout)],
+            ).await.map_err(|e| format!("Failed to insert AuthLockout: {:?}", e))?;
+
+            write_log("WARN", format!("Login locked for {} after {} failed attempts (ip: {}, device: {})", email, failures, ip_address, device_info).as_str());
+        }
+
+        // IP yang mencoba banyak email berbeda
+        let row = conn.query(
+            r#"SELECT COUNT(*) AS Failures FROM AuthLoginAttempt
+            WHERE IpAddress = @P1 AND Success = 0 AND AttemptedAt > @P2
+            AND AttemptedAt > ISNULL((SELECT MAX(LockedAt) FROM AuthLockout WHERE Email IS NULL AND IpAddress = @P1), '19000101')"#,
+            &[&ip_address, &(now - config.window)],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        let ip_failures: i32 = row.and_then(|row| row.get("Failures")).unwrap_or(0);
+        if ip_failures >= config.max_attempts_per_ip {
+            conn.execute(
+                r#"INSERT INTO [dbo].[AuthLockout] ([Email],[IpAddress],[DeviceInfo],[Reason],[LockedAt],[LockedUntil])
+                VALUES (NULL,@P1,@P2,'ip_attempts',@P3,@P4)"#,
+                &[&ip_address, &device_info, &now, &(now + config.lockout)],
+            ).await.map_err(|e| format!("Failed to insert AuthLockout: {:?}", e))?;
+
+            write_log("WARN", format!("Login locked for ip {} after {} failed attempts (device: {})", ip_address, ip_failures, device_info).as_str());
+        }
+
+        Ok(())
+    }
+
+    /// 🔑 Verifikasi ulang password untuk aksi sensitif (ganti email, tutup akun).
+    /// Salah password dihitung sama seperti login gagal supaya tidak bisa dipakai menebak password di luar `/login`
+    /// `Err` jika percobaan gagal tidak bisa dicatat: tolak, jangan sampai tebakan lolos tanpa terhitung
+    pub async fn reauthenticate(connection: web::Data<Pool<ConnectionManager>>, email: &str, password: &str, stored: &str, ip_address: &str, device_info: &str) -> Result<bool, String> {
+        if verify_password(password, stored).await.valid {
+            return Ok(true);
+        }
+        Self::record_failure(connection, email, ip_address, device_info).await?;
+        Ok(false)
+    }
+
+    /// ✅ Login berhasil memutus hitungan gagal berturut-turut
+    pub async fn record_success(connection: web::Data<Pool<ConnectionManager>>, email: &str, ip_address: &str) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthLoginAttempt] ([Email],[IpAddress],[Success],[AttemptedAt]) VALUES (@P1,@P2,1,@P3)"#,
+            &[&email, &ip_address, &Utc::now()],
+        ).await.map_err(|e| format!("Failed to insert AuthLoginAttempt: {:?}", e))?;
+        Ok(())
+    }
+
+    /// 🔓 Buka lockout user (admin). Hitungan gagal ikut di-reset
+    pub async fn unlock_user(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, unlocked_by: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match connection.clone().get().await {
+            Ok(mut conn) => {
+                let updated = match conn.execute(
+                    r#"UPDATE L SET [UnlockedAt] = @P1, [UnlockedBy] = @P2
+                    FROM [dbo].[AuthLockout] L JOIN [dbo].[AuthUser] U ON U.Email = L.Email
+                    WHERE U.AuthUserNID = @P3 AND L.UnlockedAt IS NULL AND L.LockedUntil > @P1"#,
+                    &[&Utc::now(), &unlocked_by, &auth_usernid],
+                ).await {
+                    Ok(done) => done.total(),
+                    Err(err) => {
+                        result.error = format!("Failed to update AuthLockout: {:?}", err).into();
+                        return result;
+                    }
+                };
+
+                if updated == 0 {
+                    result.message = "No active lockout for this user".to_string();
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "User unlocked".to_string();
+                result
+            }
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                result
+            }
+        }
+    }
+
+    /// 🔓 Buka lockout per IP (admin), yaitu lockout `ip_attempts` yang tidak terikat email
+    pub async fn unlock_ip(connection: web::Data<Pool<ConnectionManager>>, ip_address: &str, unlocked_by: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match connection.clone().get().await {
+            Ok(mut conn) => {
+                let updated = match conn.execute(
+                    r#"UPDATE [dbo].[AuthLockout] SET [UnlockedAt] = @P1, [UnlockedBy] = @P2
+                    WHERE Email IS NULL AND IpAddress = @P3 AND UnlockedAt IS NULL AND LockedUntil > @P1"#,
+                    &[&Utc::now(), &unlocked_by, &ip_address],
+                ).await {
+                    Ok(done) => done.total(),
+                    Err(err) => {
+                        result.error = format!("Failed to update AuthLockout: {:?}", err).into();
+                        return result;
+                    }
+                };
+
+                if updated == 0 {
+                    result.message = "No active lockout for this IP address".to_string();
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "IP address unlocked".to_string();
+                result
+            }
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                result
+            }
+        }
+    }
+
+    /// Jumlah gagal berturut-turut sejak login sukses / unlock terakhir di dalam window
+    async fn recent_failures(conn: &mut PooledConnection<'_, ConnectionManager>, email: &str, since: DateTime<Utc>) -> Result<(i32, Option<DateTime<Utc>>), String> {
+        let row = conn.query(
+            r#"SELECT COUNT(*) AS Failures, MAX(AttemptedAt) AS LastFailure FROM AuthLoginAttempt
+            WHERE Email = @P1 AND Success = 0 AND AttemptedAt > @P2
+            AND AttemptedAt > ISNULL((SELECT MAX(AttemptedAt) FROM AuthLoginAttempt WHERE Email = @P1 AND Success = 1), '19000101')
+            AND AttemptedAt > ISNULL((SELECT MAX(UnlockedAt) FROM AuthLockout WHERE Email = @P1), '19000101')
+            AND AttemptedAt > ISNULL((SELECT MAX(LockedAt) FROM AuthLockout WHERE Email = @P1), '19000101')"#,
+            &[&email, &since],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        match row {
+            Some(row) => Ok((
+                row.get("Failures").unwrap_or(0),
+                row.get::<NaiveDateTime, _>("LastFailure").map(|dt| dt.and_utc()),
+            )),
+            None => Ok((0, None)),
+        }
+    }
+
+    fn seconds_until(until: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+        (until - now).num_seconds().max(1)
+    }
+}
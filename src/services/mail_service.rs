@@ -0,0 +1,156 @@
+use std::{env, fs};
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{Duration, Utc};
+use handlebars::Handlebars;
+use serde_json::Value;
+
+use crate::contexts::{
+    crypto::{open_text, seal_text},
+    logger::write_log,
+    mailer::{transport_from_env, MailMessage, MailTransport},
+};
+
+/// Template email ada di `reports/mail_<nama>.mustache`
+pub const TEMPLATE_ACTIVATION: &str = "activation";
+pub const TEMPLATE_RESET_PASSWORD: &str = "reset_password";
+pub const TEMPLATE_CHANGE_EMAIL: &str = "change_email";
+pub const TEMPLATE_EMAIL_CHANGED: &str = "email_changed";
+pub const TEMPLATE_KYC_REVIEW: &str = "kyc_review";
+
+/// Lama "lease" saat email sedang diproses, setelah itu dianggap gagal dan diambil ulang
+const SENDING_LEASE_MINUTES: i64 = 5;
+
+pub struct MailService;
+
+impl MailService {
+
+    /// ✉️ Render template lalu simpan ke outbox. Dipanggil di dalam transaksi yang sama dengan data pemicunya,
+    /// jadi email hanya terkirim jika transaksinya commit.
+    /// Isi email bisa berisi link dengan token mentah, jadi disimpan terenkripsi (`DATA_KEYS`, wajib dikonfigurasi
+    /// supaya email yang masih antre tetap terbaca setelah restart) dan dihapus setelah diproses
+    pub async fn enqueue(conn: &mut PooledConnection<'_, ConnectionManager>, to: &str, subject: &str, template: &str, data: &Value) -> Result<(), String> {
+        let body = seal_text(&Self::render(template, data)?)?;
+
+        conn.execute(
+            r#"INSERT INTO [dbo].[MailOutbox] ([Recipient],[Subject],[Body],[BodySealed],[Template],[Status],[Attempts],[NextAttemptAt],[CreatedAt])
+            VALUES (@P1,@P2,@P3,1,@P4,'pending',0,@P5,@P5)"#,
+            &[&to, &subject, &body, &template, &Utc::now()],
+        ).await.map_err(|e| format!("Failed to insert MailOutbox: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// 🔗 Link aktivasi, base URL dari `ACTIVATION_LINK_URL`
+    pub fn activation_link(token: &str) -> String {
+        let base = env::var("ACTIVATION_LINK_URL").unwrap_or_else(|_| "http://127.0.0.1:8000/v1/auth/activation".to_string());
+        format!("{}/{}", base.trim_end_matches('/'), token)
+    }
+
+    /// 🔗 Link halaman reset password di frontend, base URL dari `RESET_PASSWORD_LINK_URL`
+    pub fn reset_password_link(email: &str, key: &str) -> String {
+        let base = env::var("RESET_PASSWORD_LINK_URL").unwrap_or_else(|_| "http://127.0.0.1:3000/reset-password".to_string());
+        format!("{}?email={}&key={}", base, email.replace('+', "%2B").replace('@', "%40"), key)
+    }
+
+    /// 🔗 Link konfirmasi email baru, base URL dari `CHANGE_EMAIL_LINK_URL`
+    pub fn change_email_link(token: &str) -> String {
+        let base = env::var("CHANGE_EMAIL_LINK_URL").unwrap_or_else(|_| "http://127.0.0.1:8000/v1/auth/change-email".to_string());
+        format!("{}/{}", base.trim_end_matches('/'), token)
+    }
+
+    fn render(template: &str, data: &Value) -> Result<String, String> {
+        let template_path = format!("./reports/mail_{}.mustache", template);
+        let template_content = fs::read_to_string(&template_path).map_err(|e| format!("Failed to read template {}: {}", template_path, e))?;
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_template_string(template, template_content).map_err(|e| format!("Invalid template {}: {}", template, e))?;
+        handlebars.render(template, data).map_err(|e| format!("Failed to render template {}: {}", template, e))
+    }
+
+    /// 📤 Kirim email yang sudah jatuh tempo. Gagal → dijadwalkan ulang dengan backoff sampai `MAIL_MAX_ATTEMPTS`
+    pub async fn process_outbox(connection: &Pool<ConnectionManager>, transport: &dyn MailTransport) -> Result<(), String> {
+        let batch_size: i32 = env::var("MAIL_OUTBOX_BATCH").ok().and_then(|v| v.parse().ok()).unwrap_or(20);
+        let max_attempts: i32 = env::var("MAIL_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).unwrap_or(8);
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+
+        // Klaim batch secara atomik (aman jika ada beberapa instance)
+        let rows = conn.query(
+            r#"UPDATE TOP (@P1) [dbo].[MailOutbox] SET [NextAttemptAt] = @P2
+            OUTPUT INSERTED.MailNID, INSERTED.Recipient, INSERTED.Subject, INSERTED.Body, INSERTED.BodySealed, INSERTED.Attempts
+            WHERE Status = 'pending' AND NextAttemptAt <= @P3"#,
+            &[&batch_size, &(now + Duration::minutes(SENDING_LEASE_MINUTES)), &now],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_first_result().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        for row in rows {
+            let mail_nid: i64 = row.get("MailNID").unwrap_or(0);
+            let attempts: i32 = row.get::<i32, _>("Attempts").unwrap_or(0) + 1;
+            let body = row.get::<&str, _>("Body").unwrap_or_default();
+            // Baris sebelum migrasi 022 masih teks biasa
+            let html = match row.get::<bool, _>("BodySealed").unwrap_or(false) {
+                true => open_text(body),
+                false => Ok(body.to_string()),
+            };
+            let to = row.get::<&str, _>("Recipient").unwrap_or_default().to_string();
+            let subject = row.get::<&str, _>("Subject").unwrap_or_default().to_string();
+
+            // Isi yang tidak bisa dibuka (kunci `DATA_KEYS` hilang / diganti) tidak akan terbuka di percobaan berikutnya
+            let (sent, retryable) = match html {
+                Ok(html) => (transport.send(&MailMessage { to: to.clone(), subject, html }).await, true),
+                Err(err) => (Err(format!("Failed to open mail body: {}", err)), false),
+            };
+
+            match sent {
+                Ok(()) => {
+                    conn.execute(
+                        r#"UPDATE [dbo].[MailOutbox] SET [Status] = 'sent', [Attempts] = @P1, [SentAt] = @P2, [LastError] = NULL, [Body] = NULL WHERE MailNID = @P3"#,
+                        &[&attempts, &Utc::now(), &mail_nid],
+                    ).await.map_err(|e| format!("Failed to update MailOutbox: {:?}", e))?;
+                }
+                Err(err) => {
+                    write_log("ERROR", format!("Failed to send mail {} to {}: {}", mail_nid, to, err).as_str());
+
+                    let status = if !retryable || attempts >= max_attempts { "failed" } else { "pending" };
+                    // Backoff 1, 2, 4, ... menit, maksimal 1 jam
+                    let retry_at = Utc::now() + Duration::minutes(2i64.pow((attempts - 1).clamp(0, 6) as u32));
+                    let last_error: String = err.chars().take(1000).collect();
+
+                    // Status akhir `failed` tidak akan dikirim lagi, isi (dan link di dalamnya) ikut dihapus
+                    conn.execute(
+                        r#"UPDATE [dbo].[MailOutbox] SET [Status] = @P1, [Attempts] = @P2, [NextAttemptAt] = @P3, [LastError] = @P4,
+                            [Body] = CASE WHEN @P1 = 'failed' THEN NULL ELSE Body END
+                        WHERE MailNID = @P5"#,
+                        &[&status, &attempts, &retry_at, &last_error, &mail_nid],
+                    ).await.map_err(|e| format!("Failed to update MailOutbox: {:?}", e))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// ⏱️ Jalankan worker outbox di background
+    pub fn start(connection: Pool<ConnectionManager>) {
+        let transport = match transport_from_env() {
+            Ok(transport) => transport,
+            Err(err) => {
+                write_log("ERROR", format!("Mail worker not started: {}", err).as_str());
+                return;
+            }
+        };
+        let interval_secs: u64 = env::var("MAIL_OUTBOX_POLL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(10);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(err) = Self::process_outbox(&connection, transport.as_ref()).await {
+                    write_log("ERROR", err.as_str());
+                }
+            }
+        });
+    }
+}
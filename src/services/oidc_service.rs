@@ -0,0 +1,315 @@
+use std::env;
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{Duration, Utc};
+
+use crate::contexts::{
+    connection::Transaction,
+    logger::write_log,
+    model::{ActionResult, OidcRedirect, WebUser},
+    oidc::{provider, OidcIdentity},
+    password::hash_password,
+};
+use super::{auth_service::AuthService, generic_service::GenericService, session_service::SessionService};
+
+pub struct OidcService;
+
+impl OidcService {
+
+    /// ⏳ Batas waktu dari redirect ke provider sampai callback (default 10 menit)
+    fn state_ttl() -> Duration {
+        let minutes: i64 = env::var("OIDC_STATE_TTL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(10);
+        Duration::minutes(minutes)
+    }
+
+    /// 🚀 Buat URL login provider. State, nonce & code_verifier disimpan di database untuk dicocokkan saat callback
+    pub async fn start(connection: web::Data<Pool<ConnectionManager>>, provider_name: &str, ip_address: &str) -> ActionResult<OidcRedirect, String> {
+        let mut result: ActionResult<OidcRedirect, String> = ActionResult::default();
+
+        let provider = match provider(provider_name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                result.message = err;
+                return result;
+            }
+        };
+
+        let request = match provider.authorization_request().await {
+            Ok(request) => request,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        let now = Utc::now();
+        match connection.get().await {
+            Ok(mut conn) => {
+                // State yang tidak pernah kembali dari provider dibersihkan di sini
+                if let Err(err) = conn.execute(r#"DELETE FROM [dbo].[AuthOidcState] WHERE ExpiresAt < @P1"#, &[&now]).await {
+                    write_log("ERROR", format!("Failed to delete AuthOidcState: {:?}", err).as_str());
+                }
+
+                if let Err(err) = conn.execute(
+                    r#"INSERT INTO [dbo].[AuthOidcState] ([State],[Provider],[Nonce],[CodeVerifier],[IpAddress],[CreatedAt],[ExpiresAt])
+                    VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7)"#,
+                    &[&request.state, &provider.name, &request.nonce, &request.code_verifier, &ip_address, &now, &(now + Self::state_ttl())],
+                ).await {
+                    result.error = format!("Failed to insert AuthOidcState: {:?}", err).into();
+                    return result;
+                }
+            }
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        }
+
+        result.result = true;
+        result.message = format!("Redirect to {}", provider.name);
+        result.data = Some(OidcRedirect { authorization_url: request.url, state: request.state });
+        result
+    }
+
+    /// 🔁 Callback provider: tukar code, verifikasi ID token, lalu cari / tautkan / buat akun
+    pub async fn finish(connection: web::Data<Pool<ConnectionManager>>, provider_name: &str, code: &str, state: &str, ip_address: &str) -> ActionResult<WebUser, String> {
+        let mut result: ActionResult<WebUser, String> = ActionResult::default();
+
+        let provider = match provider(provider_name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                result.message = err;
+                return result;
+            }
+        };
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        // 🔴 State hanya bisa dipakai sekali
+        let (nonce, code_verifier) = match conn.query(
+            r#"UPDATE [dbo].[AuthOidcState] SET [UsedAt] = @P3 OUTPUT INSERTED.Nonce, INSERTED.CodeVerifier
+            WHERE State = @P1 AND Provider = @P2 AND UsedAt IS NULL AND ExpiresAt > @P3"#,
+            &[&state, &provider.name, &Utc::now()],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => (
+                    row.get::<&str, _>("Nonce").unwrap_or_default().to_string(),
+                    row.get::<&str, _>("CodeVerifier").unwrap_or_default().to_string(),
+                ),
+                Ok(None) => {
+                    result.message = "Invalid or expired login state".to_string();
+                    return result;
+                }
+                Err(err) => {
+                    result.error = format!("Failed to update AuthOidcState: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Failed to update AuthOidcState: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let identity = match provider.exchange_code(code, &code_verifier, &nonce).await {
+            Ok(identity) => identity,
+            Err(err) => {
+                write_log("WARN", format!("OIDC login with {} failed: {}", provider.name, err).as_str());
+                result.message = "Failed to verify identity with provider".to_string();
+                return result;
+            }
+        };
+
+        let auth_usernid = match Self::linked_user(&mut conn, &provider.name, &identity.subject).await {
+            Ok(Some(auth_usernid)) => auth_usernid,
+            Ok(None) => {
+                let Some(email) = identity.email.clone().filter(|email| identity.email_verified && !email.trim().is_empty()) else {
+                    result.message = "Email not verified by provider".to_string();
+                    return result;
+                };
+
+                match Self::link_by_email(&mut conn, &provider.name, &identity.subject, &email).await {
+                    Ok(Some((auth_usernid, false))) => auth_usernid,
+                    Ok(Some((auth_usernid, true))) => {
+                        // 🚫 Token / sesi yang mungkin dibuat sebelum aktivasi tidak boleh ikut hidup
+                        if let Err(err) = SessionService::revoke_all(connection.clone(), auth_usernid, "oidc_link").await {
+                            result.error = Some(err);
+                            return result;
+                        }
+                        auth_usernid
+                    }
+                    Ok(None) => {
+                        drop(conn);
+                        match Self::create_account(&connection, &provider.name, &identity, &email, ip_address).await {
+                            Ok(auth_usernid) => {
+                                conn = match connection.get().await {
+                                    Ok(conn) => conn,
+                                    Err(err) => {
+                                        result.error = format!("Internal Server error: {:?}", err).into();
+                                        return result;
+                                    }
+                                };
+                                auth_usernid
+                            }
+                            Err(err) => {
+                                result.error = Some(err);
+                                return result;
+                            }
+                        }
+                    }
+                    Err(LinkError::AlreadyLinked) => {
+                        result.message = "Account is already linked to another identity".to_string();
+                        return result;
+                    }
+                    Err(LinkError::Database(err)) => {
+                        result.error = Some(err);
+                        return result;
+                    }
+                }
+            }
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        match AuthService::get_web_user(&mut conn, auth_usernid).await {
+            Ok(Some(user)) => {
+                result.result = true;
+                result.message = format!("Welcome {}", user.email);
+                result.data = Some(user);
+            }
+            Ok(None) => result.message = "No user found".to_string(),
+            Err(err) => result.error = Some(err),
+        }
+        result
+    }
+
+    async fn linked_user(conn: &mut PooledConnection<'_, ConnectionManager>, provider_name: &str, subject: &str) -> Result<Option<i32>, String> {
+        let row = conn.query(
+            r#"SELECT AuthUserNID FROM AuthUser WHERE OidcProvider = @P1 AND Sub = @P2"#,
+            &[&provider_name, &subject],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        Ok(row.and_then(|row| row.get::<i32, _>("AuthUserNID")))
+    }
+
+    /// 🔗 Tautkan ke akun yang sudah ada dengan email yang sama (email sudah diverifikasi provider).
+    /// Akun yang belum aktivasi ikut diaktifkan karena kepemilikan email sudah terbukti. Password & link reset akun tersebut
+    /// dibuat oleh orang yang belum tentu pemilik email, jadi diganti acak / dihapus. Hasil: (AuthUserNID, akun baru diaktifkan)
+    async fn link_by_email(conn: &mut PooledConnection<'_, ConnectionManager>, provider_name: &str, subject: &str, email: &str) -> Result<Option<(i32, bool)>, LinkError> {
+        let row = conn.query(
+            r#"SELECT AuthUserNID, OidcProvider FROM AuthUser WHERE Email = @P1"#, &[&email],
+        ).await.map_err(|e| LinkError::Database(format!("Query execution failed: {:?}", e)))?
+            .into_row().await.map_err(|e| LinkError::Database(format!("Query execution failed: {:?}", e)))?;
+
+        let Some(row) = row else { return Ok(None) };
+        if row.get::<&str, _>("OidcProvider").is_some() {
+            return Err(LinkError::AlreadyLinked);
+        }
+        let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+
+        let password = hash_password(&GenericService::random_string(64)).await.map_err(LinkError::Database)?;
+        let updated = conn.query(
+            r#"UPDATE [dbo].[AuthUser] SET [OidcProvider] = @P2, [Sub] = @P3,
+                [disableLogin] = CASE WHEN ActivateTime IS NULL THEN 0 ELSE disableLogin END,
+                [OTPGeneratedLink] = CASE WHEN ActivateTime IS NULL THEN NULL ELSE OTPGeneratedLink END,
+                [Password] = CASE WHEN ActivateTime IS NULL THEN @P5 ELSE Password END,
+                [PasswordChangedAt] = CASE WHEN ActivateTime IS NULL THEN @P4 ELSE PasswordChangedAt END,
+                [ResetPasswordKey] = CASE WHEN ActivateTime IS NULL THEN NULL ELSE ResetPasswordKey END,
+                [ResetPasswordFlag] = CASE WHEN ActivateTime IS NULL THEN 0 ELSE ResetPasswordFlag END,
+                [ActivateTime] = ISNULL(ActivateTime, @P4)
+            OUTPUT CASE WHEN DELETED.ActivateTime IS NULL THEN 1 ELSE 0 END AS Activated
+            WHERE AuthUserNID = @P1 AND OidcProvider IS NULL"#,
+            &[&auth_usernid, &provider_name, &subject, &Utc::now(), &password],
+        ).await.map_err(|e| LinkError::Database(format!("Failed to update AuthUser: {:?}", e)))?
+            .into_row().await.map_err(|e| LinkError::Database(format!("Failed to update AuthUser: {:?}", e)))?;
+
+        let Some(updated) = updated else {
+            return Err(LinkError::AlreadyLinked);
+        };
+        let activated = updated.get::<i32, _>("Activated").unwrap_or(0) == 1;
+
+        write_log("INFO", format!("Linked {} identity to user {}{}", provider_name, auth_usernid, if activated { " (activated, password reset)" } else { "" }).as_str());
+        Ok(Some((auth_usernid, activated)))
+    }
+
+    /// 🆕 Login pertama tanpa akun: buat UserKyc, AuthUser (langsung aktif) & TableRequest seperti register.
+    /// Password diisi acak, user bisa membuat password sendiri lewat reset password
+    async fn create_account(connection: &Pool<ConnectionManager>, provider_name: &str, identity: &OidcIdentity, email: &str, ip_address: &str) -> Result<i32, String> {
+        let password = hash_password(&GenericService::random_string(64)).await?;
+
+        let trans = Transaction::begin(connection).await.map_err(|e| format!("Failed to start transaction: {:?}", e))?;
+        let mut guard = trans.conn.lock().await;
+        let inserted = match guard.as_mut() {
+            Some(conn) => Self::insert_account(conn, provider_name, identity, email, ip_address, &password).await,
+            None => Err("Failed to get database connection".to_string()),
+        };
+        drop(guard);
+
+        // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+        let auth_usernid = match inserted {
+            Ok(auth_usernid) => auth_usernid,
+            Err(err) => {
+                if let Err(rollback_err) = trans.rollback().await {
+                    write_log("ERROR", format!("Failed to rollback transaction: {:?}", rollback_err).as_str());
+                }
+                return Err(err);
+            }
+        };
+        trans.commit().await.map_err(|e| format!("Failed to commit transaction: {:?}", e))?;
+
+        write_log("INFO", format!("Created user {} from {} login", auth_usernid, provider_name).as_str());
+        Ok(auth_usernid)
+    }
+
+    /// Insert UserKyc, AuthUser & TableRequest untuk [`Self::create_account`] di dalam transaksi yang sudah dibuka
+    async fn insert_account(conn: &mut PooledConnection<'_, ConnectionManager>, provider_name: &str, identity: &OidcIdentity, email: &str, ip_address: &str, password: &str) -> Result<i32, String> {
+        let full_name = identity.name.clone().unwrap_or_default();
+        let now = Utc::now();
+
+        let auto_nid: i32 = conn.query(
+            r#"INSERT INTO [dbo].[UserKyc]
+            ([Email],[MobilePhone],[Fullname],[Sales],[Stage],[CIFNID],[ChangeNID],[PendingCIFNID],
+            [IsRejected],[IsFinished],[IsRevised],[IsImported],[SaveTime],[LastUpdate],[SaveIpAddress])
+            OUTPUT INSERTED.AutoNID
+            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8,@P9,@P10,@P11,@P12,@P13,@P13,@P14)"#,
+            &[&email, &"", &full_name, &0i32, &1i32, &0i32, &0i32, &0i32, &false, &false, &false, &false, &now, &ip_address],
+        ).await.map_err(|e| format!("Failed to insert UserKyc: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Failed to insert UserKyc: {:?}", e))?
+            .and_then(|row| row.get("AutoNID"))
+            .ok_or_else(|| "Failed to get AutoNID from UserKyc".to_string())?;
+
+        let auth_usernid: i32 = conn.query(
+            r#"INSERT INTO [dbo].[AuthUser]
+            ([WebCIFNID],[Email],[Handphone],[ActivateCode],[Password],[RegisterDate],[ActivateTime],
+            [disableLogin],[Picture],[Sub],[OidcProvider],[PasswordChangedAt])
+            OUTPUT INSERTED.AuthUserNID
+            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P6,@P7,@P8,@P9,@P10,@P6)"#,
+            &[&auto_nid, &email, &"", &GenericService::random_string(20), &password, &now, &false, &"", &identity.subject, &provider_name],
+        ).await.map_err(|e| format!("Failed to insert AuthUser: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Failed to insert AuthUser: {:?}", e))?
+            .and_then(|row| row.get("AuthUserNID"))
+            .ok_or_else(|| "Failed to get AuthUserNID from AuthUser".to_string())?;
+
+        conn.execute(
+            r#"INSERT INTO [dbo].[TableRequest] ([WebCIFNID], [Referal]) VALUES (@P1, @P2)"#,
+            &[&auto_nid, &""],
+        ).await.map_err(|e| format!("Failed to insert TableRequest: {:?}", e))?;
+
+        Ok(auth_usernid)
+    }
+}
+
+enum LinkError {
+    AlreadyLinked,
+    Database(String),
+}
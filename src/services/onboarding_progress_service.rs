@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use tiberius::Row;
+use validator::ValidationError;
+
+use crate::contexts::{
+    model::{ActionResult, OnboardingProgress, StepProgress},
+    onboarding::KycStage,
+};
+use super::validation_service::validator::{valid_name, valid_number_card, valid_phone_number};
+
+pub const PROGRESS_NOT_STARTED: &str = "not_started";
+pub const PROGRESS_INCOMPLETE: &str = "incomplete";
+pub const PROGRESS_COMPLETE: &str = "complete";
+pub const PROGRESS_UNDER_REVIEW: &str = "under_review";
+
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    /// `UserKyc` (alias `A`)
+    Kyc,
+    /// `TableRequest` (alias `B`)
+    Request,
+}
+
+/// Aturan per field, sama dengan validasi di request form-nya
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    Text,
+    /// Huruf & spasi saja
+    Name,
+    /// Angka saja
+    Digits,
+    /// NIK: angka, minimal 15 karakter
+    IdCard,
+    Phone,
+    /// Kode pilihan dari `/option`, 0 = belum dipilih
+    Code,
+    Date,
+    /// File base64 yang tersimpan di kolom, cukup dicek ada isinya
+    File,
+}
+
+struct FieldRule {
+    field: &'static str,
+    source: Source,
+    column: &'static str,
+    rule: Rule,
+}
+
+const fn kyc(field: &'static str, column: &'static str, rule: Rule) -> FieldRule {
+    FieldRule { field, source: Source::Kyc, column, rule }
+}
+
+const fn req(field: &'static str, column: &'static str, rule: Rule) -> FieldRule {
+    FieldRule { field, source: Source::Request, column, rule }
+}
+
+/// Field wajib per form yang benar-benar disimpan oleh save function-nya. Field opsional (`*_text`, `copy_id`,
+/// `question_1`..`6`) dan field yang tidak disimpan (`company_city`, `company_zipcode`) tidak dihitung
+const PERSONAL_DATA: &[FieldRule] = &[
+    kyc("full_name", "Fullname", Rule::Name),
+    kyc("mother_name", "MotherName", Rule::Name),
+    kyc("idcard_number", "IDCardNumber", Rule::IdCard),
+    kyc("nationality", "Nationality", Rule::Code),
+    kyc("sex", "Sex", Rule::Code),
+    req("residence_status", "ResidencyNStatus", Rule::Code),
+    req("beneficiary_owner", "CIFInvestorBeneficiaryOwner", Rule::Code),
+    kyc("birth_place", "BirthPlace", Rule::Text),
+    kyc("birth_date", "BirthDate", Rule::Date),
+    kyc("birth_country", "BirthCountry", Rule::Text),
+    kyc("religion", "Religion", Rule::Code),
+    kyc("marital_status", "MaritalStatus", Rule::Code),
+    kyc("education", "Education", Rule::Code),
+    kyc("idcard_expireddate", "IDCardExpireDate", Rule::Date),
+    kyc("idcard_country", "IDCardCountry", Rule::Text),
+    kyc("idcard_city", "IDCardCity", Rule::Code),
+    kyc("idcard_district", "IDCardDistrict", Rule::Text),
+    kyc("idcard_subdistrict", "IDCardSubdistrict", Rule::Text),
+    kyc("idcard_rt", "IDCardRT", Rule::Digits),
+    kyc("idcard_rw", "IDCardRW", Rule::Digits),
+    kyc("idcard_address", "IDCardAddress", Rule::Text),
+    kyc("idcard_zipcode", "IDCardZipcode", Rule::Digits),
+    kyc("domicile_city", "DomicileCity", Rule::Code),
+    kyc("domicile_district", "DomicileDistrict", Rule::Text),
+    kyc("domicile_subdistrict", "DomicileSubdistrict", Rule::Text),
+    kyc("domicile_rt", "DomicileRT", Rule::Digits),
+    kyc("domicile_rw", "DomicileRW", Rule::Digits),
+    kyc("domicile_address", "DomicileAddress", Rule::Text),
+    kyc("domicile_zipcode", "DomicileZipcode", Rule::Digits),
+];
+
+const DOCUMENTS: &[FieldRule] = &[
+    kyc("idcard_file", "IDCardFile", Rule::File),
+    kyc("selfie_file", "SelfieFile", Rule::File),
+    kyc("signature_file", "SignatureFile", Rule::File),
+];
+
+const BENEFICIARY_OWNER: &[FieldRule] = &[
+    req("beneficiary_name", "CIFInvestorBeneficiaryOwnerName", Rule::Text),
+    req("beneficiary_mother_maiden_name", "CIFInvestorBeneficiaryMothersMaidenName", Rule::Text),
+    req("beneficiary_relation", "CIFInvestorBeneficiaryOwnerRelation", Rule::Code),
+    req("beneficiary_sex", "CIFInvestorBeneficiaryOwnerSex", Rule::Code),
+    req("beneficiary_birth_place", "CIFInvestorBeneficiaryOwnerBirthPlace", Rule::Text),
+    req("beneficiary_birth_date", "CIFInvestorBeneficiaryOwnerBirthDate", Rule::Date),
+    req("beneficiary_nationality", "CIFInvestorBeneficiaryOwnerNationality", Rule::Code),
+    req("beneficiary_idcard_type", "CIFInvestorBeneficiaryOwnerIDCardType", Rule::Code),
+    req("beneficiary_idcard_number", "CIFInvestorBeneficiaryOwnerIDCardNumber", Rule::Digits),
+    req("beneficiary_idcard_expiredate", "CIFInvestorBeneficiaryOwnerIDCardExpiredDate", Rule::Date),
+    req("beneficiary_email", "CIFInvestorBeneficiaryOwnerEmail", Rule::Text),
+    req("beneficiary_npwp_number", "CIFInvestorBeneficiaryOwnerNPWPNumber", Rule::Digits),
+    req("beneficiary_address1", "CIFInvestorBeneficiaryOwnerAddress1", Rule::Text),
+    req("beneficiary_address2", "CIFInvestorBeneficiaryOwnerAddress2", Rule::Text),
+    req("beneficiary_kecamatan", "CIFInvestorBeneficiaryOwnerKecamatan", Rule::Text),
+    req("beneficiary_rt", "CIFInvestorBeneficiaryOwnerRT", Rule::Digits),
+    req("beneficiary_rw", "CIFInvestorBeneficiaryOwnerRW", Rule::Digits),
+    req("beneficiary_city", "CIFInvestorBeneficiaryOwnerCity", Rule::Code),
+    req("beneficiary_province", "CIFInvestorBeneficiaryOwnerProvince", Rule::Text),
+    req("beneficiary_country", "CIFInvestorBeneficiaryOwnerCountry", Rule::Code),
+    req("beneficiary_postalcode", "CIFInvestorBeneficiaryOwnerPostalCode", Rule::Digits),
+    req("beneficiary_mobile_phone", "CIFInvestorBeneficiaryOwnerMobilePhone", Rule::Phone),
+    req("beneficiary_occupation", "CIFInvestorBeneficiaryOwnerOccupation", Rule::Code),
+    req("beneficiary_company_name", "CIFInvestorBeneficiaryOwnerCompanyName", Rule::Text),
+    req("beneficiary_position", "CIFInvestorBeneficiaryOwnerPosition", Rule::Code),
+    req("beneficiary_nature_bussiness", "CIFInvestorBeneficiaryOwnerNatureOfBusiness", Rule::Code),
+    req("beneficiary_income_peranum", "CIFInvestorBeneficiaryOwnerIncomePerAnnum", Rule::Code),
+    req("beneficiary_company_address", "CIFInvestorBeneficiaryOwnerCompanyAddress", Rule::Text),
+    req("beneficiary_company_address2", "CIFInvestorBeneficiaryOwnerCompanyAddress2", Rule::Text),
+    req("beneficiary_company_address3", "CIFInvestorBeneficiaryOwnerCompanyAddress3", Rule::Text),
+    req("beneficiary_company_city", "CIFInvestorBeneficiaryOwnerCompanyCity", Rule::Code),
+    req("beneficiary_company_province", "CIFInvestorBeneficiaryOwnerCompanyProvince", Rule::Text),
+    req("beneficiary_company_country", "CIFInvestorBeneficiaryOwnerCompanyCountry", Rule::Code),
+    req("beneficiary_company_postalcode", "CIFInvestorBeneficiaryOwnerCompanyPostalCode", Rule::Digits),
+    req("beneficiary_fund_source", "CIFInvestorBeneficiaryOwnerFundSource", Rule::Text),
+];
+
+const BANK_ACCOUNT: &[FieldRule] = &[
+    kyc("question_rdn", "QuestionRDN", Rule::Code),
+    kyc("bank_name", "BankName", Rule::Text),
+    kyc("bank_account_holder", "BankAccountHolder", Rule::Name),
+    kyc("bank_account_number", "BankAccountNumber", Rule::Digits),
+    kyc("bank_branch", "BankBranch", Rule::Text),
+];
+
+const OCCUPATION: &[FieldRule] = &[
+    kyc("company_name", "CompanyName", Rule::Text),
+    kyc("company_address", "CompanyAddress", Rule::Text),
+    kyc("question_npwp", "QuestionNPWP", Rule::Code),
+    kyc("npwp_reason", "NPWPReason", Rule::Text),
+    kyc("npwp_file", "NPWPFile", Rule::File),
+    kyc("npwp_number", "NPWPNumber", Rule::Digits),
+    kyc("fund_source", "Fundsource", Rule::Code),
+    kyc("occupation", "Occupation", Rule::Code),
+    kyc("nature_bussiness", "NatureOfBusiness", Rule::Code),
+    kyc("position", "Position", Rule::Code),
+    kyc("income_peranum", "IncomePerAnnum", Rule::Code),
+    kyc("spouse_name", "SpouseName", Rule::Text),
+    kyc("spouse_relationship", "SpouseRelationship", Rule::Code),
+    kyc("spouse_occupation", "SpouseOccupation", Rule::Code),
+    kyc("spouse_fund_source", "SpouseFundSource", Rule::Code),
+    kyc("spouse_position", "SpousePosition", Rule::Code),
+    kyc("spouse_income_peranum", "SpouseIncomePerAnnum", Rule::Code),
+    kyc("spouse_nature_bussiness", "SpouseNatureOfBusiness", Rule::Code),
+    kyc("spouse_company_name", "SpouseCompanyName", Rule::Text),
+    kyc("spouse_company_city", "SpouseCompanyCity", Rule::Code),
+    kyc("spouse_company_address", "SpouseCompanyAddress", Rule::Text),
+    kyc("spouse_company_zipcode", "SpouseCompanyZipcode", Rule::Digits),
+];
+
+const SUPPORTING_DATA: &[FieldRule] = &[
+    req("contact_person_name", "ContactPersonName", Rule::Text),
+    req("contact_person_relation", "ContactPersonRelation", Rule::Text),
+    req("contact_person_mobile_phone", "ContactPersonMobilePhone", Rule::Text),
+    req("contact_person_address", "ContactPersonAddress", Rule::Text),
+    kyc("investment_objective", "InvestmentObjectives", Rule::Code),
+    kyc("risk", "Risk", Rule::Code),
+    kyc("question_fatca", "QuestionFATCA", Rule::Text),
+    kyc("fatca_1", "FATCA1", Rule::Text),
+    kyc("fatca_2", "FATCA2", Rule::Text),
+    kyc("fatca_3", "FATCA3", Rule::Text),
+];
+
+/// Urutan form sama dengan urutan pengisian, nama step sama dengan `KycStep::as_str`
+const STEPS: [(&str, &[FieldRule]); 6] = [
+    ("personal_data", PERSONAL_DATA),
+    ("beneficiary_owner", BENEFICIARY_OWNER),
+    ("documents", DOCUMENTS),
+    ("bank_account", BANK_ACCOUNT),
+    ("occupation", OCCUPATION),
+    ("supporting_data", SUPPORTING_DATA),
+];
+
+/// Beneficiary owner 2 = pemilik dana orang lain, hanya saat itu form beneficiary owner wajib
+const BENEFICIARY_OTHER: &str = "2";
+
+fn error_message(err: ValidationError) -> String {
+    err.message.map(|message| message.to_string()).unwrap_or_else(|| "Invalid value".to_string())
+}
+
+impl Rule {
+    /// Ekspresi SQL kolom, semua dibaca sebagai teks supaya tipe kolom lama (int / varchar) tidak jadi masalah
+    fn select(self, source: Source, column: &str) -> String {
+        let column = match source {
+            Source::Kyc => format!("A.[{}]", column),
+            Source::Request => format!("B.[{}]", column),
+        };
+        match self {
+            Rule::File => format!("IIF(DATALENGTH({}) > 0, N'1', NULL)", column),
+            Rule::Date => format!("CONVERT(NVARCHAR(10), {}, 23)", column),
+            _ => format!("CONVERT(NVARCHAR(4000), {})", column),
+        }
+    }
+
+    /// `None` = kosong, `Some(Err)` = terisi tapi tidak valid
+    fn check(self, value: Option<&str>) -> Option<Result<(), String>> {
+        let value = value.map(str::trim).filter(|value| !value.is_empty())?;
+
+        match self {
+            Rule::Code => match value.parse::<i32>() {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(())),
+                Err(_) => Some(Err("Must be a number".to_string())),
+            },
+            // Tanggal default SQL Server dianggap belum diisi
+            Rule::Date if value == "1900-01-01" => None,
+            Rule::Name => Some(valid_name(value).map_err(error_message)),
+            Rule::Digits => Some(valid_number_card(value).map_err(error_message)),
+            Rule::IdCard => Some(valid_number_card(value).map_err(error_message).and_then(|_| {
+                if value.chars().count() < 15 { Err("Minimum 15 characters".to_string()) } else { Ok(()) }
+            })),
+            Rule::Phone => Some(valid_phone_number(value).map_err(error_message)),
+            Rule::Text | Rule::Date | Rule::File => Some(Ok(())),
+        }
+    }
+}
+
+pub struct OnboardingProgressService;
+
+impl OnboardingProgressService {
+
+    fn evaluate(step: &str, rules: &[FieldRule], offset: usize, row: &Row, under_review: bool) -> StepProgress {
+        let mut missing: Vec<String> = Vec::new();
+        let mut invalid: HashMap<String, String> = HashMap::new();
+
+        for (index, rule) in rules.iter().enumerate() {
+            match rule.rule.check(row.get::<&str, _>(format!("F{}", offset + index).as_str())) {
+                None => missing.push(rule.field.to_string()),
+                Some(Err(message)) => {
+                    invalid.insert(rule.field.to_string(), message);
+                }
+                Some(Ok(())) => {}
+            }
+        }
+
+        let status = if missing.len() == rules.len() {
+            PROGRESS_NOT_STARTED
+        } else if !missing.is_empty() || !invalid.is_empty() {
+            PROGRESS_INCOMPLETE
+        } else if under_review {
+            PROGRESS_UNDER_REVIEW
+        } else {
+            PROGRESS_COMPLETE
+        };
+
+        StepProgress { step: step.to_string(), status: status.to_string(), missing, invalid }
+    }
+
+    /// 📊 Status tiap form onboarding beserta field yang masih kosong / tidak valid, dari data `UserKyc` & `TableRequest`.
+    /// Form beneficiary owner hanya muncul jika pemilik dana bukan diri sendiri
+    pub async fn progress(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<OnboardingProgress, String> {
+        let mut result: ActionResult<OnboardingProgress, String> = ActionResult::default();
+
+        let columns = STEPS.iter()
+            .flat_map(|(_, rules)| rules.iter())
+            .enumerate()
+            .map(|(index, rule)| format!("{} AS [F{}]", rule.rule.select(rule.source, rule.column), index))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let row = match conn.query(
+            format!(r#"SELECT A.Stage, A.IsFinished, A.IsApproved, A.IsRejected, CONVERT(NVARCHAR(10), B.CIFInvestorBeneficiaryOwner) AS BeneficiaryOwner, {}
+            FROM UserKyc A
+            JOIN AuthUser U ON U.WebCIFNID = A.AutoNID
+            LEFT JOIN TableRequest B ON B.WebCIFNID = A.AutoNID
+            WHERE U.AuthUserNID = @P1"#, columns),
+            &[&auth_usernid],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row,
+                Ok(None) => {
+                    result.message = "No user found".to_string();
+                    return result;
+                }
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let stage = KycStage::from_i32(row.get::<i32, _>("Stage").unwrap_or(0));
+        let under_review = stage == Some(KycStage::Completed)
+            && row.get::<bool, _>("IsFinished").unwrap_or(false)
+            && !row.get::<bool, _>("IsApproved").unwrap_or(false)
+            && !row.get::<bool, _>("IsRejected").unwrap_or(false);
+
+        let beneficiary_required = row.get::<&str, _>("BeneficiaryOwner").map(str::trim) == Some(BENEFICIARY_OTHER);
+
+        let mut steps: Vec<StepProgress> = Vec::new();
+        let mut offset = 0;
+        for (step, rules) in STEPS {
+            if step != "beneficiary_owner" || beneficiary_required {
+                steps.push(Self::evaluate(step, rules, offset, &row, under_review));
+            }
+            offset += rules.len();
+        }
+
+        result.result = true;
+        result.message = "Retrieve successfully".to_string();
+        result.data = Some(OnboardingProgress { stage, under_review, steps });
+        result
+    }
+}
@@ -0,0 +1,62 @@
+use actix_web::web;
+use bb8::PooledConnection;
+use bb8_tiberius::ConnectionManager;
+use chrono::Utc;
+
+use crate::contexts::password::verify_password_blocking;
+
+pub struct PasswordHistoryService;
+
+impl PasswordHistoryService {
+
+    /// 🔁 Password baru sama dengan password sekarang atau salah satu dari `history_count` password terakhir
+    pub async fn is_reused(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, password: &str, history_count: i32) -> Result<bool, String> {
+        if history_count <= 0 {
+            return Ok(false);
+        }
+
+        // Password sekarang ikut dicek untuk user lama yang belum punya history
+        let rows = conn.query(
+            r#"SELECT Password AS PasswordHash FROM AuthUser WHERE AuthUserNID = @P1
+            UNION ALL
+            SELECT PasswordHash FROM (
+                SELECT TOP (@P2) PasswordHash FROM AuthPasswordHistory WHERE AuthUserNID = @P1 ORDER BY CreatedAt DESC, HistoryNID DESC
+            ) H"#,
+            &[&auth_usernid, &history_count],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_first_result().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        let hashes: Vec<String> = rows.iter()
+            .map(|row| row.get::<&str, _>("PasswordHash").unwrap_or_default().to_string())
+            .collect();
+        let password = password.to_string();
+
+        // ⏳ Argon2 berat (sampai N+1 verifikasi), jalankan di thread pool blocking supaya worker async tidak tertahan
+        web::block(move || hashes.iter().any(|hash| verify_password_blocking(&password, hash).valid))
+            .await
+            .map_err(|e| format!("Failed to verify password history: {:?}", e))
+    }
+
+    /// 📝 Simpan hash password yang sedang aktif ke history lalu buang yang melebihi `history_count`.
+    /// Dipanggil di transaksi yang sama dengan perubahan password
+    pub async fn record(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, history_count: i32) -> Result<(), String> {
+        if history_count <= 0 {
+            return Ok(());
+        }
+
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthPasswordHistory] ([AuthUserNID],[PasswordHash],[CreatedAt])
+            SELECT AuthUserNID, Password, @P2 FROM AuthUser WHERE AuthUserNID = @P1"#,
+            &[&auth_usernid, &Utc::now()],
+        ).await.map_err(|e| format!("Failed to insert AuthPasswordHistory: {:?}", e))?;
+
+        conn.execute(
+            r#"DELETE FROM [dbo].[AuthPasswordHistory] WHERE AuthUserNID = @P1 AND HistoryNID NOT IN (
+                SELECT TOP (@P2) HistoryNID FROM AuthPasswordHistory WHERE AuthUserNID = @P1 ORDER BY CreatedAt DESC, HistoryNID DESC
+            )"#,
+            &[&auth_usernid, &history_count],
+        ).await.map_err(|e| format!("Failed to delete AuthPasswordHistory: {:?}", e))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+use std::env;
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rand::Rng;
+
+use crate::contexts::{
+    connection::Transaction,
+    crypto::hash_token,
+    jwt_session::Claims,
+    logger::write_log,
+    model::{ActionResult, PhoneOtpIssued},
+    onboarding::KycStage,
+    sms::provider_from_env,
+};
+
+pub struct PhoneVerificationService;
+
+/// ⚙️ Batasan OTP handphone, bisa diatur lewat `.env`
+struct OtpConfig {
+    length: u32,
+    ttl: Duration,
+    max_attempts: i32,
+    resend_cooldown: Duration,
+    max_per_hour: i32,
+}
+
+impl OtpConfig {
+    fn load() -> Self {
+        let read = |key: &str, default: i64| -> i64 { env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default) };
+
+        Self {
+            length: read("PHONE_OTP_LENGTH", 6).clamp(4, 9) as u32,
+            ttl: Duration::minutes(read("PHONE_OTP_TTL_MINUTES", 5)),
+            max_attempts: read("PHONE_OTP_MAX_ATTEMPTS", 5) as i32,
+            resend_cooldown: Duration::seconds(read("PHONE_OTP_RESEND_SECONDS", 60)),
+            max_per_hour: read("PHONE_OTP_MAX_PER_HOUR", 5) as i32,
+        }
+    }
+}
+
+impl PhoneVerificationService {
+
+    /// Hash OTP diikat ke user supaya kode yang sama di user lain menghasilkan hash berbeda
+    fn hash_code(auth_usernid: i32, code: &str) -> String {
+        hash_token(&format!("{}:{}", auth_usernid, code.trim()))
+    }
+
+    /// ✅ Nomor handphone user sudah diverifikasi dan masih sama dengan yang terdaftar
+    pub async fn is_verified(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32) -> Result<bool, String> {
+        let row = conn.query(
+            r#"SELECT AuthUserNID FROM AuthUser
+            WHERE AuthUserNID = @P1 AND PhoneVerifiedAt IS NOT NULL AND PhoneVerified = Handphone"#,
+            &[&auth_usernid],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        Ok(row.is_some())
+    }
+
+    /// 🚦 Cek cooldown & batas kirim per jam. `Some(detik)` berarti harus menunggu
+    pub async fn retry_after(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> Result<Option<i64>, String> {
+        let config = OtpConfig::load();
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let row = conn.query(
+            r#"SELECT COUNT(*) AS Sent, MIN(CreatedAt) AS FirstSent, MAX(CreatedAt) AS LastSent FROM PhoneVerification
+            WHERE AuthUserNID = @P1 AND CreatedAt > @P2"#,
+            &[&auth_usernid, &(now - Duration::hours(1))],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        let Some(row) = row else { return Ok(None) };
+        let sent: i32 = row.get("Sent").unwrap_or(0);
+        let first_sent: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("FirstSent").map(|dt| dt.and_utc());
+        let last_sent: Option<DateTime<Utc>> = row.get::<NaiveDateTime, _>("LastSent").map(|dt| dt.and_utc());
+
+        if let Some(first_sent) = first_sent.filter(|_| sent >= config.max_per_hour) {
+            return Ok(Some((first_sent + Duration::hours(1) - now).num_seconds().max(1)));
+        }
+        if let Some(last_sent) = last_sent.filter(|last_sent| *last_sent + config.resend_cooldown > now) {
+            return Ok(Some((last_sent + config.resend_cooldown - now).num_seconds().max(1)));
+        }
+
+        Ok(None)
+    }
+
+    /// 📱 Set / ganti nomor handphone selama onboarding belum selesai. Verifikasi lama ikut direset
+    pub async fn set_phone(connection: web::Data<Pool<ConnectionManager>>, session: Claims, mobile_phone: String) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let completed = KycStage::Completed.as_i32();
+
+        {
+            let mut conn = match connection.get().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    result.error = format!("Internal Server error: {:?}", err).into();
+                    return result;
+                }
+            };
+
+            let row = match conn.query(
+                r#"SELECT A.Handphone, K.Stage FROM AuthUser A JOIN UserKyc K ON K.AutoNID = A.WebCIFNID WHERE A.AuthUserNID = @P1"#,
+                &[&session.auth_usernid]).await {
+                Ok(rows) => match rows.into_row().await {
+                    Ok(Some(row)) => row,
+                    _ => {
+                        result.message = "No user found".to_string();
+                        return result;
+                    }
+                },
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            };
+
+            if row.get::<i32, _>("Stage").unwrap_or(0) >= completed {
+                result.message = "Onboarding already completed, phone number can no longer be changed".to_string();
+                return result;
+            }
+
+            if row.get::<&str, _>("Handphone") == Some(mobile_phone.as_str()) {
+                result.message = "Phone number unchanged".to_string();
+                return result;
+            }
+        }
+
+        let trans = match Transaction::begin(&connection).await {
+            Ok(trans) => trans,
+            Err(err) => {
+                result.error = format!("Failed to start transaction: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let applied = 'tx: {
+            match trans.conn.lock().await.as_mut() {
+                Some(conn) => {
+                    // Stage dicek ulang di sini supaya tidak balapan dengan submit form terakhir
+                    match conn.execute(
+                        r#"UPDATE K SET [MobilePhone] = @P1 FROM [dbo].[UserKyc] K
+                        JOIN [dbo].[AuthUser] A ON A.WebCIFNID = K.AutoNID
+                        WHERE A.AuthUserNID = @P2 AND K.Stage < @P3"#,
+                        &[&mobile_phone, &session.auth_usernid, &completed],
+                    ).await {
+                        Ok(done) => {
+                            if done.total() == 0 {
+                                result.message = "Onboarding already completed, phone number can no longer be changed".to_string();
+                                break 'tx None;
+                            }
+                        }
+                        Err(err) => {
+                            result.error = format!("Failed to update UserKyc: {:?}", err).into();
+                            break 'tx None;
+                        }
+                    }
+
+                    // OTP yang sudah terkirim ke nomor lama otomatis tidak berlaku karena nomornya tidak cocok lagi
+                    if let Err(err) = conn.execute(
+                        r#"UPDATE [dbo].[AuthUser] SET [Handphone] = @P1, [PhoneVerifiedAt] = NULL, [PhoneVerified] = NULL WHERE AuthUserNID = @P2"#,
+                        &[&mobile_phone, &session.auth_usernid],
+                    ).await {
+                        result.error = format!("Failed to update AuthUser: {:?}", err).into();
+                        break 'tx None;
+                    }
+
+                    Some(())
+                }
+                None => {
+                    result.error = Some("Failed to get database connection".into());
+                    break 'tx None;
+                }
+            }
+        };
+
+        // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+        if applied.is_none() {
+            if let Err(err) = trans.rollback().await {
+                write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+            }
+            return result;
+        }
+
+        if let Err(err) = trans.commit().await {
+            result.error = format!("Failed to commit transaction: {:?}", err).into();
+            return result;
+        }
+
+        result.result = true;
+        result.message = "Phone number saved, please verify it".to_string();
+        result
+    }
+
+    /// 📲 Buat OTP baru untuk nomor handphone user lalu kirim lewat provider
+    pub async fn send_otp(connection: web::Data<Pool<ConnectionManager>>, session: Claims) -> ActionResult<PhoneOtpIssued, String> {
+        let mut result: ActionResult<PhoneOtpIssued, String> = ActionResult::default();
+        let config = OtpConfig::load();
+
+        let provider = match provider_from_env() {
+            Ok(provider) => provider,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        match Self::is_verified(&mut conn, session.auth_usernid).await {
+            Ok(true) => {
+                result.message = "Phone number already verified".to_string();
+                return result;
+            }
+            Ok(false) => {}
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        }
+
+        let mobile_phone = match conn.query(
+            r#"SELECT Handphone FROM AuthUser WHERE AuthUserNID = @P1"#, &[&session.auth_usernid]).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row.get::<&str, _>("Handphone").unwrap_or_default().to_string(),
+                _ => {
+                    result.message = "No user found".to_string();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if mobile_phone.is_empty() {
+            result.message = "No phone number registered".to_string();
+            return result;
+        }
+
+        let code: String = {
+            let mut rng = rand::rng();
+            (0..config.length).map(|_| char::from(b'0' + rng.random_range(0..10u8))).collect()
+        };
+        let now = Utc::now();
+
+        // OTP lama otomatis tidak berlaku, hanya OTP terbaru yang dicek saat verifikasi
+        if let Err(err) = conn.execute(
+            r#"INSERT INTO [dbo].[PhoneVerification] ([AuthUserNID],[MobilePhone],[CodeHash],[Attempts],[CreatedAt],[ExpiresAt])
+            VALUES (@P1,@P2,@P3,0,@P4,@P5)"#,
+            &[&session.auth_usernid, &mobile_phone, &Self::hash_code(session.auth_usernid, &code), &now, &(now + config.ttl)],
+        ).await {
+            result.error = format!("Failed to insert PhoneVerification: {:?}", err).into();
+            return result;
+        }
+
+        let message = format!("Kode verifikasi Anda: {}. Berlaku {} menit. Jangan berikan kode ini kepada siapa pun.", code, config.ttl.num_minutes());
+        if let Err(err) = provider.send(&mobile_phone, &message).await {
+            result.error = Some(err);
+            return result;
+        }
+
+        result.result = true;
+        result.message = "Verification code sent".to_string();
+        result.data = Some(PhoneOtpIssued {
+            expires_in: config.ttl.num_seconds(),
+            resend_in: config.resend_cooldown.num_seconds(),
+        });
+        result
+    }
+
+    /// ✅ Cocokkan OTP terbaru. Setelah `PHONE_OTP_MAX_ATTEMPTS` kali salah, user harus minta kode baru
+    pub async fn verify_otp(connection: web::Data<Pool<ConnectionManager>>, session: Claims, code: String) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let config = OtpConfig::load();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let row = match conn.query(
+            r#"SELECT TOP 1 V.VerificationNID, V.MobilePhone, V.CodeHash, V.ExpiresAt, V.VerifiedAt, A.Handphone
+            FROM PhoneVerification V JOIN AuthUser A ON A.AuthUserNID = V.AuthUserNID
+            WHERE V.AuthUserNID = @P1 ORDER BY V.CreatedAt DESC"#, &[&session.auth_usernid]).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row,
+                _ => {
+                    result.message = "No verification code requested".to_string();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let verification_nid: i64 = row.get("VerificationNID").unwrap_or(0);
+        let mobile_phone = row.get::<&str, _>("MobilePhone").unwrap_or_default().to_string();
+        let expired = row.get::<NaiveDateTime, _>("ExpiresAt").is_none_or(|dt| dt.and_utc() < Utc::now());
+
+        if row.get::<NaiveDateTime, _>("VerifiedAt").is_some() || expired || row.get::<&str, _>("Handphone") != Some(mobile_phone.as_str()) {
+            result.message = "Verification code expired, please request a new code".to_string();
+            return result;
+        }
+
+        // Jatah percobaan dipakai dulu secara atomik, jadi request paralel tidak bisa melewati batas
+        let attempts = match conn.query(
+            r#"UPDATE [dbo].[PhoneVerification] SET [Attempts] = [Attempts] + 1 OUTPUT INSERTED.Attempts
+            WHERE VerificationNID = @P1 AND VerifiedAt IS NULL AND Attempts < @P2"#,
+            &[&verification_nid, &config.max_attempts],
+        ).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row.get::<i32, _>("Attempts").unwrap_or(config.max_attempts),
+                Ok(None) => {
+                    result.message = "Too many invalid attempts, please request a new code".to_string();
+                    return result;
+                }
+                Err(err) => {
+                    result.error = format!("Failed to update PhoneVerification: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Failed to update PhoneVerification: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if row.get::<&str, _>("CodeHash") != Some(Self::hash_code(session.auth_usernid, &code).as_str()) {
+            result.message = format!("Invalid verification code, {} attempt(s) left", (config.max_attempts - attempts).max(0));
+            return result;
+        }
+
+        let now = Utc::now();
+        let updated = match conn.execute(
+            r#"UPDATE [dbo].[PhoneVerification] SET [VerifiedAt] = @P1 WHERE VerificationNID = @P2 AND VerifiedAt IS NULL"#,
+            &[&now, &verification_nid],
+        ).await {
+            Ok(done) => done.total(),
+            Err(err) => {
+                result.error = format!("Failed to update PhoneVerification: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if updated == 0 {
+            result.message = "Verification code expired, please request a new code".to_string();
+            return result;
+        }
+
+        if let Err(err) = conn.execute(
+            r#"UPDATE [dbo].[AuthUser] SET [PhoneVerifiedAt] = @P1, [PhoneVerified] = @P2 WHERE AuthUserNID = @P3"#,
+            &[&now, &mobile_phone, &session.auth_usernid],
+        ).await {
+            result.error = format!("Failed to update AuthUser: {:?}", err).into();
+            return result;
+        }
+
+        result.result = true;
+        result.message = "Phone number verified".to_string();
+        result
+    }
+}
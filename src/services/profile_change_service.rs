@@ -0,0 +1,712 @@
+use std::collections::HashMap;
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use serde_json::{Map, Value};
+use tiberius::{Row, ToSql};
+use tokio_stream::StreamExt;
+
+use crate::contexts::{
+    connection::Transaction,
+    logger::write_log,
+    model::{ActionResult, KycVersionInfo, ProfileChangeInfo},
+};
+use super::{
+    audit_service::{AuditService, AUDIT_PROFILE_CHANGE_APPROVED, AUDIT_PROFILE_CHANGE_REJECTED},
+    validation_service::validator::{valid_name, valid_number_card},
+};
+
+pub const CHANGE_PENDING: &str = "pending";
+pub const CHANGE_APPROVED: &str = "approved";
+pub const CHANGE_REJECTED: &str = "rejected";
+pub const CHANGE_CANCELLED: &str = "cancelled";
+
+const CHANGE_COLUMNS: &str = "ChangeNID, AuthUserNID, Section, Changes, Previous, Reason, Status, RequestedAt, ReviewedAt, ReviewNote";
+
+/// Maksimum panjang teks bebas per field
+const MAX_TEXT_LENGTH: usize = 255;
+
+#[derive(Debug, Clone, Copy)]
+enum FieldKind {
+    /// Teks wajib diisi
+    Text,
+    /// Teks boleh kosong (keterangan "lainnya")
+    OptionalText,
+    /// Huruf & spasi saja
+    Name,
+    /// Angka saja (nomor rekening, RT/RW, kode pos)
+    Digits,
+    /// Kode pilihan dari `/option`, angka bukan 0
+    Code,
+}
+
+#[derive(Debug)]
+pub struct ChangeableField {
+    section: &'static str,
+    field: &'static str,
+    column: &'static str,
+    kind: FieldKind,
+}
+
+/// 📋 Field yang boleh diubah setelah approval, per section. Nama field sama dengan form onboarding-nya
+const CHANGEABLE_FIELDS: [ChangeableField; 22] = [
+    ChangeableField { section: "address", field: "domicile_address", column: "DomicileAddress", kind: FieldKind::Text },
+    ChangeableField { section: "address", field: "domicile_city", column: "DomicileCity", kind: FieldKind::Code },
+    ChangeableField { section: "address", field: "domicile_district", column: "DomicileDistrict", kind: FieldKind::Text },
+    ChangeableField { section: "address", field: "domicile_subdistrict", column: "DomicileSubdistrict", kind: FieldKind::Text },
+    ChangeableField { section: "address", field: "domicile_rt", column: "DomicileRT", kind: FieldKind::Digits },
+    ChangeableField { section: "address", field: "domicile_rw", column: "DomicileRW", kind: FieldKind::Digits },
+    ChangeableField { section: "address", field: "domicile_zipcode", column: "DomicileZipcode", kind: FieldKind::Digits },
+    ChangeableField { section: "bank", field: "bank_name", column: "BankName", kind: FieldKind::Text },
+    ChangeableField { section: "bank", field: "bank_account_holder", column: "BankAccountHolder", kind: FieldKind::Name },
+    ChangeableField { section: "bank", field: "bank_account_number", column: "BankAccountNumber", kind: FieldKind::Digits },
+    ChangeableField { section: "bank", field: "bank_branch", column: "BankBranch", kind: FieldKind::Text },
+    ChangeableField { section: "occupation", field: "company_name", column: "CompanyName", kind: FieldKind::Text },
+    ChangeableField { section: "occupation", field: "company_address", column: "CompanyAddress", kind: FieldKind::Text },
+    ChangeableField { section: "occupation", field: "occupation", column: "Occupation", kind: FieldKind::Code },
+    ChangeableField { section: "occupation", field: "occupation_text", column: "OccupationText", kind: FieldKind::OptionalText },
+    ChangeableField { section: "occupation", field: "nature_bussiness", column: "NatureOfBusiness", kind: FieldKind::Code },
+    ChangeableField { section: "occupation", field: "nature_bussiness_text", column: "NatureOfBusinessText", kind: FieldKind::OptionalText },
+    ChangeableField { section: "occupation", field: "position", column: "Position", kind: FieldKind::Code },
+    ChangeableField { section: "occupation", field: "position_text", column: "PositionText", kind: FieldKind::OptionalText },
+    ChangeableField { section: "occupation", field: "income_peranum", column: "IncomePerAnnum", kind: FieldKind::Code },
+    ChangeableField { section: "occupation", field: "fund_source", column: "Fundsource", kind: FieldKind::Code },
+    ChangeableField { section: "occupation", field: "fund_source_text", column: "FundsourceText", kind: FieldKind::OptionalText },
+];
+
+#[derive(Debug)]
+enum FieldValue {
+    Text(String),
+    Int(i32),
+}
+
+/// Satu field yang sudah divalidasi, siap ditulis ke `UserKyc`
+#[derive(Debug)]
+pub struct FieldChange {
+    field: &'static ChangeableField,
+    value: FieldValue,
+}
+
+impl FieldChange {
+    fn sql_value(&self) -> &dyn ToSql {
+        match &self.value {
+            FieldValue::Text(value) => value,
+            FieldValue::Int(value) => value,
+        }
+    }
+
+    fn json_value(&self) -> Value {
+        match &self.value {
+            FieldValue::Text(value) => Value::from(value.as_str()),
+            FieldValue::Int(value) => Value::from(*value),
+        }
+    }
+}
+
+fn parse_field(field: &ChangeableField, value: &Value) -> Result<FieldValue, String> {
+    match field.kind {
+        FieldKind::Code => value
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+            .filter(|value| *value != 0)
+            .map(FieldValue::Int)
+            .ok_or_else(|| "Must be a non-zero number".to_string()),
+        kind => {
+            let text = value.as_str().ok_or_else(|| "Must be a string".to_string())?.trim().to_string();
+            if text.chars().count() > MAX_TEXT_LENGTH {
+                return Err(format!("Maximum {} characters", MAX_TEXT_LENGTH));
+            }
+            if text.is_empty() && !matches!(kind, FieldKind::OptionalText) {
+                return Err("This field is required".to_string());
+            }
+            let checked = match kind {
+                FieldKind::Name => valid_name(&text),
+                FieldKind::Digits => valid_number_card(&text),
+                _ => Ok(()),
+            };
+            checked.map_err(|err| err.message.map(|message| message.to_string()).unwrap_or_else(|| "Invalid value".to_string()))?;
+            Ok(FieldValue::Text(text))
+        }
+    }
+}
+
+pub struct ProfileChangeService;
+
+impl ProfileChangeService {
+
+    /// ✅ Validasi isi perubahan terhadap daftar field yang boleh diubah. Error per field, format sama dengan validasi form
+    pub fn parse_changes(section: &str, changes: &Map<String, Value>) -> Result<Vec<FieldChange>, HashMap<String, String>> {
+        let mut errors: HashMap<String, String> = HashMap::new();
+
+        if !CHANGEABLE_FIELDS.iter().any(|field| field.section == section) {
+            errors.insert("section".to_string(), format!("Unknown section '{}'", section));
+            return Err(errors);
+        }
+        if changes.is_empty() {
+            errors.insert("changes".to_string(), "At least one field is required".to_string());
+            return Err(errors);
+        }
+
+        let mut parsed: Vec<FieldChange> = Vec::new();
+        for (name, value) in changes {
+            match CHANGEABLE_FIELDS.iter().find(|field| field.section == section && field.field == name) {
+                Some(field) => match parse_field(field, value) {
+                    Ok(value) => parsed.push(FieldChange { field, value }),
+                    Err(message) => {
+                        errors.insert(name.clone(), message);
+                    }
+                },
+                None => {
+                    errors.insert(name.clone(), format!("Field cannot be changed in section '{}'", section));
+                }
+            }
+        }
+
+        if errors.is_empty() { Ok(parsed) } else { Err(errors) }
+    }
+
+    fn change_info(row: &Row) -> ProfileChangeInfo {
+        let json = |column: &str| row.get::<&str, _>(column).and_then(|data| serde_json::from_str(data).ok()).unwrap_or(Value::Null);
+        ProfileChangeInfo {
+            change_nid: row.get("ChangeNID").unwrap_or(0),
+            auth_usernid: row.get("AuthUserNID").unwrap_or(0),
+            section: row.get::<&str, _>("Section").unwrap_or_default().to_string(),
+            changes: json("Changes"),
+            previous: json("Previous"),
+            reason: row.get::<&str, _>("Reason").map(|s| s.to_string()),
+            status: row.get::<&str, _>("Status").unwrap_or_default().to_string(),
+            requested_at: row.get::<NaiveDateTime, _>("RequestedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+            reviewed_at: row.get::<NaiveDateTime, _>("ReviewedAt").map(|dt| dt.and_utc()),
+            review_note: row.get::<&str, _>("ReviewNote").map(|s| s.to_string()),
+        }
+    }
+
+    /// 🗂️ Simpan snapshot baris `UserKyc` sebagai versi berikutnya. `baseline` hanya menulis versi 1 jika belum ada versi sama sekali
+    async fn snapshot(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, auto_nid: i32, change_nid: Option<i32>, created_by: Option<i32>, baseline: bool) -> Result<(), String> {
+        let guard = if baseline { "NOT EXISTS (SELECT 1 FROM [dbo].[UserKycVersion] WHERE AutoNID = @P2)" } else { "1 = 1" };
+        conn.execute(
+            format!(r#"IF {}
+            INSERT INTO [dbo].[UserKycVersion] ([AuthUserNID],[AutoNID],[Version],[ChangeNID],[Data],[CreatedBy],[CreatedAt])
+            SELECT @P1, @P2,
+                ISNULL((SELECT MAX([Version]) FROM [dbo].[UserKycVersion] WHERE AutoNID = @P2), 0) + 1,
+                @P3,
+                (SELECT * FROM [dbo].[UserKyc] WHERE AutoNID = @P2 FOR JSON PATH, WITHOUT_ARRAY_WRAPPER, INCLUDE_NULL_VALUES),
+                @P4, @P5"#, guard),
+            &[&auth_usernid, &auto_nid, &change_nid, &created_by, &Utc::now()],
+        ).await.map_err(|e| format!("Failed to insert UserKycVersion: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// ✏️ Nasabah mengajukan perubahan data. Hanya untuk aplikasi yang sudah disetujui dan satu permintaan pending per nasabah
+    pub async fn submit(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, section: &str, changes: Vec<FieldChange>, reason: Option<String>) -> ActionResult<ProfileChangeInfo, String> {
+        let mut result: ActionResult<ProfileChangeInfo, String> = ActionResult::default();
+        let reason = reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+        let now = Utc::now();
+
+        let changes_json: Map<String, Value> = changes.iter().map(|change| (change.field.field.to_string(), change.json_value())).collect();
+        let changes_json = Value::Object(changes_json);
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let info = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            // 🔒 Kunci baris UserKyc supaya tidak ada dua permintaan pending sekaligus
+                            let (auto_nid, is_approved, pending_change) = match conn.query(
+                                r#"SELECT K.AutoNID, K.IsApproved, K.ChangeNID
+                                FROM UserKyc K WITH (UPDLOCK, ROWLOCK)
+                                JOIN AuthUser U ON U.WebCIFNID = K.AutoNID
+                                WHERE U.AuthUserNID = @P1"#,
+                                &[&auth_usernid],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => (
+                                        row.get::<i32, _>("AutoNID").unwrap_or(0),
+                                        row.get::<bool, _>("IsApproved").unwrap_or(false),
+                                        row.get::<i32, _>("ChangeNID").unwrap_or(0),
+                                    ),
+                                    _ => {
+                                        result.message = "No user found".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Query execution failed: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            if !is_approved {
+                                result.message = "Profile changes are only available after the application is approved".to_string();
+                                break 'tx None;
+                            }
+                            if pending_change != 0 {
+                                result.message = "A profile change request is already pending".to_string();
+                                break 'tx None;
+                            }
+
+                            // Nilai lama field yang diubah, untuk ditampilkan berdampingan saat review
+                            let previous_columns = changes.iter()
+                                .map(|change| format!("[{}] AS [{}]", change.field.column, change.field.field))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let previous: String = match conn.query(
+                                format!("SELECT (SELECT {} FROM [dbo].[UserKyc] WHERE AutoNID = @P1 FOR JSON PATH, WITHOUT_ARRAY_WRAPPER, INCLUDE_NULL_VALUES) AS Previous", previous_columns),
+                                &[&auto_nid],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get::<&str, _>("Previous").unwrap_or("{}").to_string(),
+                                    _ => "{}".to_string(),
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Query execution failed: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            let change_nid: i32 = match conn.query(
+                                r#"INSERT INTO [dbo].[KycChangeRequest] ([AuthUserNID],[AutoNID],[Section],[Changes],[Previous],[Reason],[Status],[RequestedAt])
+                                OUTPUT INSERTED.ChangeNID
+                                VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8)"#,
+                                &[&auth_usernid, &auto_nid, &section, &changes_json.to_string(), &previous, &reason, &CHANGE_PENDING, &now],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => row.get("ChangeNID").unwrap_or(0),
+                                    _ => {
+                                        result.error = Some("Failed to insert KycChangeRequest".to_string());
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to insert KycChangeRequest: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[UserKyc] SET [ChangeNID] = @P2 WHERE AutoNID = @P1"#,
+                                &[&auto_nid, &change_nid],
+                            ).await {
+                                result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            Some(ProfileChangeInfo {
+                                change_nid,
+                                auth_usernid,
+                                section: section.to_string(),
+                                changes: changes_json,
+                                previous: serde_json::from_str(&previous).unwrap_or(Value::Null),
+                                reason,
+                                status: CHANGE_PENDING.to_string(),
+                                requested_at: now,
+                                reviewed_at: None,
+                                review_note: None,
+                            })
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                let Some(info) = info else {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                };
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Profile change request submitted, waiting for approval".to_string();
+                result.data = Some(info);
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// 📋 Daftar permintaan perubahan. `auth_usernid` diisi untuk riwayat milik satu nasabah (semua status),
+    /// kosong untuk antrian admin (default `pending`)
+    pub async fn list(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: Option<i32>, status: Option<String>) -> ActionResult<Vec<ProfileChangeInfo>, String> {
+        let mut result: ActionResult<Vec<ProfileChangeInfo>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let query = match auth_usernid {
+            Some(auth_usernid) => conn.query(
+                format!("SELECT {} FROM KycChangeRequest WHERE AuthUserNID = @P1 ORDER BY RequestedAt DESC", CHANGE_COLUMNS),
+                &[&auth_usernid],
+            ).await,
+            None => conn.query(
+                format!("SELECT {} FROM KycChangeRequest WHERE Status = @P1 ORDER BY RequestedAt", CHANGE_COLUMNS),
+                &[&status.unwrap_or_else(|| CHANGE_PENDING.to_string())],
+            ).await,
+        };
+        let mut rows = match query {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut requests: Vec<ProfileChangeInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        requests.push(Self::change_info(row));
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} profile change request(s)", requests.len());
+        result.data = Some(requests);
+        result
+    }
+
+    /// ↩️ Nasabah membatalkan permintaan yang masih pending
+    pub async fn cancel(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let (change_nid, auto_nid): (i32, i32) = match conn.query(
+                                r#"UPDATE [dbo].[KycChangeRequest] SET [Status] = @P2
+                                OUTPUT INSERTED.ChangeNID, INSERTED.AutoNID
+                                WHERE AuthUserNID = @P1 AND Status = @P3"#,
+                                &[&auth_usernid, &CHANGE_CANCELLED, &CHANGE_PENDING],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => (row.get("ChangeNID").unwrap_or(0), row.get("AutoNID").unwrap_or(0)),
+                                    _ => {
+                                        result.message = "No pending profile change request".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update KycChangeRequest: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[UserKyc] SET [ChangeNID] = 0 WHERE AutoNID = @P1 AND ChangeNID = @P2"#,
+                                &[&auto_nid, &change_nid],
+                            ).await {
+                                result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Profile change request cancelled".to_string();
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// ✅ Admin menyetujui: perubahan ditulis ke `UserKyc`, versi lama & baru disimpan, semuanya dalam satu transaksi.
+    /// `PendingCIFNID` diisi supaya perubahan ikut dikirim ke CIF
+    pub async fn approve(connection: web::Data<Pool<ConnectionManager>>, change_nid: i32, reviewed_by: i32, note: Option<String>) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+        let now = Utc::now();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let (auth_usernid, auto_nid, section, changes): (i32, i32, String, String) = match conn.query(
+                                r#"UPDATE [dbo].[KycChangeRequest] SET [Status] = @P2, [ReviewedBy] = @P3, [ReviewedAt] = @P4, [ReviewNote] = @P5
+                                OUTPUT INSERTED.AuthUserNID, INSERTED.AutoNID, INSERTED.Section, INSERTED.Changes
+                                WHERE ChangeNID = @P1 AND Status = @P6"#,
+                                &[&change_nid, &CHANGE_APPROVED, &reviewed_by, &now, &note, &CHANGE_PENDING],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => (
+                                        row.get("AuthUserNID").unwrap_or(0),
+                                        row.get("AutoNID").unwrap_or(0),
+                                        row.get::<&str, _>("Section").unwrap_or_default().to_string(),
+                                        row.get::<&str, _>("Changes").unwrap_or("{}").to_string(),
+                                    ),
+                                    _ => {
+                                        result.message = "No pending profile change request".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update KycChangeRequest: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            // Isi sudah divalidasi saat diajukan, parse ulang hanya untuk memetakan field ke kolom
+                            let stored: Map<String, Value> = serde_json::from_str(&changes).unwrap_or_default();
+                            let changes = match Self::parse_changes(&section, &stored) {
+                                Ok(changes) => changes,
+                                Err(errors) => {
+                                    result.error = Some(format!("Stored profile change {} is invalid: {:?}", change_nid, errors));
+                                    break 'tx None;
+                                }
+                            };
+
+                            // 🗂️ Versi awal disimpan sekali, sebelum perubahan pertama
+                            if let Err(err) = Self::snapshot(conn, auth_usernid, auto_nid, None, None, true).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            let assignments = changes.iter().enumerate()
+                                .map(|(index, change)| format!("[{}] = @P{}", change.field.column, index + 4))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let mut params: Vec<&dyn ToSql> = vec![&auto_nid, &change_nid, &now];
+                            params.extend(changes.iter().map(FieldChange::sql_value));
+
+                            match conn.execute(
+                                format!(r#"UPDATE [dbo].[UserKyc] SET {}, [ChangeNID] = 0, [PendingCIFNID] = @P2, [LastUpdate] = @P3
+                                WHERE AutoNID = @P1 AND ChangeNID = @P2"#, assignments),
+                                &params,
+                            ).await.map(|done| done.total()) {
+                                Ok(0) => {
+                                    result.error = Some(format!("UserKyc {} is not waiting for profile change {}", auto_nid, change_nid));
+                                    break 'tx None;
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+
+                            if let Err(err) = Self::snapshot(conn, auth_usernid, auto_nid, Some(change_nid), Some(reviewed_by), false).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            let fields: Vec<&str> = changes.iter().map(|change| change.field.field).collect();
+                            let detail = serde_json::json!({ "change_nid": change_nid, "section": section, "fields": fields });
+                            if let Err(err) = AuditService::record(conn, AUDIT_PROFILE_CHANGE_APPROVED, Some(reviewed_by), Some(auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Profile change approved and applied".to_string();
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// ❌ Admin menolak, data `UserKyc` tidak berubah
+    pub async fn reject(connection: web::Data<Pool<ConnectionManager>>, change_nid: i32, reviewed_by: i32, note: Option<String>) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let (auth_usernid, auto_nid): (i32, i32) = match conn.query(
+                                r#"UPDATE [dbo].[KycChangeRequest] SET [Status] = @P2, [ReviewedBy] = @P3, [ReviewedAt] = @P4, [ReviewNote] = @P5
+                                OUTPUT INSERTED.AuthUserNID, INSERTED.AutoNID
+                                WHERE ChangeNID = @P1 AND Status = @P6"#,
+                                &[&change_nid, &CHANGE_REJECTED, &reviewed_by, &Utc::now(), &note, &CHANGE_PENDING],
+                            ).await {
+                                Ok(rows) => match rows.into_row().await {
+                                    Ok(Some(row)) => (row.get("AuthUserNID").unwrap_or(0), row.get("AutoNID").unwrap_or(0)),
+                                    _ => {
+                                        result.message = "No pending profile change request".to_string();
+                                        break 'tx None;
+                                    }
+                                },
+                                Err(err) => {
+                                    result.error = Some(format!("Failed to update KycChangeRequest: {:?}", err));
+                                    break 'tx None;
+                                }
+                            };
+
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[UserKyc] SET [ChangeNID] = 0 WHERE AutoNID = @P1 AND ChangeNID = @P2"#,
+                                &[&auto_nid, &change_nid],
+                            ).await {
+                                result.error = Some(format!("Failed to update UserKyc: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            let detail = serde_json::json!({ "change_nid": change_nid });
+                            if let Err(err) = AuditService::record(conn, AUDIT_PROFILE_CHANGE_REJECTED, Some(reviewed_by), Some(auth_usernid), &detail).await {
+                                result.error = Some(err);
+                                break 'tx None;
+                            }
+
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Profile change request rejected".to_string();
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+            }
+        }
+
+        result
+    }
+
+    /// 🗂️ Semua versi data KYC satu nasabah, terbaru di atas
+    pub async fn versions(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> ActionResult<Vec<KycVersionInfo>, String> {
+        let mut result: ActionResult<Vec<KycVersionInfo>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            r#"SELECT [Version], ChangeNID, Data, CreatedBy, CreatedAt FROM UserKycVersion
+            WHERE AuthUserNID = @P1 ORDER BY [Version] DESC"#,
+            &[&auth_usernid],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut versions: Vec<KycVersionInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        versions.push(KycVersionInfo {
+                            version: row.get("Version").unwrap_or(0),
+                            change_nid: row.get("ChangeNID"),
+                            data: row.get::<&str, _>("Data").and_then(|data| serde_json::from_str(data).ok()).unwrap_or(Value::Null),
+                            created_by: row.get("CreatedBy"),
+                            created_at: row.get::<NaiveDateTime, _>("CreatedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                        });
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} version(s)", versions.len());
+        result.data = Some(versions);
+        result
+    }
+}
@@ -0,0 +1,221 @@
+use std::{collections::HashMap, env, sync::RwLock};
+use actix_web::web;
+use bb8::Pool;
+use bb8_tiberius::ConnectionManager;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use tokio_stream::StreamExt;
+
+use crate::contexts::{jwt_session::{access_token_ttl, Claims}, logger::write_log};
+
+/// 🗂️ Cache revocation di memori, sumber kebenarannya tetap tabel `AuthTokenRevocation`
+#[derive(Default)]
+struct RevocationState {
+    /// jti → exp (unix timestamp)
+    tokens: HashMap<String, i64>,
+    /// AuthUserNID → (revoked_before dalam milidetik, expires_at dalam unix timestamp)
+    users: HashMap<i32, (i64, i64)>,
+    /// SessionID (claim `sid`) → exp (unix timestamp)
+    sessions: HashMap<String, i64>,
+}
+
+/// Jendela tumpang-tindih antar sinkron, lihat [`RevocationService::start`]
+const SYNC_OVERLAP_SECONDS: i64 = 30;
+
+static STORE: Lazy<RwLock<RevocationState>> = Lazy::new(|| RwLock::new(RevocationState::default()));
+
+pub struct RevocationService;
+
+impl RevocationService {
+
+    /// Cek apakah token sudah dicabut (dipanggil oleh `validate_jwt`)
+    pub fn is_revoked(claims: &Claims) -> bool {
+        let store = match STORE.read() {
+            Ok(store) => store,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if store.tokens.contains_key(&claims.jti) {
+            return true;
+        }
+
+        if claims.sid.as_ref().is_some_and(|sid| store.sessions.contains_key(sid)) {
+            return true;
+        }
+
+        // Token lama tanpa `iat_ms` dianggap terbit di awal detiknya: yang terbit di detik yang sama dengan pencabutan ikut dicabut
+        let issued_ms = if claims.iat_ms > 0 { claims.iat_ms } else { claims.iat as i64 * 1000 };
+        match store.users.get(&claims.auth_usernid) {
+            Some((revoked_before, _)) => issued_ms <= *revoked_before,
+            None => false,
+        }
+    }
+
+    /// 🚫 Cabut satu token (logout)
+    pub async fn revoke_token(connection: web::Data<Pool<ConnectionManager>>, claims: &Claims, reason: &str) -> Result<(), String> {
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+        Self::revoke_jti(connection, &claims.jti, claims.auth_usernid, expires_at, reason).await
+    }
+
+    /// 🚫 Cabut token berdasarkan `jti` tanpa memegang token-nya (misalnya mengakhiri impersonation)
+    pub async fn revoke_jti(connection: web::Data<Pool<ConnectionManager>>, jti: &str, auth_usernid: i32, expires_at: DateTime<Utc>, reason: &str) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthTokenRevocation] ([Jti],[AuthUserNID],[RevokedBefore],[ExpiresAt],[RevokedAt],[Reason])
+            VALUES (@P1,@P2,NULL,@P3,@P4,@P5)"#,
+            &[&jti, &auth_usernid, &expires_at, &Utc::now(), &reason],
+        ).await.map_err(|e| format!("Failed to insert AuthTokenRevocation: {:?}", e))?;
+
+        if let Ok(mut store) = STORE.write() {
+            store.tokens.insert(jti.to_string(), expires_at.timestamp());
+        }
+        Ok(())
+    }
+
+    /// 🚫 Cabut semua token milik user yang terbit sebelum sekarang (ganti password, force sign-out)
+    pub async fn revoke_user(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, reason: &str) -> Result<(), String> {
+        let now = Utc::now();
+        let expires_at = now + access_token_ttl();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthTokenRevocation] ([Jti],[AuthUserNID],[RevokedBefore],[ExpiresAt],[RevokedAt],[Reason])
+            VALUES (NULL,@P1,@P2,@P3,@P2,@P4)"#,
+            &[&auth_usernid, &now, &expires_at, &reason],
+        ).await.map_err(|e| format!("Failed to insert AuthTokenRevocation: {:?}", e))?;
+
+        if let Ok(mut store) = STORE.write() {
+            store.users.insert(auth_usernid, (now.timestamp_millis(), expires_at.timestamp()));
+        }
+        Ok(())
+    }
+
+    /// 🚫 Cabut semua access token milik satu sesi (revoke device)
+    pub async fn revoke_session(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, session_id: &str, reason: &str) -> Result<(), String> {
+        let now = Utc::now();
+        let expires_at = now + access_token_ttl();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthTokenRevocation] ([Jti],[AuthUserNID],[RevokedBefore],[ExpiresAt],[RevokedAt],[Reason],[SessionID])
+            VALUES (NULL,@P1,NULL,@P2,@P3,@P4,@P5)"#,
+            &[&auth_usernid, &expires_at, &now, &reason, &session_id],
+        ).await.map_err(|e| format!("Failed to insert AuthTokenRevocation: {:?}", e))?;
+
+        if let Ok(mut store) = STORE.write() {
+            store.sessions.insert(session_id.to_string(), expires_at.timestamp());
+        }
+        Ok(())
+    }
+
+    /// 🔄 Muat ulang cache dari database (sinkron antar instance)
+    pub async fn reload(connection: &Pool<ConnectionManager>) -> Result<(), String> {
+        let mut state = RevocationState::default();
+        Self::load_into(connection, &mut state, None).await?;
+
+        if let Ok(mut store) = STORE.write() {
+            *store = state;
+        }
+        Ok(())
+    }
+
+    /// 🔄 Ambil pencabutan baru dari instance lain sejak `since`, digabung ke cache yang ada
+    pub async fn sync(connection: &Pool<ConnectionManager>, since: DateTime<Utc>) -> Result<(), String> {
+        let mut state = RevocationState::default();
+        Self::load_into(connection, &mut state, Some(since)).await?;
+
+        if let Ok(mut store) = STORE.write() {
+            store.tokens.extend(state.tokens);
+            store.sessions.extend(state.sessions);
+            for (auth_usernid, (revoked_before, expires_at)) in state.users {
+                let entry = store.users.entry(auth_usernid).or_insert((revoked_before, expires_at));
+                if revoked_before > entry.0 {
+                    *entry = (revoked_before, expires_at);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Baca `AuthTokenRevocation` yang belum kedaluwarsa (opsional hanya yang dicabut sejak `since`) ke `state`
+    async fn load_into(connection: &Pool<ConnectionManager>, state: &mut RevocationState, since: Option<DateTime<Utc>>) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let since = since.map(|since| since.naive_utc()).unwrap_or_default();
+        let mut rows = conn.query(
+            r#"SELECT Jti, AuthUserNID, RevokedBefore, ExpiresAt, SessionID FROM AuthTokenRevocation WHERE ExpiresAt > @P1 AND RevokedAt >= @P2"#,
+            &[&Utc::now(), &since],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        while let Some(item) = rows.try_next().await.map_err(|e| format!("Query execution failed: {:?}", e))? {
+            if let Some(row) = item.as_row() {
+                let expires_at = row.get::<NaiveDateTime, _>("ExpiresAt").map(|dt| dt.and_utc().timestamp()).unwrap_or(0);
+
+                if let Some(jti) = row.get::<&str, _>("Jti") {
+                    state.tokens.insert(jti.to_string(), expires_at);
+                } else if let Some(session_id) = row.get::<&str, _>("SessionID") {
+                    state.sessions.insert(session_id.to_string(), expires_at);
+                } else if let Some(revoked_before) = row.get::<NaiveDateTime, _>("RevokedBefore") {
+                    let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+                    let revoked_before = revoked_before.and_utc().timestamp_millis();
+                    let entry = state.users.entry(auth_usernid).or_insert((revoked_before, expires_at));
+                    if revoked_before > entry.0 {
+                        *entry = (revoked_before, expires_at);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 🧹 Hapus entri yang sudah kedaluwarsa (token aslinya juga sudah tidak valid)
+    pub async fn cleanup(connection: &Pool<ConnectionManager>) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"DELETE FROM [dbo].[AuthTokenRevocation] WHERE ExpiresAt <= @P1"#,
+            &[&Utc::now()],
+        ).await.map_err(|e| format!("Failed to cleanup AuthTokenRevocation: {:?}", e))?;
+
+        let now = Utc::now().timestamp();
+        if let Ok(mut store) = STORE.write() {
+            store.tokens.retain(|_, exp| *exp > now);
+            store.users.retain(|_, (_, exp)| *exp > now);
+            store.sessions.retain(|_, exp| *exp > now);
+        }
+        Ok(())
+    }
+
+    /// ⏱️ Jalankan background job: load awal, lalu cleanup + reload penuh berkala (`REVOCATION_CLEANUP_SECONDS`)
+    /// dan sinkron pencabutan baru dari instance lain setiap `REVOCATION_SYNC_SECONDS`
+    pub fn start(connection: Pool<ConnectionManager>) {
+        let interval_secs: u64 = env::var("REVOCATION_CLEANUP_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(300);
+        let sync_secs: u64 = env::var("REVOCATION_SYNC_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(5).max(1);
+
+        let cleanup_connection = connection.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(err) = Self::cleanup(&cleanup_connection).await {
+                    write_log("ERROR", err.as_str());
+                }
+                if let Err(err) = Self::reload(&cleanup_connection).await {
+                    write_log("ERROR", err.as_str());
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(sync_secs));
+            let mut since = Utc::now();
+            loop {
+                interval.tick().await;
+                // Mundur sedikit: transaksi yang commit terlambat & selisih jam antar instance tetap terbaca
+                let next = Utc::now() - Duration::seconds(SYNC_OVERLAP_SECONDS);
+                match Self::sync(&connection, since).await {
+                    Ok(()) => since = next,
+                    Err(err) => write_log("ERROR", err.as_str()),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,122 @@
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use tokio_stream::StreamExt;
+
+use crate::contexts::{
+    connection::Transaction,
+    logger::write_log,
+    model::ActionResult,
+    rbac::{ROLE_ADMINISTRATOR, ROLE_APPLICANT, ROLE_REVIEWER, ROLE_SALES},
+};
+use super::revocation_service::RevocationService;
+
+pub struct RoleService;
+
+impl RoleService {
+
+    /// 🛡️ Ambil role & permission milik user. User tanpa role dianggap `applicant`.
+    pub async fn get_user_access(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut rows = conn.query(
+            r#"SELECT R.RoleCode, P.Permission
+            FROM AuthUserRole U
+            JOIN AuthRole R ON R.RoleNID = U.RoleNID
+            LEFT JOIN AuthRolePermission P ON P.RoleNID = R.RoleNID
+            WHERE U.AuthUserNID = @P1"#, &[&auth_usernid]).await
+            .map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        let mut roles: Vec<String> = Vec::new();
+        let mut permissions: Vec<String> = Vec::new();
+
+        while let Some(item) = rows.try_next().await.map_err(|e| format!("Query execution failed: {:?}", e))? {
+            if let Some(row) = item.as_row() {
+                if let Some(role) = row.get::<&str, _>("RoleCode") {
+                    if !roles.iter().any(|r| r == role) {
+                        roles.push(role.to_string());
+                    }
+                }
+                if let Some(permission) = row.get::<&str, _>("Permission") {
+                    if !permissions.iter().any(|p| p == permission) {
+                        permissions.push(permission.to_string());
+                    }
+                }
+            }
+        }
+
+        if roles.is_empty() {
+            roles.push(ROLE_APPLICANT.to_string());
+        }
+
+        Ok((roles, permissions))
+    }
+
+    /// Ganti seluruh role user. Token lama dicabut supaya claims ikut diperbarui saat refresh.
+    pub async fn assign_roles(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, roles: Vec<String>, assigned_by: i32) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        let known_roles = [ROLE_APPLICANT, ROLE_SALES, ROLE_REVIEWER, ROLE_ADMINISTRATOR];
+        if let Some(role) = roles.iter().find(|r| !known_roles.contains(&r.as_str())) {
+            result.message = format!("Unknown role '{}'", role);
+            return result;
+        }
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            if let Err(err) = conn.execute(
+                                r#"DELETE FROM [dbo].[AuthUserRole] WHERE AuthUserNID = @P1"#, &[&auth_usernid],
+                            ).await {
+                                result.error = Some(format!("Failed to delete AuthUserRole: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            for role in roles.iter() {
+                                if let Err(err) = conn.execute(
+                                    r#"INSERT INTO [dbo].[AuthUserRole] ([AuthUserNID],[RoleNID],[AssignedAt],[AssignedBy])
+                                    SELECT @P1, RoleNID, @P2, @P3 FROM AuthRole WHERE RoleCode = @P4"#,
+                                    &[&auth_usernid, &chrono::Utc::now(), &assigned_by, role],
+                                ).await {
+                                    result.error = Some(format!("Failed to insert AuthUserRole: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        }
+
+        if let Err(err) = RevocationService::revoke_user(connection, auth_usernid, "role_change").await {
+            write_log("ERROR", err.as_str());
+        }
+
+        result.result = true;
+        result.message = "Roles updated successfully".to_string();
+        result
+    }
+}
@@ -0,0 +1,156 @@
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use tokio_stream::StreamExt;
+
+use crate::contexts::model::{ActionResult, SessionInfo};
+use super::{generic_service::GenericService, revocation_service::RevocationService, token_service::TokenService};
+
+/// User-Agent disimpan maksimal sepanjang kolom `AuthSession.UserAgent`
+const USER_AGENT_MAX_LENGTH: usize = 512;
+
+pub struct SessionService;
+
+impl SessionService {
+
+    /// 🆕 Catat sesi login baru lalu terbitkan refresh token pertamanya. Return (session_id, refresh_token)
+    pub async fn start(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, mfa: bool, ip_address: &str, user_agent: &str) -> Result<(String, String), String> {
+        let session_id = GenericService::random_string(32);
+        let user_agent: String = user_agent.chars().take(USER_AGENT_MAX_LENGTH).collect();
+        let ip_address = GenericService::ip_address_column(ip_address);
+        let now = Utc::now();
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthSession] ([SessionID],[AuthUserNID],[IpAddress],[UserAgent],[CreatedAt],[LastSeenAt])
+            VALUES (@P1,@P2,@P3,@P4,@P5,@P5)"#,
+            &[&session_id, &auth_usernid, &ip_address, &user_agent, &now],
+        ).await.map_err(|e| format!("Failed to insert AuthSession: {:?}", e))?;
+        drop(conn);
+
+        // Family refresh token = sesi, jadi revoke sesi cukup revoke family-nya
+        let refresh_token = TokenService::issue_refresh_token(connection, auth_usernid, Some(session_id.clone()), None, mfa, &ip_address).await?;
+        Ok((session_id, refresh_token))
+    }
+
+    /// 👀 Perbarui last-seen (dipanggil setiap refresh token dirotasi)
+    pub async fn touch(conn: &mut PooledConnection<'_, ConnectionManager>, session_id: &str, ip_address: &str) -> Result<(), String> {
+        conn.execute(
+            r#"UPDATE [dbo].[AuthSession] SET [LastSeenAt] = @P1, [IpAddress] = @P2 WHERE SessionID = @P3 AND RevokedAt IS NULL"#,
+            &[&Utc::now(), &GenericService::ip_address_column(ip_address), &session_id],
+        ).await.map_err(|e| format!("Failed to update AuthSession: {:?}", e))?;
+        Ok(())
+    }
+
+    /// 📋 Sesi aktif user: belum dicabut dan masih punya refresh token yang bisa dipakai
+    pub async fn list(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, current_session: Option<&str>) -> ActionResult<Vec<SessionInfo>, String> {
+        let mut result: ActionResult<Vec<SessionInfo>, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut rows = match conn.query(
+            r#"SELECT S.SessionID, S.IpAddress, S.UserAgent, S.CreatedAt, S.LastSeenAt FROM AuthSession S
+            WHERE S.AuthUserNID = @P1 AND S.RevokedAt IS NULL
+            AND EXISTS (SELECT 1 FROM AuthRefreshToken T WHERE T.FamilyID = S.SessionID AND T.UsedAt IS NULL AND T.RevokedAt IS NULL AND T.ExpiresAt > @P2)
+            ORDER BY S.LastSeenAt DESC"#,
+            &[&auth_usernid, &Utc::now()],
+        ).await {
+            Ok(rows) => rows,
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let mut sessions: Vec<SessionInfo> = Vec::new();
+        loop {
+            match rows.try_next().await {
+                Ok(Some(item)) => {
+                    if let Some(row) = item.as_row() {
+                        let session_id = row.get::<&str, _>("SessionID").unwrap_or_default().to_string();
+                        sessions.push(SessionInfo {
+                            current: current_session == Some(session_id.as_str()),
+                            session_id,
+                            ip_address: row.get::<&str, _>("IpAddress").unwrap_or_default().to_string(),
+                            user_agent: row.get::<&str, _>("UserAgent").unwrap_or_default().to_string(),
+                            created_at: row.get::<NaiveDateTime, _>("CreatedAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                            last_seen_at: row.get::<NaiveDateTime, _>("LastSeenAt").map(|dt| dt.and_utc()).unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap()),
+                        });
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    result.error = format!("Query execution failed: {:?}", err).into();
+                    return result;
+                }
+            }
+        }
+
+        result.result = true;
+        result.message = format!("{} active session(s)", sessions.len());
+        result.data = Some(sessions);
+        result
+    }
+
+    /// 🚫 Cabut satu sesi milik user: refresh token family + semua access token dengan `sid` tersebut
+    pub async fn revoke(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, session_id: &str, reason: &str) -> ActionResult<(), String> {
+        let mut result: ActionResult<(), String> = ActionResult::default();
+
+        let updated = match connection.get().await {
+            Ok(mut conn) => match conn.execute(
+                r#"UPDATE [dbo].[AuthSession] SET [RevokedAt] = @P1, [RevokedReason] = @P2
+                WHERE SessionID = @P3 AND AuthUserNID = @P4 AND RevokedAt IS NULL"#,
+                &[&Utc::now(), &reason, &session_id, &auth_usernid],
+            ).await {
+                Ok(done) => done.total(),
+                Err(err) => {
+                    result.error = format!("Failed to update AuthSession: {:?}", err).into();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        if updated == 0 {
+            result.message = "Session not found".to_string();
+            return result;
+        }
+
+        if let Err(err) = TokenService::revoke_family(connection.clone(), session_id).await {
+            result.error = Some(err);
+            return result;
+        }
+
+        if let Err(err) = RevocationService::revoke_session(connection, auth_usernid, session_id, reason).await {
+            result.error = Some(err);
+            return result;
+        }
+
+        result.result = true;
+        result.message = "Session revoked".to_string();
+        result
+    }
+
+    /// 🚫 Cabut semua sesi user (revoke all, ganti password, force sign-out admin)
+    pub async fn revoke_all(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, reason: &str) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"UPDATE [dbo].[AuthSession] SET [RevokedAt] = @P1, [RevokedReason] = @P2 WHERE AuthUserNID = @P3 AND RevokedAt IS NULL"#,
+            &[&Utc::now(), &reason, &auth_usernid],
+        ).await.map_err(|e| format!("Failed to update AuthSession: {:?}", e))?;
+        drop(conn);
+
+        RevocationService::revoke_user(connection.clone(), auth_usernid, reason).await?;
+        TokenService::revoke_user(connection, auth_usernid).await
+    }
+}
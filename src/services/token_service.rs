@@ -0,0 +1,255 @@
+use std::env;
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use chrono::{Duration, NaiveDateTime, Utc};
+
+use crate::contexts::{
+    connection::Transaction,
+    crypto::hash_token,
+    logger::write_log,
+    model::{ActionResult, WebUser},
+};
+use super::{auth_service::AuthService, generic_service::GenericService, session_service::SessionService};
+
+pub struct TokenService;
+
+/// Hasil rotasi refresh token: user pemilik token + refresh token baru
+#[derive(Debug, Clone)]
+pub struct RotatedToken {
+    pub user: WebUser,
+    pub refresh_token: String,
+}
+
+impl TokenService {
+
+    /// Masa berlaku refresh token (default 30 hari)
+    pub fn refresh_token_ttl() -> Duration {
+        let days: i64 = env::var("JWT_REFRESH_TTL_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        Duration::days(days)
+    }
+
+    /// 🎟️ Buat refresh token baru. `family_id` kosong berarti login baru (family baru).
+    /// `mfa` menandai sesi yang sudah lolos 2FA supaya access token hasil refresh tetap membawa claim `mfa`
+    pub async fn issue_refresh_token(
+        connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, family_id: Option<String>, parent_token_nid: Option<i64>, mfa: bool, ip_address: &str
+    ) -> Result<String, String> {
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        Self::insert_refresh_token(&mut conn, auth_usernid, family_id, parent_token_nid, mfa, ip_address).await
+    }
+
+    /// Insert refresh token baru lewat koneksi yang sudah ada (bisa di dalam transaksi rotasi)
+    async fn insert_refresh_token(
+        conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, family_id: Option<String>, parent_token_nid: Option<i64>, mfa: bool, ip_address: &str
+    ) -> Result<String, String> {
+
+        let token = GenericService::random_string(64);
+        let family_id = family_id.unwrap_or_else(|| GenericService::random_string(32));
+        let ip_address = GenericService::ip_address_column(ip_address);
+        let now = Utc::now();
+
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthRefreshToken]
+            ([AuthUserNID],[FamilyID],[ParentTokenNID],[TokenHash],[IssuedAt],[ExpiresAt],[IpAddress],[Mfa])
+            VALUES (@P1,@P2,@P3,@P4,@P5,@P6,@P7,@P8)"#,
+            &[&auth_usernid, &family_id, &parent_token_nid, &hash_token(&token), &now, &(now + Self::refresh_token_ttl()), &ip_address, &mfa],
+        ).await.map_err(|e| format!("Failed to insert AuthRefreshToken: {:?}", e))?;
+
+        Ok(token)
+    }
+
+    /// 🔁 Tukar refresh token lama dengan yang baru (rotasi).
+    /// Token yang sudah pernah dipakai / dicabut dianggap reuse → seluruh family dicabut.
+    pub async fn rotate_refresh_token(connection: web::Data<Pool<ConnectionManager>>, refresh_token: String, ip_address: String) -> ActionResult<RotatedToken, String> {
+
+        let mut result: ActionResult<RotatedToken, String> = ActionResult::default();
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let row = match conn.query(
+            r#"SELECT TokenNID, AuthUserNID, FamilyID, ExpiresAt, UsedAt, RevokedAt, Mfa
+            FROM AuthRefreshToken WHERE TokenHash = @P1"#, &[&hash_token(&refresh_token)]).await {
+            Ok(rows) => match rows.into_row().await {
+                Ok(Some(row)) => row,
+                _ => {
+                    result.message = "Invalid refresh token".to_string();
+                    return result;
+                }
+            },
+            Err(err) => {
+                result.error = format!("Query execution failed: {:?}", err).into();
+                return result;
+            }
+        };
+
+        let token_nid: i64 = row.get("TokenNID").unwrap_or(0);
+        let auth_usernid: i32 = row.get("AuthUserNID").unwrap_or(0);
+        let family_id: String = row.get::<&str, _>("FamilyID").unwrap_or_default().to_string();
+        let expires_at = row.get::<NaiveDateTime, _>("ExpiresAt").map(|dt| dt.and_utc());
+        let mfa: bool = row.get("Mfa").unwrap_or(false);
+        let already_used = row.get::<NaiveDateTime, _>("UsedAt").is_some() || row.get::<NaiveDateTime, _>("RevokedAt").is_some();
+
+        if already_used {
+            write_log("WARN", format!("Refresh token reuse detected for user {} (family {})", auth_usernid, family_id).as_str());
+            if let Err(err) = Self::revoke_family_on(&mut conn, &family_id).await {
+                result.error = Some(err);
+                return result;
+            }
+            result.message = "Refresh token reuse detected, please login again".to_string();
+            return result;
+        }
+
+        if expires_at.is_none_or(|exp| exp < Utc::now()) {
+            result.message = "Refresh token expired".to_string();
+            return result;
+        }
+
+        let mut user = match AuthService::get_web_user(&mut conn, auth_usernid).await {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                result.message = "No user found for refresh token".to_string();
+                return result;
+            }
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        // 🚫 Akun yang dinonaktifkan (mis. ditutup) tidak boleh memperpanjang sesi
+        if user.disabled_login {
+            if let Err(err) = Self::revoke_family_on(&mut conn, &family_id).await {
+                result.error = Some(err);
+                return result;
+            }
+            result.message = "Account is disabled".to_string();
+            return result;
+        }
+
+        // Status 2FA dari login awal tetap berlaku selama family yang sama
+        user.mfa = mfa;
+        user.session_id = Some(family_id.clone());
+
+        drop(conn);
+
+        let trans = match Transaction::begin(&connection).await {
+            Ok(trans) => trans,
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        };
+
+        // Tandai terpakai + token baru di satu transaksi: token lama tidak bisa hangus tanpa penggantinya
+        let rotated: Option<Option<String>> = 'tx: {
+            match trans.conn.lock().await.as_mut() {
+                Some(conn) => {
+                    // Tandai terpakai secara atomik supaya request paralel tidak bisa rotasi dua kali
+                    let updated = match conn.execute(
+                        r#"UPDATE [dbo].[AuthRefreshToken] SET [UsedAt] = @P1
+                        WHERE TokenNID = @P2 AND UsedAt IS NULL AND RevokedAt IS NULL"#,
+                        &[&Utc::now(), &token_nid],
+                    ).await {
+                        Ok(done) => done.total(),
+                        Err(err) => {
+                            result.error = format!("Failed to update AuthRefreshToken: {:?}", err).into();
+                            break 'tx None;
+                        }
+                    };
+
+                    // Kalah balapan dengan request lain → reuse, pencabutan family ikut di-commit
+                    if updated != 1 {
+                        if let Err(err) = Self::revoke_family_on(conn, &family_id).await {
+                            result.error = Some(err);
+                            break 'tx None;
+                        }
+                        break 'tx Some(None);
+                    }
+
+                    if let Err(err) = SessionService::touch(conn, &family_id, &ip_address).await {
+                        write_log("ERROR", err.as_str());
+                    }
+
+                    match Self::insert_refresh_token(conn, auth_usernid, Some(family_id.clone()), Some(token_nid), mfa, &ip_address).await {
+                        Ok(new_token) => Some(Some(new_token)),
+                        Err(err) => {
+                            result.error = Some(err);
+                            None
+                        }
+                    }
+                }
+                None => {
+                    result.error = Some("Failed to get database connection".into());
+                    None
+                }
+            }
+        };
+
+        // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+        let Some(rotated) = rotated else {
+            if let Err(err) = trans.rollback().await {
+                write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+            }
+            return result;
+        };
+
+        if let Err(err) = trans.commit().await {
+            result.error = Some(format!("Failed to commit transaction: {:?}", err));
+            return result;
+        }
+
+        match rotated {
+            Some(new_token) => {
+                result.result = true;
+                result.message = "Refresh token success".to_string();
+                result.data = Some(RotatedToken { user, refresh_token: new_token });
+            }
+            None => result.message = "Refresh token reuse detected, please login again".to_string(),
+        }
+
+        result
+    }
+
+    /// 🚫 Cabut semua refresh token dalam satu family
+    pub async fn revoke_family(connection: web::Data<Pool<ConnectionManager>>, family_id: &str) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        Self::revoke_family_on(&mut conn, family_id).await
+    }
+
+    /// Sama dengan `revoke_family`, lewat koneksi yang sedang dipegang (tanpa ambil koneksi kedua dari pool)
+    async fn revoke_family_on(conn: &mut PooledConnection<'_, ConnectionManager>, family_id: &str) -> Result<(), String> {
+        conn.execute(
+            r#"UPDATE [dbo].[AuthRefreshToken] SET [RevokedAt] = @P1 WHERE FamilyID = @P2 AND RevokedAt IS NULL"#,
+            &[&Utc::now(), &family_id],
+        ).await.map_err(|e| format!("Failed to revoke refresh token family: {:?}", e))?;
+        Ok(())
+    }
+
+    /// 🚫 Cabut family milik refresh token yang dikirim client (dipakai saat logout)
+    pub async fn revoke_by_token(connection: web::Data<Pool<ConnectionManager>>, refresh_token: &str) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"UPDATE [dbo].[AuthRefreshToken] SET [RevokedAt] = @P1
+            WHERE RevokedAt IS NULL AND FamilyID = (SELECT FamilyID FROM AuthRefreshToken WHERE TokenHash = @P2)"#,
+            &[&Utc::now(), &hash_token(refresh_token)],
+        ).await.map_err(|e| format!("Failed to revoke refresh token: {:?}", e))?;
+        Ok(())
+    }
+
+    /// 🚫 Cabut semua refresh token milik user (ganti password, force sign-out)
+    pub async fn revoke_user(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> Result<(), String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"UPDATE [dbo].[AuthRefreshToken] SET [RevokedAt] = @P1 WHERE AuthUserNID = @P2 AND RevokedAt IS NULL"#,
+            &[&Utc::now(), &auth_usernid],
+        ).await.map_err(|e| format!("Failed to revoke refresh tokens: {:?}", e))?;
+        Ok(())
+    }
+}
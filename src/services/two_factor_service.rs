@@ -0,0 +1,388 @@
+use actix_web::web;
+use bb8::{Pool, PooledConnection};
+use bb8_tiberius::ConnectionManager;
+use std::env;
+use chrono::{DateTime, Utc};
+
+use crate::contexts::{
+    connection::Transaction,
+    crypto::{hash_token, needs_reseal, open_text, seal_text},
+    jwt_session::{create_mfa_token, mfa_challenge_ttl, Claims, MfaChallengeClaims},
+    logger::write_log,
+    model::{ActionResult, MfaChallenge, TotpEnrollment, WebUser},
+    totp::{generate_secret, provisioning_uri, qr_code, verify_code},
+};
+use super::{auth_service::AuthService, generic_service::GenericService};
+
+/// Jumlah recovery code yang dibuat saat 2FA diaktifkan
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+pub struct TwoFactorService;
+
+/// 🔢 Maksimum percobaan kode per token challenge (default 5), setelah itu harus login ulang
+fn mfa_max_attempts() -> i32 {
+    env::var("MFA_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).unwrap_or(5)
+}
+
+impl TwoFactorService {
+
+    /// 🎟️ Terbitkan token challenge setelah password benar dan catat `jti`-nya
+    pub async fn issue_challenge(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, email: &str) -> Result<MfaChallenge, String> {
+        let (mfa_token, claims) = create_mfa_token(auth_usernid, email).map_err(|e| format!("Failed to create MFA token: {}", e))?;
+        let now = Utc::now();
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0).unwrap_or(now);
+
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        conn.execute(
+            r#"DELETE FROM [dbo].[AuthMfaChallenge] WHERE ExpiresAt <= @P1"#, &[&now],
+        ).await.map_err(|e| format!("Failed to cleanup AuthMfaChallenge: {:?}", e))?;
+        conn.execute(
+            r#"INSERT INTO [dbo].[AuthMfaChallenge] ([Jti],[AuthUserNID],[Attempts],[CreatedAt],[ExpiresAt]) VALUES (@P1,@P2,0,@P3,@P4)"#,
+            &[&claims.jti, &auth_usernid, &now, &expires_at],
+        ).await.map_err(|e| format!("Failed to insert AuthMfaChallenge: {:?}", e))?;
+
+        Ok(MfaChallenge {
+            mfa_required: true,
+            mfa_token,
+            expires_in: mfa_challenge_ttl().num_seconds(),
+        })
+    }
+
+    /// 🔢 Pakai satu jatah percobaan dari challenge. `false` = challenge sudah dipakai, habis jatah atau kedaluwarsa
+    pub async fn start_attempt(connection: web::Data<Pool<ConnectionManager>>, challenge: &MfaChallengeClaims) -> Result<bool, String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let done = conn.execute(
+            r#"UPDATE [dbo].[AuthMfaChallenge] SET [Attempts] = Attempts + 1
+            WHERE Jti = @P1 AND AuthUserNID = @P2 AND UsedAt IS NULL AND Attempts < @P3 AND ExpiresAt > @P4"#,
+            &[&challenge.jti, &challenge.auth_usernid, &mfa_max_attempts(), &Utc::now()],
+        ).await.map_err(|e| format!("Failed to update AuthMfaChallenge: {:?}", e))?;
+
+        Ok(done.total() == 1)
+    }
+
+    /// Cek apakah user sudah mengaktifkan 2FA (dipakai oleh login)
+    pub async fn is_enabled(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> Result<bool, String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let row = conn.query(
+            r#"SELECT Enabled FROM AuthUserTotp WHERE AuthUserNID = @P1"#, &[&auth_usernid],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        Ok(row.and_then(|row| row.get::<bool, _>("Enabled")).unwrap_or(false))
+    }
+
+    /// 🔑 Mulai enrollment: buat secret baru (belum aktif sampai diverifikasi)
+    pub async fn enroll(connection: web::Data<Pool<ConnectionManager>>, claims: Claims) -> ActionResult<TotpEnrollment, String> {
+        let mut result: ActionResult<TotpEnrollment, String> = ActionResult::default();
+
+        match Self::is_enabled(connection.clone(), claims.auth_usernid).await {
+            Ok(true) => {
+                result.message = "Two-factor authentication already enabled".to_string();
+                return result;
+            }
+            Ok(false) => {}
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        }
+
+        let secret = generate_secret();
+        let uri = provisioning_uri(&claims.email, &secret);
+        let qr = match qr_code(&uri) {
+            Ok(qr) => qr,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        let sealed_secret = match seal_text(&secret) {
+            Ok(sealed_secret) => sealed_secret,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        match connection.clone().get().await {
+            Ok(mut conn) => {
+                // Enrollment ulang yang belum diverifikasi cukup menimpa secret lama
+                if let Err(err) = conn.execute(
+                    r#"MERGE [dbo].[AuthUserTotp] AS T
+                    USING (SELECT @P1 AS AuthUserNID) AS S ON T.AuthUserNID = S.AuthUserNID
+                    WHEN MATCHED THEN UPDATE SET [Secret] = @P2, [Enabled] = 0, [LastUsedStep] = NULL, [CreatedAt] = @P3, [VerifiedAt] = NULL
+                    WHEN NOT MATCHED THEN INSERT ([AuthUserNID],[Secret],[Enabled],[CreatedAt]) VALUES (@P1,@P2,0,@P3);"#,
+                    &[&claims.auth_usernid, &sealed_secret, &Utc::now()],
+                ).await {
+                    result.error = format!("Failed to save AuthUserTotp: {:?}", err).into();
+                    return result;
+                }
+
+                result.result = true;
+                result.message = "Scan the QR code and verify with the generated code".to_string();
+                result.data = Some(TotpEnrollment { secret, provisioning_uri: uri, qr_code: qr });
+                result
+            }
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                result
+            }
+        }
+    }
+
+    /// ✅ Verifikasi kode pertama, aktifkan 2FA dan kembalikan recovery code (hanya ditampilkan sekali)
+    pub async fn verify_enrollment(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32, code: String) -> ActionResult<Vec<String>, String> {
+        let mut result: ActionResult<Vec<String>, String> = ActionResult::default();
+
+        let (secret, enabled, last_used_step) = match Self::load_secret(connection.clone(), auth_usernid).await {
+            Ok(Some(totp)) => totp,
+            Ok(None) => {
+                result.message = "Two-factor enrollment not started".to_string();
+                return result;
+            }
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        if enabled {
+            result.message = "Two-factor authentication already enabled".to_string();
+            return result;
+        }
+
+        let step = match verify_code(&secret, &code, last_used_step) {
+            Some(step) => step,
+            None => {
+                result.message = "Invalid two-factor code".to_string();
+                return result;
+            }
+        };
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| GenericService::random_string(RECOVERY_CODE_LENGTH))
+            .collect();
+
+        match Transaction::begin(&connection).await {
+            Ok(trans) => {
+                let applied = 'tx: {
+                    match trans.conn.lock().await.as_mut() {
+                        Some(conn) => {
+                            let now = Utc::now();
+
+                            if let Err(err) = conn.execute(
+                                r#"UPDATE [dbo].[AuthUserTotp] SET [Enabled] = 1, [VerifiedAt] = @P1, [LastUsedStep] = @P2 WHERE AuthUserNID = @P3"#,
+                                &[&now, &step, &auth_usernid],
+                            ).await {
+                                result.error = Some(format!("Failed to update AuthUserTotp: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            if let Err(err) = conn.execute(
+                                r#"DELETE FROM [dbo].[AuthRecoveryCode] WHERE AuthUserNID = @P1"#, &[&auth_usernid],
+                            ).await {
+                                result.error = Some(format!("Failed to delete AuthRecoveryCode: {:?}", err));
+                                break 'tx None;
+                            }
+
+                            for code in recovery_codes.iter() {
+                                if let Err(err) = conn.execute(
+                                    r#"INSERT INTO [dbo].[AuthRecoveryCode] ([AuthUserNID],[CodeHash],[CreatedAt]) VALUES (@P1,@P2,@P3)"#,
+                                    &[&auth_usernid, &hash_token(code), &now],
+                                ).await {
+                                    result.error = Some(format!("Failed to insert AuthRecoveryCode: {:?}", err));
+                                    break 'tx None;
+                                }
+                            }
+                            Some(())
+                        }
+                        None => {
+                            result.error = Some("Failed to get database connection".into());
+                            break 'tx None;
+                        }
+                    }
+                };
+
+                // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+                if applied.is_none() {
+                    if let Err(err) = trans.rollback().await {
+                        write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+                    }
+                    return result;
+                }
+
+                // 🔵 Commit transaksi
+                if let Err(err) = trans.commit().await {
+                    result.error = Some(format!("Failed to commit transaction: {:?}", err));
+                    return result;
+                }
+            }
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        }
+
+        result.result = true;
+        result.message = "Two-factor authentication enabled".to_string();
+        result.data = Some(recovery_codes);
+        result
+    }
+
+    /// 🔐 Langkah kedua login: terima kode TOTP atau recovery code, lalu kembalikan user dengan `mfa = true`
+    pub async fn verify_login(connection: web::Data<Pool<ConnectionManager>>, challenge: &MfaChallengeClaims, code: String) -> ActionResult<WebUser, String> {
+        let mut result: ActionResult<WebUser, String> = ActionResult::default();
+        let auth_usernid = challenge.auth_usernid;
+
+        let (secret, last_used_step) = match Self::load_secret(connection.clone(), auth_usernid).await {
+            Ok(Some((secret, true, last_used_step))) => (secret, last_used_step),
+            Ok(_) => {
+                result.message = "Two-factor authentication not enabled".to_string();
+                return result;
+            }
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        };
+
+        let trans = match Transaction::begin(&connection).await {
+            Ok(trans) => trans,
+            Err(err) => {
+                result.error = Some(format!("Failed to start transaction: {:?}", err));
+                return result;
+            }
+        };
+
+        let applied = 'tx: {
+            match trans.conn.lock().await.as_mut() {
+                Some(conn) => {
+                    // 🎟️ Challenge sekali pakai diklaim dulu: request paralel dengan token yang sama menunggu lock baris ini,
+                    // jadi kode TOTP / recovery code tidak terpakai oleh request yang akhirnya ditolak
+                    match conn.execute(
+                        r#"UPDATE [dbo].[AuthMfaChallenge] SET [UsedAt] = @P2 WHERE Jti = @P1 AND UsedAt IS NULL"#,
+                        &[&challenge.jti, &Utc::now()],
+                    ).await.map(|done| done.total()) {
+                        Ok(1) => {}
+                        Ok(_) => {
+                            result.message = "Two-factor challenge already used, please login again".to_string();
+                            break 'tx None;
+                        }
+                        Err(err) => {
+                            result.error = Some(format!("Failed to update AuthMfaChallenge: {:?}", err));
+                            break 'tx None;
+                        }
+                    }
+
+                    let accepted = match verify_code(&secret, &code, last_used_step) {
+                        // Simpan step terakhir supaya kode yang sama tidak bisa di-replay
+                        Some(step) => Self::update_last_used_step(conn, auth_usernid, step, last_used_step).await,
+                        None => Self::use_recovery_code(conn, auth_usernid, &code).await,
+                    };
+
+                    // Kode salah → rollback juga melepas klaim challenge, user masih bisa mencoba lagi
+                    match accepted {
+                        Ok(true) => Some(()),
+                        Ok(false) => {
+                            result.message = "Invalid two-factor code".to_string();
+                            break 'tx None;
+                        }
+                        Err(err) => {
+                            result.error = Some(err);
+                            break 'tx None;
+                        }
+                    }
+                }
+                None => {
+                    result.error = Some("Failed to get database connection".into());
+                    break 'tx None;
+                }
+            }
+        };
+
+        // 🔙 Jalur gagal: rollback eksplisit, jangan andalkan `Drop`
+        if applied.is_none() {
+            if let Err(err) = trans.rollback().await {
+                write_log("ERROR", format!("Failed to rollback transaction: {:?}", err).as_str());
+            }
+            return result;
+        }
+
+        if let Err(err) = trans.commit().await {
+            result.error = Some(format!("Failed to commit transaction: {:?}", err));
+            return result;
+        }
+
+        let mut conn = match connection.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                result.error = format!("Internal Server error: {:?}", err).into();
+                return result;
+            }
+        };
+
+        match AuthService::get_web_user(&mut conn, auth_usernid).await {
+            Ok(Some(mut user)) => {
+                user.mfa = true;
+                result.result = true;
+                result.message = format!("Welcome {}", user.email);
+                result.data = Some(user);
+            }
+            Ok(None) => result.message = "No user found".to_string(),
+            Err(err) => result.error = Some(err),
+        }
+
+        result
+    }
+
+    /// Ambil (secret terdekripsi, enabled, last_used_step)
+    async fn load_secret(connection: web::Data<Pool<ConnectionManager>>, auth_usernid: i32) -> Result<Option<(String, bool, Option<i64>)>, String> {
+        let mut conn = connection.get().await.map_err(|e| format!("Internal Server error: {:?}", e))?;
+        let row = conn.query(
+            r#"SELECT Secret, Enabled, LastUsedStep FROM AuthUserTotp WHERE AuthUserNID = @P1"#, &[&auth_usernid],
+        ).await.map_err(|e| format!("Query execution failed: {:?}", e))?
+            .into_row().await.map_err(|e| format!("Query execution failed: {:?}", e))?;
+
+        match row {
+            Some(row) => {
+                let sealed = row.get::<&str, _>("Secret").unwrap_or_default();
+                let secret = open_text(sealed)?;
+
+                // 🔁 Format lama / kunci lama → enkripsi ulang dengan kunci aktif
+                if needs_reseal(sealed) {
+                    conn.execute(
+                        r#"UPDATE [dbo].[AuthUserTotp] SET [Secret] = @P2 WHERE AuthUserNID = @P1 AND Secret = @P3"#,
+                        &[&auth_usernid, &seal_text(&secret)?, &sealed],
+                    ).await.map_err(|e| format!("Failed to update AuthUserTotp: {:?}", e))?;
+                }
+
+                Ok(Some((secret, row.get("Enabled").unwrap_or(false), row.get("LastUsedStep"))))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Update atomik: gagal jika request lain sudah memakai step yang sama / lebih baru
+    async fn update_last_used_step(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, step: i64, last_used_step: Option<i64>) -> Result<bool, String> {
+        let done = conn.execute(
+            r#"UPDATE [dbo].[AuthUserTotp] SET [LastUsedStep] = @P1
+            WHERE AuthUserNID = @P2 AND (LastUsedStep IS NULL OR LastUsedStep = @P3)"#,
+            &[&step, &auth_usernid, &last_used_step],
+        ).await.map_err(|e| format!("Failed to update AuthUserTotp: {:?}", e))?;
+
+        Ok(done.total() == 1)
+    }
+
+    /// Recovery code hanya bisa dipakai sekali
+    async fn use_recovery_code(conn: &mut PooledConnection<'_, ConnectionManager>, auth_usernid: i32, code: &str) -> Result<bool, String> {
+        let done = conn.execute(
+            r#"UPDATE [dbo].[AuthRecoveryCode] SET [UsedAt] = @P1
+            WHERE AuthUserNID = @P2 AND CodeHash = @P3 AND UsedAt IS NULL"#,
+            &[&Utc::now(), &auth_usernid, &hash_token(&code.trim().to_uppercase())],
+        ).await.map_err(|e| format!("Failed to update AuthRecoveryCode: {:?}", e))?;
+
+        Ok(done.total() == 1)
+    }
+}
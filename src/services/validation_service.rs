@@ -5,6 +5,7 @@ pub mod validator {
     use image::ImageFormat;
     use regex::Regex;
     use validator::{ValidationError, ValidationErrors};
+    use crate::contexts::password_policy::PasswordPolicy;
 
     pub fn required(value: &str) -> Result<(), ValidationError> {
         if value.trim().is_empty() {
@@ -45,13 +46,17 @@ pub mod validator {
         Ok(())
     }
 
+    /// Aturan statis dari `PasswordPolicy` (panjang, jenis karakter, daftar bocor).
+    /// History & umur password dicek terpisah karena butuh database
     pub fn valid_password(value: &str) -> Result<(), ValidationError> {
-        let password_regex = Regex::new(r"^(?=.*[A-Za-z])(?=.*\d)[A-Za-z\d]{8,}$")
-            .map_err(|_| ValidationError::new("invalid_regex"))?;
+        let violations = PasswordPolicy::from_env().check(value);
 
-        if !password_regex.is_match(value) {
+        if !violations.is_empty() {
             let mut error = ValidationError::new("invalid_password");
-            error.message = Some("Required character number and text".into());
+            error.message = Some(violations.iter().map(|v| v.message.as_str()).collect::<Vec<_>>().join(", ").into());
+            for violation in violations {
+                error.add_param(violation.rule.into(), &true);
+            }
             return Err(error);
         }
         Ok(())